@@ -53,8 +53,7 @@ pub async fn get_bot_token(
     redis: &mut Connection,
 ) -> Result<String, Error> {
     let token = cache::get::<String>(&format!("bot:{}:token", bot), redis).await?;
-    if token.is_some() {
-        let token = token.unwrap();
+    if let Some(token) = token {
         return Ok(token);
     }
 
@@ -69,7 +68,7 @@ pub async fn get_token_info(
     token: &str,
     redis: &mut Connection,
 ) -> Result<Option<TokenInfo>, Error> {
-    return Ok(cache::get_as::<TokenInfo>(&format!("token:{}", &token), redis).await?);
+    return cache::get_as::<TokenInfo>(&format!("token:{}", &token), redis).await;
 }
 
 /**
@@ -125,7 +124,7 @@ pub async fn refresh_bot_token(
     cache::set_with_ttl(
         &format!("bot:{}:token", bot),
         &res.access_token,
-        (&res.expires_in - 60 * 20) as usize,
+        (res.expires_in - 60 * 20) as usize,
         redis,
     )
     .await?;
@@ -141,7 +140,7 @@ pub async fn refresh_bot_token(
     cache::set_with_ttl(
         &format!("token:{}", &res.access_token),
         &token_info,
-        (&res.expires_in - 60 * 20) as usize,
+        (res.expires_in - 60 * 20) as usize,
         redis,
     )
     .await?;
@@ -205,7 +204,7 @@ pub async fn validate_token(token: &str, redis: &mut Connection) -> Result<(), E
     let res = match res {
         Ok(res) => res,
         Err(_) => {
-            crate::token::delete_token(&token, redis).await?;
+            crate::token::delete_token(token, redis).await?;
             return Err(anyhow::anyhow!("Request error"));
         }
     };
@@ -213,7 +212,7 @@ pub async fn validate_token(token: &str, redis: &mut Connection) -> Result<(), E
     return match res.status() {
         reqwest::StatusCode::OK => Ok(()),
         _ => {
-            crate::token::delete_token(&token, redis).await?;
+            crate::token::delete_token(token, redis).await?;
             Err(anyhow::anyhow!("Invalid token"))
         }
     };
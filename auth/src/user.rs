@@ -1,5 +1,6 @@
 use database::sea_orm::DatabaseConnection;
 use anyhow::{Result, Error};
+use redis::aio::Connection;
 
 /**
  * Delete a user
@@ -10,10 +11,14 @@ pub async fn delete_user(user_id: i32, db: &DatabaseConnection) -> Result<(), Er
 }
 
 /**
- * Deactivate a user also deactivates the channel
+ * Deactivate a user also deactivates the channel and makes the bots leave it
  */
-pub async fn deactivate_user(user_id: i32, db: &DatabaseConnection) -> Result<(), Error> {
+pub async fn deactivate_user(
+    user_id: i32,
+    db: &DatabaseConnection,
+    redis: &mut Connection,
+) -> Result<(), Error> {
     database::handler::user::deactivate_user(user_id, db).await?;
-    database::handler::channel::deactivate_channel(user_id, db).await?;
+    database::handler::channel::deactivate_channel(user_id, db, redis).await?;
     return Ok(());
 }
@@ -0,0 +1,188 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::{BanShareMode, ModerationActionType, SharedBanStatus};
+use database::entity::shared_ban as shared_ban_entity;
+use database::entity::user as user_entity;
+use database::sea_orm::ActiveValue;
+use parser::clearchat_tag::ClearChatTags;
+use parser::irc_parser::ParsedMessage;
+
+use crate::context::BotContext;
+use crate::enforcement::{moderate, Target};
+
+/**
+ * The user a CLEARCHAT banned, None for a timeout or a cleared chat
+ */
+pub fn banned_user(tags: &ClearChatTags) -> Option<i32> {
+    return match (tags.target_user_id, tags.ban_duration) {
+        (Some(x), None) => Some(x),
+        _ => None,
+    };
+}
+
+/**
+ * Share a ban in a channel of a ban group with the other channels of the
+ * group, channels that take the bans of the group get them right away and the
+ * others queue them for their moderators, timeouts are not shared
+ */
+pub async fn handle_clearchat(msg: &ParsedMessage, ctx: &BotContext) -> Result<(), Error> {
+    let tags = match msg.clearchat_tags() {
+        Some(x) => x,
+        None => return Err(Error::msg("No tags")),
+    };
+    let user_id = match banned_user(&tags) {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let channel_id = tags.room_id;
+
+    let member =
+        match database::handler::ban_group::get_ban_group_member(channel_id, &ctx.db).await? {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+    // the echo of a ban the group put here, sharing it again would loop, the
+    // echo may arrive before twitch answered the ban so it is still pending
+    let shared = database::handler::ban_group::get_shared_bans(
+        channel_id,
+        user_id,
+        &[SharedBanStatus::Pending, SharedBanStatus::Applied],
+        &ctx.db,
+    )
+    .await?;
+    if !shared.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(nick) = &msg.params {
+        database::handler::user::create_user(
+            user_entity::ActiveModel {
+                id: ActiveValue::Set(user_id),
+                nick: ActiveValue::Set(String::from(nick)),
+                display_name: ActiveValue::Set(String::from(nick)),
+                ..Default::default()
+            },
+            &ctx.db,
+        )
+        .await?;
+    }
+    let source = match database::handler::user::get_user(channel_id, &ctx.db).await? {
+        Some(x) => x.nick,
+        None => channel_id.to_string(),
+    };
+    let reason = format!("shared ban from {}", source);
+
+    let members =
+        database::handler::ban_group::get_ban_group_members(&member.group_id, &ctx.db).await?;
+    for member in members.iter().filter(|x| x.channel_id != channel_id) {
+        let open = database::handler::ban_group::get_shared_bans(
+            member.channel_id,
+            user_id,
+            &[SharedBanStatus::Pending, SharedBanStatus::Applied],
+            &ctx.db,
+        )
+        .await?;
+        if !open.is_empty() {
+            continue;
+        }
+
+        let shared_ban = database::handler::ban_group::create_shared_ban(
+            &member.group_id,
+            member.channel_id,
+            channel_id,
+            user_id,
+            Some(String::from(&reason)),
+            &ctx.db,
+        )
+        .await?;
+        match member.mode {
+            BanShareMode::Auto => {
+                apply_shared_ban(&shared_ban, None, ctx).await?;
+            }
+            BanShareMode::Approval => println!(
+                "Shared ban of {} from {} waits for approval in {}",
+                user_id, source, member.channel_id
+            ),
+        }
+    }
+
+    return Ok(());
+}
+
+/**
+ * Ban the user of a shared ban in its channel, a ban twitch refuses is
+ * recorded as failed so it does not stop the other channels
+ */
+pub async fn apply_shared_ban(
+    shared_ban: &shared_ban_entity::Model,
+    moderator_id: Option<i32>,
+    ctx: &BotContext,
+) -> Result<SharedBanStatus, Error> {
+    let target = Target {
+        channel_id: shared_ban.channel_id,
+        user_id: shared_ban.user_id,
+        nick: None,
+        display_name: None,
+        msg_id: None,
+    };
+    let reason = shared_ban.reason.as_deref().unwrap_or("shared ban");
+    // a ban taken without a moderator is on behalf of the channel it came
+    // from, it is not automated here and gives no strike
+    let acting = moderator_id.unwrap_or(shared_ban.source_channel_id);
+
+    let status = match moderate(
+        &target,
+        ModerationActionType::Ban,
+        None,
+        reason,
+        Some(acting),
+        ctx,
+    )
+    .await
+    {
+        Ok(_) => SharedBanStatus::Applied,
+        Err(e) => {
+            println!("Failed to apply shared ban {}: {:?}", shared_ban.id, e);
+            SharedBanStatus::Failed
+        }
+    };
+    database::handler::ban_group::set_shared_ban_status(
+        &shared_ban.id,
+        status,
+        moderator_id,
+        &ctx.db,
+    )
+    .await?;
+
+    return Ok(status);
+}
+
+/**
+ * Revert a shared ban, an applied ban is lifted in its channel and a pending
+ * one is not applied anymore
+ */
+pub async fn undo_shared_ban(
+    shared_ban: &shared_ban_entity::Model,
+    moderator_id: i32,
+    ctx: &BotContext,
+) -> Result<(), Error> {
+    if shared_ban.status == SharedBanStatus::Applied {
+        let token = ctx.bot_token().await?;
+        twitch_api::unban_user(
+            &ctx.twitch.client_id,
+            &token,
+            ctx.bot_id,
+            shared_ban.channel_id,
+            shared_ban.user_id,
+        )
+        .await?;
+    }
+    database::handler::ban_group::set_shared_ban_status(
+        &shared_ban.id,
+        SharedBanStatus::Undone,
+        Some(moderator_id),
+        &ctx.db,
+    )
+    .await?;
+
+    return Ok(());
+}
@@ -0,0 +1,268 @@
+use anyhow::{Error, Result};
+use database::entity::banned_phrase as banned_phrase_entity;
+use database::entity::sea_orm_active_enums::{BannedPhraseAction, BannedPhraseType, Permission};
+use database::sea_orm::ActiveValue;
+use serde_json::json;
+
+use crate::builtins::audit;
+use crate::builtins::management::join_limited;
+use crate::context::{BotContext, Invocation};
+use crate::permission::has_permission;
+use crate::phrases::{build_regex, normalize_text};
+
+const DEFAULT_TIMEOUT: i32 = 600;
+const MAX_TIMEOUT: i32 = 14 * 24 * 60 * 60;
+const MAX_PHRASE_LENGTH: usize = 255;
+const MAX_LIST_LENGTH: usize = 400;
+const USAGE: &str = "Usage: !banphrase add [-wildcard|-regex] [-delete|-timeout=600|-ban] [-dryrun] phrase, !banphrase del phrase, !banphrase dryrun on|off phrase, !banphrase test text or !banphrase list";
+
+/**
+ * A banned phrase as given to !banphrase add
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannedPhraseOptions {
+    pub phrase: String,
+    pub match_type: BannedPhraseType,
+    pub action: BannedPhraseAction,
+    pub timeout: i32,
+    pub dry_run: bool,
+}
+
+/**
+ * Parse `[-wildcard|-regex] [-delete|-timeout=seconds|-ban] [-dryrun] phrase`,
+ * the options come before the phrase, the errors are meant to be shown in chat
+ */
+pub fn parse_banned_phrase(args: &[String]) -> Result<BannedPhraseOptions, String> {
+    let mut options = BannedPhraseOptions {
+        phrase: String::new(),
+        match_type: BannedPhraseType::Term,
+        action: BannedPhraseAction::Delete,
+        timeout: DEFAULT_TIMEOUT,
+        dry_run: false,
+    };
+
+    let mut args = args.iter().filter(|x| !x.is_empty()).peekable();
+    while let Some(flag) = args.peek().and_then(|x| x.strip_prefix('-')) {
+        let flag = flag.to_lowercase();
+        match flag.split_once('=') {
+            None if flag == "wildcard" => options.match_type = BannedPhraseType::Wildcard,
+            None if flag == "regex" => options.match_type = BannedPhraseType::Regex,
+            None if flag == "delete" => options.action = BannedPhraseAction::Delete,
+            None if flag == "ban" => options.action = BannedPhraseAction::Ban,
+            None if flag == "dryrun" => options.dry_run = true,
+            Some(("timeout", x)) => match x.parse::<i32>() {
+                Ok(x) if (1..=MAX_TIMEOUT).contains(&x) => {
+                    options.action = BannedPhraseAction::Timeout;
+                    options.timeout = x;
+                }
+                _ => return Err(format!("-timeout needs seconds up to {}", MAX_TIMEOUT)),
+            },
+            _ => break,
+        }
+        args.next();
+    }
+
+    options.phrase = args.map(|x| x.as_str()).collect::<Vec<&str>>().join(" ");
+    if options.phrase.is_empty() {
+        return Err(String::from(USAGE));
+    }
+    if options.phrase.len() > MAX_PHRASE_LENGTH {
+        return Err(format!("A phrase has at most {} characters", MAX_PHRASE_LENGTH));
+    }
+
+    return Ok(options);
+}
+
+fn describe_action(phrase: &banned_phrase_entity::Model) -> String {
+    let action = match phrase.action {
+        BannedPhraseAction::Delete => String::from("delete"),
+        BannedPhraseAction::Timeout => format!("timeout {}s", phrase.timeout),
+        BannedPhraseAction::Ban => String::from("ban"),
+    };
+
+    return match phrase.dry_run == 1 {
+        true => format!("{}, dry run", action),
+        false => action,
+    };
+}
+
+/**
+ * !banphrase add|del|dryrun|test|list, moderators only
+ */
+pub async fn handle_banphrase(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let mut args = invocation.args.iter().filter(|x| !x.is_empty());
+    let action = args.next().map(|x| x.to_lowercase());
+    let rest = args.cloned().collect::<Vec<String>>();
+
+    return match action.as_deref() {
+        Some("add") => add_phrase(&rest, invocation, ctx).await,
+        Some("del" | "delete" | "remove") => delete_phrase(&rest.join(" "), invocation, ctx).await,
+        Some("dryrun") => dry_run(&rest, invocation, ctx).await,
+        Some("test") => test_phrases(&rest.join(" "), invocation, ctx).await,
+        Some("list") => list_phrases(invocation, ctx).await,
+        _ => ctx.reply(invocation, USAGE).await,
+    };
+}
+
+async fn add_phrase(args: &[String], invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let options = match parse_banned_phrase(args) {
+        Ok(x) => x,
+        Err(e) => return ctx.reply(invocation, &e).await,
+    };
+    let channel_id = invocation.tags.room_id;
+
+    if database::handler::banned_phrase::get_banned_phrase(channel_id, &options.phrase, &ctx.db)
+        .await?
+        .is_some()
+    {
+        return ctx
+            .reply(invocation, "That phrase is banned already")
+            .await;
+    }
+
+    if let Err(e) = build_regex(&options.phrase, options.match_type) {
+        println!("Invalid banned phrase {}: {:?}", options.phrase, e);
+        return ctx.reply(invocation, "That is not a valid regex").await;
+    }
+
+    let phrase = banned_phrase_entity::ActiveModel {
+        channel_id: ActiveValue::Set(channel_id),
+        phrase: ActiveValue::Set(String::from(&options.phrase)),
+        match_type: ActiveValue::Set(options.match_type),
+        action: ActiveValue::Set(options.action),
+        timeout: ActiveValue::Set(options.timeout),
+        dry_run: ActiveValue::Set(options.dry_run as i8),
+        match_count: ActiveValue::Set(0),
+        last_matched: ActiveValue::Set(None),
+        ..Default::default()
+    };
+    let phrase = database::handler::banned_phrase::create_banned_phrase(phrase, &ctx.db).await?;
+    ctx.banned_phrases.forget(channel_id).await;
+    audit(
+        invocation,
+        "banphrase.add",
+        &phrase.phrase,
+        json!({
+            "match_type": format!("{:?}", phrase.match_type),
+            "action": format!("{:?}", phrase.action),
+            "timeout": phrase.timeout,
+            "dry_run": options.dry_run,
+        }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(
+            invocation,
+            &format!("Banned phrase added ({})", describe_action(&phrase)),
+        )
+        .await;
+}
+
+async fn delete_phrase(phrase: &str, invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let phrase =
+        match database::handler::banned_phrase::get_banned_phrase(channel_id, phrase, &ctx.db)
+            .await?
+        {
+            Some(x) => x,
+            None => return ctx.reply(invocation, "That phrase is not banned").await,
+        };
+
+    database::handler::banned_phrase::delete_banned_phrase(&phrase.id, &ctx.db).await?;
+    ctx.banned_phrases.forget(channel_id).await;
+    audit(
+        invocation,
+        "banphrase.delete",
+        &phrase.phrase,
+        json!({ "before": { "action": describe_action(&phrase), "match_count": phrase.match_count } }),
+        ctx,
+    )
+    .await?;
+
+    return ctx.reply(invocation, "Banned phrase deleted").await;
+}
+
+async fn dry_run(args: &[String], invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let (enabled, phrase) = match args.split_first() {
+        Some((x, phrase)) if x == "on" => (true, phrase.join(" ")),
+        Some((x, phrase)) if x == "off" => (false, phrase.join(" ")),
+        _ => return ctx.reply(invocation, "Usage: !banphrase dryrun on|off phrase").await,
+    };
+    let channel_id = invocation.tags.room_id;
+    let phrase =
+        match database::handler::banned_phrase::get_banned_phrase(channel_id, &phrase, &ctx.db)
+            .await?
+        {
+            Some(x) => x,
+            None => return ctx.reply(invocation, "That phrase is not banned").await,
+        };
+
+    database::handler::banned_phrase::set_dry_run(&phrase.id, enabled, &ctx.db).await?;
+    ctx.banned_phrases.forget(channel_id).await;
+    audit(
+        invocation,
+        "banphrase.dryrun",
+        &phrase.phrase,
+        json!({ "dry_run": enabled }),
+        ctx,
+    )
+    .await?;
+
+    let text = match enabled {
+        true => "Matches of that phrase are only logged now",
+        false => "That phrase is enforced now",
+    };
+    return ctx.reply(invocation, text).await;
+}
+
+async fn test_phrases(text: &str, invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if text.is_empty() {
+        return ctx.reply(invocation, "Usage: !banphrase test text").await;
+    }
+
+    let phrases = ctx
+        .banned_phrases
+        .get(invocation.tags.room_id, &ctx.db)
+        .await?;
+    let normalized = normalize_text(text);
+    let matches = phrases
+        .iter()
+        .filter(|x| x.matches(&normalized))
+        .map(|x| format!("{} ({})", x.model.phrase, describe_action(&x.model)))
+        .collect::<Vec<String>>();
+
+    let result = match matches.is_empty() {
+        true => String::from("no banned phrase matches"),
+        false => format!("matches {}", join_limited(&matches, MAX_LIST_LENGTH)),
+    };
+    return ctx
+        .reply(invocation, &format!("\"{}\" {}", normalized, result))
+        .await;
+}
+
+async fn list_phrases(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let phrases =
+        database::handler::banned_phrase::get_banned_phrases(invocation.tags.room_id, &ctx.db)
+            .await?;
+    if phrases.is_empty() {
+        return ctx.reply(invocation, "There are no banned phrases").await;
+    }
+
+    let phrases = phrases
+        .iter()
+        .map(|x| format!("{} ({}, {} matches)", x.phrase, describe_action(x), x.match_count))
+        .collect::<Vec<String>>();
+
+    return ctx
+        .reply(
+            invocation,
+            &format!("Banned phrases: {}", join_limited(&phrases, MAX_LIST_LENGTH)),
+        )
+        .await;
+}
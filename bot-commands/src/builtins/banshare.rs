@@ -0,0 +1,228 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::{Permission, SharedBanStatus};
+use database::entity::user as user_entity;
+use serde_json::json;
+
+use crate::bans::{apply_shared_ban, undo_shared_ban};
+use crate::builtins::audit;
+use crate::builtins::management::join_limited;
+use crate::context::{BotContext, Invocation};
+use crate::permission::has_permission;
+
+const MAX_LIST_LENGTH: usize = 400;
+const USAGE: &str = "Usage: !banshare pending, !banshare approve user, !banshare reject user or !banshare undo user";
+
+/**
+ * !banshare pending|approve|reject|undo, moderators only
+ */
+pub async fn handle_banshare(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let mut args = invocation.args.iter().filter(|x| !x.is_empty());
+    let action = args.next().map(|x| x.to_lowercase());
+    let nick = args
+        .next()
+        .map(|x| x.trim_start_matches('@').to_lowercase());
+
+    return match (action.as_deref(), nick) {
+        (Some("pending"), _) => list_pending(invocation, ctx).await,
+        (Some("approve"), Some(nick)) => approve(&nick, invocation, ctx).await,
+        (Some("reject"), Some(nick)) => reject(&nick, invocation, ctx).await,
+        (Some("undo"), Some(nick)) => undo(&nick, invocation, ctx).await,
+        _ => ctx.reply(invocation, USAGE).await,
+    };
+}
+
+async fn nick_of(user_id: i32, ctx: &BotContext) -> Result<String, Error> {
+    let nick = match database::handler::user::get_user(user_id, &ctx.db).await? {
+        Some(x) => x.nick,
+        None => user_id.to_string(),
+    };
+
+    return Ok(nick);
+}
+
+async fn find_user(
+    nick: &str,
+    invocation: &Invocation,
+    ctx: &BotContext,
+) -> Result<Option<user_entity::Model>, Error> {
+    let user = database::handler::user::get_user_by_nick(nick, &ctx.db).await?;
+    if user.is_none() {
+        ctx.reply(invocation, "I have never seen that user").await?;
+    }
+
+    return Ok(user);
+}
+
+async fn list_pending(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let pending =
+        database::handler::ban_group::get_pending_shared_bans(invocation.tags.room_id, &ctx.db)
+            .await?;
+    if pending.is_empty() {
+        return ctx
+            .reply(invocation, "No shared bans wait for approval")
+            .await;
+    }
+
+    let mut bans = Vec::new();
+    for shared_ban in pending.iter() {
+        let user = nick_of(shared_ban.user_id, ctx).await?;
+        let source = nick_of(shared_ban.source_channel_id, ctx).await?;
+        bans.push(format!("{} (from {})", user, source));
+    }
+
+    return ctx
+        .reply(
+            invocation,
+            &format!(
+                "Waiting for approval: {}",
+                join_limited(&bans, MAX_LIST_LENGTH)
+            ),
+        )
+        .await;
+}
+
+async fn approve(nick: &str, invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let user = match find_user(nick, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let pending = database::handler::ban_group::get_shared_bans(
+        invocation.tags.room_id,
+        user.id,
+        &[SharedBanStatus::Pending],
+        &ctx.db,
+    )
+    .await?;
+    let shared_ban = match pending.first() {
+        Some(x) => x,
+        None => {
+            return ctx
+                .reply(invocation, "No shared ban of that user waits for approval")
+                .await
+        }
+    };
+
+    let status = apply_shared_ban(shared_ban, Some(invocation.tags.user_id), ctx).await?;
+    audit(
+        invocation,
+        "banshare.approve",
+        &user.nick,
+        json!({ "shared_ban": shared_ban.id, "status": format!("{:?}", status) }),
+        ctx,
+    )
+    .await?;
+
+    let text = match status {
+        SharedBanStatus::Applied => format!("Banned {}", user.display_name),
+        _ => format!("Twitch did not let me ban {}", user.display_name),
+    };
+    return ctx.reply(invocation, &text).await;
+}
+
+async fn reject(nick: &str, invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let user = match find_user(nick, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let pending = database::handler::ban_group::get_shared_bans(
+        invocation.tags.room_id,
+        user.id,
+        &[SharedBanStatus::Pending],
+        &ctx.db,
+    )
+    .await?;
+    if pending.is_empty() {
+        return ctx
+            .reply(invocation, "No shared ban of that user waits for approval")
+            .await;
+    }
+
+    for shared_ban in pending.iter() {
+        database::handler::ban_group::set_shared_ban_status(
+            &shared_ban.id,
+            SharedBanStatus::Rejected,
+            Some(invocation.tags.user_id),
+            &ctx.db,
+        )
+        .await?;
+    }
+    audit(
+        invocation,
+        "banshare.reject",
+        &user.nick,
+        json!({ "shared_bans": pending.iter().map(|x| &x.id).collect::<Vec<&String>>() }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(
+            invocation,
+            &format!("{} will not be banned here", user.display_name),
+        )
+        .await;
+}
+
+/**
+ * Revert the shared bans of a user that this channel started or received
+ */
+async fn undo(nick: &str, invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let user = match find_user(nick, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let open = database::handler::ban_group::get_open_shared_bans(
+        invocation.tags.room_id,
+        user.id,
+        &ctx.db,
+    )
+    .await?;
+    if open.is_empty() {
+        return ctx
+            .reply(invocation, "That user has no shared bans to undo")
+            .await;
+    }
+
+    let mut undone = Vec::new();
+    for shared_ban in open.iter() {
+        match undo_shared_ban(shared_ban, invocation.tags.user_id, ctx).await {
+            Ok(_) => undone.push(shared_ban),
+            Err(e) => println!("Failed to undo shared ban {}: {:?}", shared_ban.id, e),
+        }
+    }
+    audit(
+        invocation,
+        "banshare.undo",
+        &user.nick,
+        json!({
+            "undone": undone.iter().map(|x| &x.id).collect::<Vec<&String>>(),
+            "failed": open.len() - undone.len(),
+        }),
+        ctx,
+    )
+    .await?;
+
+    let mut channels = Vec::new();
+    for shared_ban in undone.iter() {
+        channels.push(nick_of(shared_ban.channel_id, ctx).await?);
+    }
+    let text = match (channels.is_empty(), open.len() - undone.len()) {
+        (true, _) => String::from("Twitch did not let me undo the shared bans"),
+        (false, 0) => format!(
+            "Undid the shared bans of {} in {}",
+            user.display_name,
+            join_limited(&channels, MAX_LIST_LENGTH)
+        ),
+        (false, x) => format!(
+            "Undid the shared bans of {} in {}, {} failed",
+            user.display_name,
+            join_limited(&channels, MAX_LIST_LENGTH),
+            x
+        ),
+    };
+    return ctx.reply(invocation, &text).await;
+}
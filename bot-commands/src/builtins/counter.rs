@@ -0,0 +1,220 @@
+use anyhow::{Error, Result};
+use database::entity::counter as counter_entity;
+use database::entity::sea_orm_active_enums::Permission;
+use database::handler::counter::CounterChange;
+use database::sea_orm::ActiveValue;
+use serde_json::json;
+
+use crate::builtins::management::{join_limited, normalize_name};
+use crate::builtins::{audit, is_builtin};
+use crate::context::{BotContext, Invocation};
+use crate::permission::has_permission;
+
+const MAX_LIST_LENGTH: usize = 450;
+
+/**
+ * What a counter command asks for
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterAction {
+    Show,
+    Change(CounterChange),
+}
+
+/**
+ * Parse `!name`, `!name+ [amount]`, `!name- [amount]`, `!name set value` and
+ * `!name reset` into the counter name and the action
+ */
+pub fn parse_counter_command(command: &str, args: &[String]) -> Option<(String, CounterAction)> {
+    let mut args = args.iter().filter(|x| !x.is_empty());
+    let amount = |x: Option<&String>| match x {
+        Some(x) => x.parse::<i32>().ok(),
+        None => Some(1),
+    };
+
+    if let Some(name) = command.strip_suffix('+') {
+        let amount = amount(args.next())?;
+        return Some((String::from(name), CounterAction::Change(CounterChange::Add(amount))));
+    }
+    if let Some(name) = command.strip_suffix('-') {
+        let amount = amount(args.next())?.checked_neg()?;
+        return Some((String::from(name), CounterAction::Change(CounterChange::Add(amount))));
+    }
+
+    let action = match args.next().map(|x| x.to_lowercase()).as_deref() {
+        Some("set") => CounterAction::Change(CounterChange::Set(args.next()?.parse::<i32>().ok()?)),
+        Some("reset") => CounterAction::Change(CounterChange::Set(0)),
+        _ => CounterAction::Show,
+    };
+
+    return Some((String::from(command), action));
+}
+
+/**
+ * The commands a counter answers to
+ */
+fn counter_commands(name: &str) -> Vec<String> {
+    return vec![String::from(name), format!("{}+", name), format!("{}-", name)];
+}
+
+/**
+ * Find the counter that answers to a command
+ */
+pub async fn get_command_counter(
+    channel_id: i32,
+    command: &str,
+    ctx: &BotContext,
+) -> Result<Option<counter_entity::Model>, Error> {
+    let name = command
+        .strip_suffix('+')
+        .or_else(|| command.strip_suffix('-'))
+        .unwrap_or(command);
+
+    return database::handler::counter::get_counter(channel_id, name, &ctx.db).await;
+}
+
+/**
+ * Show or change the counter of the invocation, changes need a moderator,
+ * returns false if the channel has no such counter
+ */
+pub async fn handle_counter(invocation: &Invocation, ctx: &BotContext) -> Result<bool, Error> {
+    let (name, action) = match parse_counter_command(&invocation.command, &invocation.args) {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+    let counter =
+        match database::handler::counter::get_counter(invocation.tags.room_id, &name, &ctx.db)
+            .await?
+        {
+            Some(x) => x,
+            None => return Ok(false),
+        };
+
+    let counter = match action {
+        CounterAction::Show => counter,
+        CounterAction::Change(change) => {
+            if !has_permission(&invocation.tags, &Permission::Moderator) {
+                return Ok(true);
+            }
+
+            let mut redis = ctx.redis.lock().await;
+            database::handler::counter::change_counter(&counter.id, change, &ctx.db, &mut redis)
+                .await?
+        }
+    };
+
+    ctx.say(&invocation.channel_name, &format!("{}: {}", counter.name, counter.value))
+        .await?;
+
+    return Ok(true);
+}
+
+/**
+ * !counter add|del|list name, moderators only
+ */
+pub async fn handle_counter_management(
+    invocation: &Invocation,
+    ctx: &BotContext,
+) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let mut args = invocation.args.iter().filter(|x| !x.is_empty());
+    let action = args.next().map(|x| x.to_lowercase());
+    let name = args.next().map(|x| normalize_name(x));
+    let channel_id = invocation.tags.room_id;
+
+    let name = match (action.as_deref(), name) {
+        (Some("list"), _) => {
+            let counters = database::handler::counter::get_counters(channel_id, &ctx.db).await?;
+            if counters.is_empty() {
+                return ctx.reply(invocation, "There are no counters yet").await;
+            }
+
+            let names = counters
+                .iter()
+                .map(|x| format!("!{} ({})", x.name, x.value))
+                .collect::<Vec<String>>();
+
+            return ctx
+                .reply(invocation, &format!("Counters: {}", join_limited(&names, MAX_LIST_LENGTH)))
+                .await;
+        }
+        (Some("add" | "del" | "delete" | "remove"), Some(Ok(x)))
+            if !x.ends_with('+') && !x.ends_with('-') =>
+        {
+            x
+        }
+        (_, Some(Err(e))) => return ctx.reply(invocation, &e).await,
+        _ => return ctx.reply(invocation, "Usage: !counter add|del|list name").await,
+    };
+
+    let existing = database::handler::counter::get_counter(channel_id, &name, &ctx.db).await?;
+
+    if action.as_deref() == Some("add") {
+        if is_builtin(&name) {
+            return ctx
+                .reply(invocation, &format!("!{} is a built-in command", name))
+                .await;
+        }
+        if existing.is_some() {
+            return ctx
+                .reply(invocation, &format!("Counter {} already exists", name))
+                .await;
+        }
+        // counters answer before the custom commands and would hide them
+        for command in counter_commands(&name) {
+            if database::handler::command::get_command(channel_id, &command, &ctx.db)
+                .await?
+                .is_some()
+            {
+                return ctx
+                    .reply(invocation, &format!("!{} is a custom command", command))
+                    .await;
+            }
+        }
+
+        let counter = counter_entity::ActiveModel {
+            channel_id: ActiveValue::Set(channel_id),
+            name: ActiveValue::Set(String::from(&name)),
+            value: ActiveValue::Set(0),
+            ..Default::default()
+        };
+        database::handler::counter::create_counter(counter, &ctx.db).await?;
+        audit(invocation, "counter.add", &name, json!({}), ctx).await?;
+
+        return ctx
+            .reply(
+                invocation,
+                &format!("Counter {} added, use !{0}+ !{0}- !{0} set and !{0} reset", name),
+            )
+            .await;
+    }
+
+    let counter = match existing {
+        Some(x) => x,
+        None => {
+            return ctx
+                .reply(invocation, &format!("Counter {} does not exist", name))
+                .await
+        }
+    };
+
+    {
+        let mut redis = ctx.redis.lock().await;
+        database::handler::counter::delete_counter(&counter, &ctx.db, &mut redis).await?;
+    }
+    audit(
+        invocation,
+        "counter.delete",
+        &name,
+        json!({ "before": { "value": counter.value } }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(invocation, &format!("Counter {} deleted", name))
+        .await;
+}
@@ -0,0 +1,119 @@
+use anyhow::{Error, Result};
+use chrono::Utc;
+
+use crate::context::{BotContext, Invocation};
+use crate::format::format_duration;
+use crate::lookup::{current_stream, followed_at, watch_time, CurrentStream};
+
+/**
+ * The user asked about, either the one given as the first argument or the
+ * user of the invocation, None if the given user was never seen
+ */
+async fn target_user(
+    invocation: &Invocation,
+    ctx: &BotContext,
+) -> Result<Option<(i32, String)>, Error> {
+    let nick = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(x) => x.trim_start_matches('@').to_lowercase(),
+        None => {
+            return Ok(Some((
+                invocation.tags.user_id,
+                String::from(&invocation.tags.display_name),
+            )))
+        }
+    };
+
+    let user = database::handler::user::get_user_by_nick(&nick, &ctx.db).await?;
+
+    return Ok(user.map(|x| (x.id, x.display_name)));
+}
+
+/**
+ * !watchtime [user] how long the user watched the channel
+ */
+pub async fn watchtime(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let (user_id, name) = match target_user(invocation, ctx).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "I have never seen that user").await,
+    };
+
+    let seconds = watch_time(invocation.tags.room_id, user_id, &ctx.db, &ctx.redis).await?;
+    let text = match seconds > 0 {
+        true => format!(
+            "{} watched {} for {}",
+            name,
+            invocation.channel_name,
+            format_duration(seconds)
+        ),
+        false => format!("{} has not watched {} yet", name, invocation.channel_name),
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !followage [user] how long the user follows the channel
+ */
+pub async fn followage(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let (user_id, name) = match target_user(invocation, ctx).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "I have never seen that user").await,
+    };
+
+    let followed_at =
+        followed_at(invocation.tags.room_id, user_id, &ctx.db, &ctx.redis, &ctx.twitch).await?;
+    let text = match followed_at {
+        Some(x) => format!(
+            "{} follows {} for {}",
+            name,
+            invocation.channel_name,
+            format_duration((Utc::now() - x).num_seconds())
+        ),
+        None => format!("{} is not following {}", name, invocation.channel_name),
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * Reply with a detail of the current stream or that the channel is offline
+ */
+async fn stream_info(
+    invocation: &Invocation,
+    ctx: &BotContext,
+    format: fn(&CurrentStream) -> String,
+) -> Result<(), Error> {
+    let text = match current_stream(invocation.tags.room_id, &ctx.db, &ctx.redis).await? {
+        Some(x) => format(&x),
+        None => format!("{} is offline", invocation.channel_name),
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !uptime how long the current stream is live
+ */
+pub async fn uptime(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    return stream_info(invocation, ctx, |x| {
+        format!(
+            "Live for {}",
+            format_duration((Utc::now() - x.started_at).num_seconds())
+        )
+    })
+    .await;
+}
+
+/**
+ * !game the game of the current stream
+ */
+pub async fn game(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    return stream_info(invocation, ctx, |x| format!("Current game: {}", x.game)).await;
+}
+
+/**
+ * !title the title of the current stream
+ */
+pub async fn title(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    return stream_info(invocation, ctx, |x| format!("Current title: {}", x.title)).await;
+}
@@ -0,0 +1,371 @@
+use anyhow::{Error, Result};
+use database::entity::command as command_entity;
+use database::entity::sea_orm_active_enums::Permission;
+use database::sea_orm::ActiveValue;
+use serde_json::json;
+
+use crate::builtins::counter::get_command_counter;
+use crate::builtins::{audit, is_builtin};
+use crate::context::{BotContext, Invocation};
+use crate::permission::{has_permission, parse_permission, permission_name};
+
+const MAX_COOLDOWN: i32 = 24 * 60 * 60;
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_LIST_LENGTH: usize = 450;
+
+/**
+ * The name and flags given to !addcom or !editcom, flags come before the body
+ * and only the ones given are changed
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOptions {
+    pub name: String,
+    pub permission: Option<Permission>,
+    pub global_cooldown: Option<i32>,
+    pub user_cooldown: Option<i32>,
+    pub whisper: Option<bool>,
+    pub rename: Option<String>,
+    pub body: String,
+}
+
+/**
+ * Normalize a command name to lowercase without the leading '!'
+ */
+pub fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim_start_matches('!').to_lowercase();
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Command names need 1 to {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+
+    return Ok(name);
+}
+
+fn parse_cooldown(flag: &str, value: &str) -> Result<i32, String> {
+    return match value.trim_end_matches('s').parse::<i32>() {
+        Ok(x) if (0..=MAX_COOLDOWN).contains(&x) => Ok(x),
+        _ => Err(format!("-{} needs seconds between 0 and {}", flag, MAX_COOLDOWN)),
+    };
+}
+
+/**
+ * Parse `!name [-ul=mod] [-cd=30] [-ucd=10] [-whisper=on] [-name=!new] body`,
+ * the errors are meant to be shown in chat
+ */
+pub fn parse_options(args: &[String]) -> Result<CommandOptions, String> {
+    let mut args = args.iter().filter(|x| !x.is_empty()).peekable();
+
+    let name = match args.next() {
+        Some(x) => normalize_name(x)?,
+        None => return Err(String::from("Missing the command name")),
+    };
+    let mut options = CommandOptions {
+        name,
+        ..Default::default()
+    };
+
+    while let Some(arg) = args.peek() {
+        let (flag, value) = match arg.strip_prefix('-').and_then(|x| x.split_once('=')) {
+            Some(x) => x,
+            None => break,
+        };
+
+        match flag.to_lowercase().as_str() {
+            "ul" | "userlevel" => match parse_permission(value) {
+                Some(x) => options.permission = Some(x),
+                None => {
+                    return Err(String::from(
+                        "-ul needs everyone, subscriber, vip, moderator or broadcaster",
+                    ))
+                }
+            },
+            "cd" | "cooldown" => options.global_cooldown = Some(parse_cooldown(flag, value)?),
+            "ucd" | "usercooldown" => options.user_cooldown = Some(parse_cooldown(flag, value)?),
+            "whisper" => match value.to_lowercase().as_str() {
+                "on" | "true" | "1" => options.whisper = Some(true),
+                "off" | "false" | "0" => options.whisper = Some(false),
+                _ => return Err(String::from("-whisper needs on or off")),
+            },
+            "name" | "rename" => options.rename = Some(normalize_name(value)?),
+            _ => return Err(format!("Unknown option -{}", flag)),
+        }
+
+        args.next();
+    }
+
+    options.body = args.map(|x| x.as_str()).collect::<Vec<&str>>().join(" ");
+
+    return Ok(options);
+}
+
+fn command_details(command: &command_entity::Model) -> serde_json::Value {
+    return json!({
+        "name": command.name,
+        "body": command.body,
+        "permission": permission_name(&command.permission),
+        "global_cooldown": command.global_cooldown,
+        "user_cooldown": command.user_cooldown,
+        "cooldown_whisper": command.cooldown_whisper == 1,
+    });
+}
+
+/**
+ * Parse the options of a management command, replies with the problem if invalid
+ */
+async fn options(invocation: &Invocation, ctx: &BotContext) -> Result<Option<CommandOptions>, Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(None);
+    }
+
+    return match parse_options(&invocation.args) {
+        Ok(x) => Ok(Some(x)),
+        Err(e) => {
+            ctx.reply(invocation, &e).await?;
+            Ok(None)
+        }
+    };
+}
+
+/**
+ * !addcom !name [-ul=mod] [-cd=30] [-ucd=10] [-whisper=on] body
+ */
+pub async fn add_command(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let options = match options(invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    if options.body.is_empty() {
+        return ctx
+            .reply(invocation, "Usage: !addcom !name [-ul=mod] [-cd=30] [-ucd=10] body")
+            .await;
+    }
+    if is_builtin(&options.name) {
+        return ctx
+            .reply(invocation, &format!("!{} is a built-in command", options.name))
+            .await;
+    }
+    let channel_id = invocation.tags.room_id;
+    if database::handler::command::get_command(channel_id, &options.name, &ctx.db)
+        .await?
+        .is_some()
+    {
+        return ctx
+            .reply(invocation, &format!("!{} already exists, use !editcom", options.name))
+            .await;
+    }
+    if let Some(counter) = get_command_counter(channel_id, &options.name, ctx).await? {
+        return ctx
+            .reply(
+                invocation,
+                &format!("!{} belongs to the counter {}", options.name, counter.name),
+            )
+            .await;
+    }
+
+    let command = command_entity::ActiveModel {
+        channel_id: ActiveValue::Set(channel_id),
+        name: ActiveValue::Set(String::from(&options.name)),
+        body: ActiveValue::Set(options.body),
+        permission: ActiveValue::Set(options.permission.unwrap_or(Permission::Everyone)),
+        invoke_count: ActiveValue::Set(0),
+        global_cooldown: ActiveValue::Set(options.global_cooldown.unwrap_or(0)),
+        user_cooldown: ActiveValue::Set(options.user_cooldown.unwrap_or(0)),
+        cooldown_whisper: ActiveValue::Set(options.whisper.unwrap_or(false) as i8),
+        ..Default::default()
+    };
+    let command = database::handler::command::create_command(command, &ctx.db).await?;
+
+    audit(
+        invocation,
+        "command.add",
+        &command.name,
+        json!({ "after": command_details(&command) }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(invocation, &format!("Command !{} added", command.name))
+        .await;
+}
+
+/**
+ * !editcom !name [-ul=mod] [-cd=30] [-ucd=10] [-whisper=on] [-name=!new] [body]
+ */
+pub async fn edit_command(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let options = match options(invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    let channel_id = invocation.tags.room_id;
+    let before = match database::handler::command::get_command(channel_id, &options.name, &ctx.db)
+        .await?
+    {
+        Some(x) => x,
+        None => {
+            return ctx
+                .reply(invocation, &format!("!{} does not exist", options.name))
+                .await
+        }
+    };
+
+    let mut command: command_entity::ActiveModel = before.clone().into();
+    let mut changed = false;
+
+    if let Some(rename) = options.rename {
+        if is_builtin(&rename) {
+            return ctx
+                .reply(invocation, &format!("!{} is a built-in command", rename))
+                .await;
+        }
+        if rename != before.name
+            && database::handler::command::get_command(channel_id, &rename, &ctx.db)
+                .await?
+                .is_some()
+        {
+            return ctx
+                .reply(invocation, &format!("!{} already exists", rename))
+                .await;
+        }
+        if let Some(counter) = get_command_counter(channel_id, &rename, ctx).await? {
+            return ctx
+                .reply(
+                    invocation,
+                    &format!("!{} belongs to the counter {}", rename, counter.name),
+                )
+                .await;
+        }
+        command.name = ActiveValue::Set(rename);
+        changed = true;
+    }
+    if !options.body.is_empty() {
+        command.body = ActiveValue::Set(options.body);
+        changed = true;
+    }
+    if let Some(x) = options.permission {
+        command.permission = ActiveValue::Set(x);
+        changed = true;
+    }
+    if let Some(x) = options.global_cooldown {
+        command.global_cooldown = ActiveValue::Set(x);
+        changed = true;
+    }
+    if let Some(x) = options.user_cooldown {
+        command.user_cooldown = ActiveValue::Set(x);
+        changed = true;
+    }
+    if let Some(x) = options.whisper {
+        command.cooldown_whisper = ActiveValue::Set(x as i8);
+        changed = true;
+    }
+
+    if !changed {
+        return ctx
+            .reply(
+                invocation,
+                "Usage: !editcom !name [-ul=mod] [-cd=30] [-ucd=10] [-name=!new] [body]",
+            )
+            .await;
+    }
+
+    let after = database::handler::command::update_command(command, &ctx.db).await?;
+
+    audit(
+        invocation,
+        "command.edit",
+        &before.name,
+        json!({ "before": command_details(&before), "after": command_details(&after) }),
+        ctx,
+    )
+    .await?;
+
+    let text = match after.name == before.name {
+        true => format!("Command !{} updated", after.name),
+        false => format!("Command !{} updated and renamed to !{}", before.name, after.name),
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !delcom !name
+ */
+pub async fn delete_command(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let options = match options(invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    let channel_id = invocation.tags.room_id;
+    let command = match database::handler::command::get_command(channel_id, &options.name, &ctx.db)
+        .await?
+    {
+        Some(x) => x,
+        None => {
+            return ctx
+                .reply(invocation, &format!("!{} does not exist", options.name))
+                .await
+        }
+    };
+
+    database::handler::command::delete_command(&command.id, &ctx.db).await?;
+
+    audit(
+        invocation,
+        "command.delete",
+        &command.name,
+        json!({ "before": command_details(&command) }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(invocation, &format!("Command !{} deleted", command.name))
+        .await;
+}
+
+/**
+ * !commands lists the commands the user may use
+ */
+pub async fn list_commands(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let commands =
+        database::handler::command::get_commands(invocation.tags.room_id, &ctx.db).await?;
+    let names = commands
+        .iter()
+        .filter(|x| has_permission(&invocation.tags, &x.permission))
+        .map(|x| format!("!{}", x.name))
+        .collect::<Vec<String>>();
+
+    if names.is_empty() {
+        return ctx.reply(invocation, "There are no commands yet").await;
+    }
+
+    return ctx
+        .reply(invocation, &format!("Commands: {}", join_limited(&names, MAX_LIST_LENGTH)))
+        .await;
+}
+
+/**
+ * Join names with commas, cut off with "..." once the text gets too long for chat
+ */
+pub fn join_limited(names: &[String], limit: usize) -> String {
+    let mut text = String::new();
+
+    for name in names {
+        let next = match text.is_empty() {
+            true => name.to_string(),
+            false => format!(", {}", name),
+        };
+        if text.len() + next.len() > limit {
+            text.push_str(", ...");
+            break;
+        }
+        text.push_str(&next);
+    }
+
+    return text;
+}
@@ -0,0 +1,176 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::Permission;
+use database::entity::user as user_entity;
+use database::sea_orm::ActiveValue;
+
+use crate::context::{BotContext, Invocation};
+use crate::cooldown::{start_cooldown, Cooldown};
+use crate::permission::has_permission;
+
+pub mod banphrase;
+pub mod banshare;
+pub mod counter;
+pub mod info;
+pub mod management;
+pub mod moderation;
+pub mod points;
+pub mod poll;
+pub mod queue;
+pub mod quote;
+pub mod raffle;
+pub mod strike;
+pub mod timer;
+
+/**
+ * Seconds a viewer waits between uses of the same built-in command
+ */
+const BUILTIN_USER_COOLDOWN: i32 = 3;
+
+/**
+ * Names of the built-in commands, custom commands can not use them
+ */
+pub const BUILTINS: &[&str] = &[
+    "addcom",
+    "editcom",
+    "delcom",
+    "commands",
+    "timer",
+    "watchtime",
+    "followage",
+    "uptime",
+    "game",
+    "title",
+    "quote",
+    "addquote",
+    "delquote",
+    "counter",
+    "points",
+    "give",
+    "top",
+    "addpoints",
+    "raffle",
+    "poll",
+    "vote",
+    "queue",
+    "join",
+    "leave",
+    "position",
+    "next",
+    "permit",
+    "banphrase",
+    "history",
+    "warn",
+    "strikes",
+    "pardon",
+    "banshare",
+];
+
+/**
+ * Run the built-in command of the invocation, returns false if it is not a
+ * built-in. Viewers get a short cooldown per built-in so their errors can not
+ * be spammed
+ */
+pub async fn handle_builtin(invocation: &Invocation, ctx: &BotContext) -> Result<bool, Error> {
+    if !is_builtin(&invocation.command) {
+        return Ok(false);
+    }
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        let cooldown = Cooldown {
+            key: format!("channel:{}:builtin:{}", invocation.tags.room_id, invocation.command),
+            global: 0,
+            user: BUILTIN_USER_COOLDOWN,
+        };
+        let mut redis = ctx.redis.lock().await;
+        let started = start_cooldown(&cooldown, invocation.tags.user_id, &mut redis).await?;
+        drop(redis);
+        if !started {
+            return Ok(true);
+        }
+    }
+
+    match invocation.command.as_str() {
+        "addcom" => management::add_command(invocation, ctx).await?,
+        "editcom" => management::edit_command(invocation, ctx).await?,
+        "delcom" => management::delete_command(invocation, ctx).await?,
+        "commands" => management::list_commands(invocation, ctx).await?,
+        "timer" => timer::handle_timer(invocation, ctx).await?,
+        "watchtime" => info::watchtime(invocation, ctx).await?,
+        "followage" => info::followage(invocation, ctx).await?,
+        "uptime" => info::uptime(invocation, ctx).await?,
+        "game" => info::game(invocation, ctx).await?,
+        "title" => info::title(invocation, ctx).await?,
+        "quote" => quote::handle_quote(invocation, ctx).await?,
+        "addquote" => quote::handle_add_quote(invocation, ctx).await?,
+        "delquote" => quote::handle_delete_quote(invocation, ctx).await?,
+        "counter" => counter::handle_counter_management(invocation, ctx).await?,
+        "points" => points::points(invocation, ctx).await?,
+        "give" => points::give(invocation, ctx).await?,
+        "top" => points::top(invocation, ctx).await?,
+        "addpoints" => points::add_points(invocation, ctx).await?,
+        "raffle" => raffle::handle_raffle(invocation, ctx).await?,
+        "poll" => poll::handle_poll(invocation, ctx).await?,
+        "vote" => poll::vote(invocation, ctx).await?,
+        "queue" => queue::handle_queue(invocation, ctx).await?,
+        "join" => queue::join(invocation, ctx).await?,
+        "leave" => queue::leave(invocation, ctx).await?,
+        "position" => queue::position(invocation, ctx).await?,
+        "next" => queue::next(invocation, ctx).await?,
+        "permit" => moderation::permit(invocation, ctx).await?,
+        "banphrase" => banphrase::handle_banphrase(invocation, ctx).await?,
+        "history" => moderation::history(invocation, ctx).await?,
+        "warn" => strike::warn(invocation, ctx).await?,
+        "strikes" => strike::strikes(invocation, ctx).await?,
+        "pardon" => strike::pardon(invocation, ctx).await?,
+        "banshare" => banshare::handle_banshare(invocation, ctx).await?,
+        _ => return Ok(false),
+    }
+
+    return Ok(true);
+}
+
+/**
+ * Check if a name is taken by a built-in command
+ */
+pub fn is_builtin(name: &str) -> bool {
+    return BUILTINS.contains(&name);
+}
+
+/**
+ * Create the user of the invocation for rows that reference it
+ */
+pub async fn ensure_user(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let user = user_entity::ActiveModel {
+        id: ActiveValue::Set(invocation.tags.user_id),
+        nick: ActiveValue::Set(String::from(&invocation.nick)),
+        display_name: ActiveValue::Set(String::from(&invocation.tags.display_name)),
+        ..Default::default()
+    };
+    database::handler::user::create_user(user, &ctx.db).await?;
+
+    return Ok(());
+}
+
+/**
+ * Record who changed what
+ */
+pub async fn audit(
+    invocation: &Invocation,
+    action: &str,
+    target: &str,
+    details: serde_json::Value,
+    ctx: &BotContext,
+) -> Result<(), Error> {
+    ensure_user(invocation, ctx).await?;
+
+    database::handler::audit::create_audit_log(
+        invocation.tags.room_id,
+        invocation.tags.user_id,
+        action,
+        target,
+        Some(details.to_string()),
+        &ctx.db,
+    )
+    .await?;
+
+    return Ok(());
+}
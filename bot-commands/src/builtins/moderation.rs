@@ -0,0 +1,141 @@
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use database::entity::moderation_action as moderation_action_entity;
+use database::entity::sea_orm_active_enums::{ModerationActionType, Permission};
+use database::handler::moderation::ActionCount;
+use serde_json::json;
+
+use crate::builtins::audit;
+use crate::context::{BotContext, Invocation};
+use crate::format::format_duration;
+use crate::permission::has_permission;
+
+const HISTORY_LIMIT: u64 = 3;
+
+fn action_label(action: &ModerationActionType) -> &'static str {
+    return match action {
+        ModerationActionType::Ban => "ban",
+        ModerationActionType::Timeout => "timeout",
+        ModerationActionType::Clear => "clear",
+        ModerationActionType::Delete => "deletion",
+    };
+}
+
+fn action_name(action: &ModerationActionType, count: i64) -> String {
+    let name = action_label(action);
+
+    return match count {
+        1 => format!("1 {}", name),
+        _ => format!("{} {}s", count, name),
+    };
+}
+
+/**
+ * Summarize the moderation history of a user like `2 timeouts, 1 deletion,
+ * latest: timeout 600s (too many caps) 3 days ago`
+ */
+pub fn format_history(
+    counts: &[ActionCount],
+    latest: &[moderation_action_entity::Model],
+    now: DateTime<Utc>,
+) -> String {
+    if counts.is_empty() {
+        return String::from("a clean record");
+    }
+
+    let counts = counts
+        .iter()
+        .map(|x| action_name(&x.action, x.count))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let latest = latest
+        .iter()
+        .map(|x| {
+            let mut text = match (&x.action, x.duration) {
+                (ModerationActionType::Timeout, Some(duration)) => format!("timeout {}s", duration),
+                (action, _) => String::from(action_label(action)),
+            };
+            if let Some(reason) = x.reason.as_ref().filter(|x| !x.is_empty()) {
+                text.push_str(&format!(" ({})", reason));
+            }
+            text.push_str(&format!(
+                " {} ago",
+                format_duration((now - x.timestamp).num_seconds())
+            ));
+            text
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    return format!("{}, latest: {}", counts, latest);
+}
+
+/**
+ * !permit user lets a viewer post one link past the link filter, moderators only
+ */
+pub async fn permit(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let nick = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(x) => x.trim_start_matches('@').to_lowercase(),
+        None => return ctx.reply(invocation, "Usage: !permit user").await,
+    };
+    let user = match database::handler::user::get_user_by_nick(&nick, &ctx.db).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "I have never seen that user").await,
+    };
+
+    crate::spam::permit(invocation.tags.room_id, user.id, ctx).await?;
+    audit(invocation, "spam.permit", &user.nick, json!({}), ctx).await?;
+
+    return ctx
+        .say(
+            &invocation.channel_name,
+            &format!("@{} you may post one link in the next minute", user.display_name),
+        )
+        .await;
+}
+
+/**
+ * !history user shows the past moderation actions against a viewer,
+ * moderators only
+ */
+pub async fn history(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let nick = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(x) => x.trim_start_matches('@').to_lowercase(),
+        None => return ctx.reply(invocation, "Usage: !history user").await,
+    };
+    let user = match database::handler::user::get_user_by_nick(&nick, &ctx.db).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "I have never seen that user").await,
+    };
+
+    let channel_id = invocation.tags.room_id;
+    let counts =
+        database::handler::moderation::get_user_action_counts(channel_id, user.id, &ctx.db).await?;
+    let latest = database::handler::moderation::get_user_history(
+        channel_id,
+        user.id,
+        HISTORY_LIMIT,
+        &ctx.db,
+    )
+    .await?;
+
+    return ctx
+        .reply(
+            invocation,
+            &format!(
+                "{} has {}",
+                user.display_name,
+                format_history(&counts, &latest, Utc::now())
+            ),
+        )
+        .await;
+}
@@ -0,0 +1,185 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::Permission;
+use database::handler::points::{PointsChange, Transfer, REASON_ADD};
+use serde_json::json;
+
+use crate::builtins::{audit, ensure_user};
+use crate::context::{BotContext, Invocation};
+use crate::permission::has_permission;
+
+const TOP_LIMIT: u64 = 5;
+pub const MAX_AMOUNT: i64 = 1_000_000_000;
+
+/**
+ * Parse `user amount` as given to !give and !addpoints, amounts go up to
+ * `MAX_AMOUNT` either way
+ */
+pub fn parse_user_amount(args: &[String]) -> Option<(String, i64)> {
+    let mut args = args.iter().filter(|x| !x.is_empty());
+
+    let user = args.next()?.trim_start_matches('@').to_lowercase();
+    let amount = args.next()?.parse::<i64>().ok()?;
+    if user.is_empty() || amount.unsigned_abs() > MAX_AMOUNT as u64 {
+        return None;
+    }
+
+    return Some((user, amount));
+}
+
+/**
+ * !points [user] the balance and rank of a viewer
+ */
+pub async fn points(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let (user_id, name) = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(nick) => {
+            let nick = nick.trim_start_matches('@');
+            match database::handler::user::get_user_by_nick(nick, &ctx.db).await? {
+                Some(x) => (x.id, x.display_name),
+                None => return ctx.reply(invocation, "I have never seen that user").await,
+            }
+        }
+        None => (
+            invocation.tags.user_id,
+            String::from(&invocation.tags.display_name),
+        ),
+    };
+
+    let balance = database::handler::points::get_balance(channel_id, user_id, &ctx.db).await?;
+    let rank = database::handler::points::get_rank(channel_id, balance, &ctx.db).await?;
+
+    return ctx
+        .reply(
+            invocation,
+            &format!("{} has {} points and is rank #{}", name, balance, rank),
+        )
+        .await;
+}
+
+/**
+ * !give user amount moves points to another viewer
+ */
+pub async fn give(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let (nick, amount) = match parse_user_amount(&invocation.args) {
+        Some(x) if x.1 > 0 => x,
+        _ => {
+            let usage = format!("Usage: !give user amount, up to {} points", MAX_AMOUNT);
+            return ctx.reply(invocation, &usage).await;
+        }
+    };
+    let user = match database::handler::user::get_user_by_nick(&nick, &ctx.db).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "I have never seen that user").await,
+    };
+    if user.id == invocation.tags.user_id {
+        return ctx.reply(invocation, "You can not give points to yourself").await;
+    }
+
+    ensure_user(invocation, ctx).await?;
+    let transfer = database::handler::points::transfer_points(
+        invocation.tags.room_id,
+        invocation.tags.user_id,
+        user.id,
+        amount,
+        &ctx.db,
+    )
+    .await?;
+
+    let text = match transfer {
+        Transfer::Done { from_balance, .. } => format!(
+            "You gave {} points to {}, you have {} left",
+            amount, user.display_name, from_balance
+        ),
+        Transfer::Insufficient { balance } => {
+            format!("You only have {} points", balance)
+        }
+        Transfer::TooLarge => format!("{} can not hold that many points", user.display_name),
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !top the viewers with the most points
+ */
+pub async fn top(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let top = database::handler::points::get_top(invocation.tags.room_id, TOP_LIMIT, &ctx.db).await?;
+    if top.is_empty() {
+        return ctx.reply(invocation, "Nobody has points yet").await;
+    }
+
+    let text = top
+        .iter()
+        .enumerate()
+        .map(|(i, (balance, user))| {
+            let name = match user {
+                Some(x) => String::from(&x.display_name),
+                None => balance.user_id.to_string(),
+            };
+            format!("{}. {} ({})", i + 1, name, balance.balance)
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !addpoints user amount, a negative amount removes points, moderators only
+ */
+pub async fn add_points(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let (nick, amount) = match parse_user_amount(&invocation.args) {
+        Some(x) if x.1 != 0 => x,
+        _ => {
+            let usage = format!("Usage: !addpoints user amount, up to {} points", MAX_AMOUNT);
+            return ctx.reply(invocation, &usage).await;
+        }
+    };
+    let user = match database::handler::user::get_user_by_nick(&nick, &ctx.db).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "I have never seen that user").await,
+    };
+
+    let balance = database::handler::points::change_points(
+        invocation.tags.room_id,
+        user.id,
+        amount,
+        REASON_ADD,
+        Some(invocation.tags.user_id),
+        &ctx.db,
+    )
+    .await?;
+    let balance = match balance {
+        PointsChange::Done { balance } => balance,
+        PointsChange::Insufficient { .. } => {
+            return ctx
+                .reply(invocation, &format!("{} does not have that many points", user.display_name))
+                .await
+        }
+        PointsChange::TooLarge => {
+            return ctx
+                .reply(invocation, &format!("{} can not hold that many points", user.display_name))
+                .await
+        }
+    };
+
+    audit(
+        invocation,
+        "points.add",
+        &user.nick,
+        json!({ "amount": amount, "balance": balance }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(
+            invocation,
+            &format!("{} now has {} points", user.display_name, balance),
+        )
+        .await;
+}
@@ -0,0 +1,370 @@
+use anyhow::{Error, Result};
+use chrono::Utc;
+use database::entity::sea_orm_active_enums::Permission;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::builtins::audit;
+use crate::context::{BotContext, Invocation};
+use crate::format::format_duration;
+use crate::permission::has_permission;
+
+const DEFAULT_DURATION: i64 = 120;
+const MAX_DURATION: i64 = 60 * 60;
+const MAX_OPTIONS: usize = 10;
+const USAGE: &str = "Usage: !poll \"Question\" a|b|c [-time=120], !poll end or !poll cancel";
+
+/**
+ * An open poll of a channel, kept in redis so a restart does not lose it
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<String>,
+    /** the index of the option each user voted for */
+    pub votes: HashMap<i32, usize>,
+    pub channel_name: String,
+    pub started_by: i32,
+    pub ends_at: i64,
+}
+
+/**
+ * The channels with an open poll, so closing them does not scan redis
+ */
+const OPEN_POLLS: &str = "polls:open";
+
+fn poll_key(channel_id: i32) -> String {
+    return format!("channel:{}:poll", channel_id);
+}
+
+/**
+ * Parse `"Question" a|b|c [-time=seconds]` into the question, the options
+ * and the duration in seconds, the errors are meant to be shown in chat
+ */
+pub fn parse_poll(text: &str) -> Result<(String, Vec<String>, i64), String> {
+    let mut duration = DEFAULT_DURATION;
+    let mut words: Vec<&str> = Vec::new();
+    for word in text.split(' ').filter(|x| !x.is_empty()) {
+        let value = match word.strip_prefix("-time=") {
+            Some(x) => x,
+            None => {
+                words.push(word);
+                continue;
+            }
+        };
+        duration = match value.strip_suffix('m') {
+            Some(x) => x.parse::<i64>().map(|x| x * 60),
+            None => value.parse::<i64>(),
+        }
+        .map_err(|_| String::from("-time needs seconds"))?;
+        if !(1..=MAX_DURATION).contains(&duration) {
+            return Err(format!("A poll runs for at most {}", format_duration(MAX_DURATION)));
+        }
+    }
+
+    let text = words.join(" ");
+    let (question, options) = match text.strip_prefix('"').and_then(|x| x.split_once('"')) {
+        Some((question, options)) => (question.trim(), options),
+        None => return Err(String::from(USAGE)),
+    };
+    let options = options
+        .split('|')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    if question.is_empty() || options.len() < 2 {
+        return Err(String::from(USAGE));
+    }
+    if options.len() > MAX_OPTIONS {
+        return Err(format!("A poll has at most {} options", MAX_OPTIONS));
+    }
+
+    return Ok((String::from(question), options, duration));
+}
+
+/**
+ * Find the option a vote is for, by its number starting at 1 or its text
+ */
+pub fn parse_vote(options: &[String], vote: &str) -> Option<usize> {
+    let vote = vote.trim();
+    if let Ok(x) = vote.parse::<usize>() {
+        return match x {
+            x if x >= 1 && x <= options.len() => Some(x - 1),
+            _ => None,
+        };
+    }
+
+    return options.iter().position(|x| x.eq_ignore_ascii_case(vote));
+}
+
+/**
+ * Format the results of a poll like `Question: a 2 (67%), b 1 (33%), winner: a`
+ */
+pub fn poll_results(poll: &Poll) -> String {
+    let mut counts = vec![0; poll.options.len()];
+    for option in poll.votes.values() {
+        if let Some(x) = counts.get_mut(*option) {
+            *x += 1;
+        }
+    }
+    let total = counts.iter().sum::<usize>();
+
+    let results = poll
+        .options
+        .iter()
+        .zip(counts.iter())
+        .map(|(option, count)| match total {
+            0 => format!("{} 0", option),
+            _ => format!("{} {} ({}%)", option, count, count * 100 / total),
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let winners = poll
+        .options
+        .iter()
+        .zip(counts.iter())
+        .filter(|(_, count)| most > 0 && **count == most)
+        .map(|(option, _)| option.as_str())
+        .collect::<Vec<&str>>();
+    let outcome = match winners.len() {
+        0 => String::from("nobody voted"),
+        1 => format!("winner: {}", winners[0]),
+        _ => format!("tie: {}", winners.join(", ")),
+    };
+
+    return format!("{}: {}, {}", poll.question, results, outcome);
+}
+
+/**
+ * Remove the poll of a channel, returns it if it was still open
+ */
+async fn take_poll(channel_id: i32, ctx: &BotContext) -> Result<Option<Poll>, Error> {
+    let mut redis = ctx.redis.lock().await;
+    let poll =
+        cache::update_as(&poll_key(channel_id), &mut redis, |x: &mut Option<Poll>| x.take())
+            .await?;
+    cache::remove_from_set(OPEN_POLLS, &channel_id.to_string(), &mut redis).await?;
+
+    return Ok(poll);
+}
+
+/**
+ * !poll "Question" a|b|c starts a poll, !poll end closes it early and !poll
+ * cancel drops it, moderators only, !poll alone shows the open poll
+ */
+pub async fn handle_poll(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let action = invocation
+        .args
+        .iter()
+        .find(|x| !x.is_empty())
+        .map(|x| x.to_lowercase());
+
+    if action.is_none() {
+        let poll = cache::get_as::<Poll>(&poll_key(channel_id), &mut *ctx.redis.lock().await).await?;
+        let text = match poll {
+            Some(x) => format!(
+                "{} Vote with !vote {}, {} left",
+                x.question,
+                x.options
+                    .iter()
+                    .enumerate()
+                    .map(|(i, option)| format!("{}={}", i + 1, option))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+                format_duration(x.ends_at - Utc::now().timestamp())
+            ),
+            None => String::from("There is no poll running"),
+        };
+        return ctx.reply(invocation, &text).await;
+    }
+
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    if let Some("end" | "cancel") = action.as_deref() {
+        let poll = match take_poll(channel_id, ctx).await? {
+            Some(x) => x,
+            None => return ctx.reply(invocation, "There is no poll running").await,
+        };
+
+        let results = poll_results(&poll);
+        if action.as_deref() == Some("cancel") {
+            audit(invocation, "poll.cancel", &poll.question, json!({ "results": results }), ctx)
+                .await?;
+            return ctx.reply(invocation, "The poll was cancelled").await;
+        }
+
+        audit(invocation, "poll.end", &poll.question, json!({ "results": results }), ctx).await?;
+        return ctx
+            .say(&invocation.channel_name, &format!("Poll closed! {}", results))
+            .await;
+    }
+
+    let (question, options, duration) = match parse_poll(&invocation.args.join(" ")) {
+        Ok(x) => x,
+        Err(e) => return ctx.reply(invocation, &e).await,
+    };
+    let poll = Poll {
+        question,
+        options,
+        votes: HashMap::new(),
+        channel_name: String::from(&invocation.channel_name),
+        started_by: invocation.tags.user_id,
+        ends_at: Utc::now().timestamp() + duration,
+    };
+
+    {
+        let mut redis = ctx.redis.lock().await;
+        let started = cache::update_as(&poll_key(channel_id), &mut redis, |x: &mut Option<Poll>| {
+            if x.is_some() {
+                return false;
+            }
+            *x = Some(poll.clone());
+            true
+        })
+        .await?;
+        if !started {
+            drop(redis);
+            return ctx
+                .reply(invocation, "A poll is already running, end or cancel it first")
+                .await;
+        }
+        cache::add_to_set(OPEN_POLLS, &channel_id.to_string(), &mut redis).await?;
+    }
+
+    audit(
+        invocation,
+        "poll.start",
+        &poll.question,
+        json!({ "options": poll.options, "duration": duration }),
+        ctx,
+    )
+    .await?;
+
+    let options = poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| format!("{}={}", i + 1, option))
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    return ctx
+        .say(
+            &invocation.channel_name,
+            &format!(
+                "Poll: {} Vote with !vote {} ({})",
+                poll.question,
+                options,
+                format_duration(duration)
+            ),
+        )
+        .await;
+}
+
+/**
+ * !vote n or !vote option, one vote per user, later votes are ignored
+ */
+pub async fn vote(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let choice = invocation.args.join(" ");
+
+    let user_id = invocation.tags.user_id;
+    let now = Utc::now().timestamp();
+
+    // the option voted for and the number of options, None without a poll
+    let vote = cache::update_as(
+        &poll_key(channel_id),
+        &mut *ctx.redis.lock().await,
+        |x: &mut Option<Poll>| {
+            let poll = match x {
+                Some(x) if x.ends_at > now => x,
+                _ => return None,
+            };
+            let option = parse_vote(&poll.options, &choice);
+            if let Some(option) = option {
+                poll.votes.entry(user_id).or_insert(option);
+            }
+            Some((option, poll.options.len()))
+        },
+    )
+    .await?;
+
+    return match vote {
+        Some((None, options)) => {
+            ctx.reply(invocation, &format!("Vote with a number from 1 to {}", options))
+                .await
+        }
+        _ => Ok(()),
+    };
+}
+
+/**
+ * Close the polls whose time ran out and post their results, checked every
+ * interval
+ */
+pub fn spawn(ctx: Arc<BotContext>, interval: Duration) -> JoinHandle<()> {
+    return tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+
+        loop {
+            tick.tick().await;
+
+            if let Err(e) = close_expired_polls(&ctx).await {
+                println!("Failed to close polls: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn close_expired_polls(ctx: &BotContext) -> Result<(), Error> {
+    let now = Utc::now().timestamp();
+    let channels = cache::set_members(OPEN_POLLS, &mut *ctx.redis.lock().await).await?;
+
+    for channel in channels {
+        let channel_id = match channel.parse::<i32>() {
+            Ok(x) => x,
+            Err(_) => continue,
+        };
+
+        let poll = {
+            let mut redis = ctx.redis.lock().await;
+            // the poll if it ran out and if the channel is done
+            let (poll, done) =
+                cache::update_as(&poll_key(channel_id), &mut redis, |x: &mut Option<Poll>| {
+                    match x.as_ref().map(|x| x.ends_at <= now) {
+                        Some(true) => (x.take(), true),
+                        Some(false) => (None, false),
+                        None => (None, true),
+                    }
+                })
+                .await?;
+            if done {
+                cache::remove_from_set(OPEN_POLLS, &channel, &mut redis).await?;
+            }
+            match poll {
+                Some(x) => x,
+                None => continue,
+            }
+        };
+
+        if let Err(e) = ctx
+            .say(&poll.channel_name, &format!("Poll closed! {}", poll_results(&poll)))
+            .await
+        {
+            println!("Failed to post poll results in {}: {:?}", channel_id, e);
+        }
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,290 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::Permission;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::builtins::audit;
+use crate::builtins::management::join_limited;
+use crate::context::{BotContext, Invocation};
+use crate::permission::has_permission;
+
+const MAX_NEXT: usize = 10;
+const MAX_LIST_LENGTH: usize = 300;
+
+/**
+ * A viewer waiting in the queue of a channel
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub user_id: i32,
+    pub name: String,
+    pub subscriber: bool,
+}
+
+/**
+ * The viewer queue of a channel, kept in redis so a restart does not lose it
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Queue {
+    pub open: bool,
+    pub entries: Vec<QueueEntry>,
+}
+
+impl Queue {
+    /**
+     * Add a viewer behind everyone of the same priority, subscribers go
+     * ahead of the other viewers, returns the position starting at 1 or None
+     * if the viewer is already waiting
+     */
+    pub fn join(&mut self, entry: QueueEntry) -> Option<usize> {
+        if self.position(entry.user_id).is_some() {
+            return None;
+        }
+
+        let index = match entry.subscriber {
+            true => self
+                .entries
+                .iter()
+                .position(|x| !x.subscriber)
+                .unwrap_or(self.entries.len()),
+            false => self.entries.len(),
+        };
+        self.entries.insert(index, entry);
+
+        return Some(index + 1);
+    }
+
+    /**
+     * Remove a viewer, returns false if the viewer was not waiting
+     */
+    pub fn leave(&mut self, user_id: i32) -> bool {
+        let length = self.entries.len();
+        self.entries.retain(|x| x.user_id != user_id);
+
+        return self.entries.len() != length;
+    }
+
+    /**
+     * The position of a viewer starting at 1
+     */
+    pub fn position(&self, user_id: i32) -> Option<usize> {
+        return self.entries.iter().position(|x| x.user_id == user_id).map(|x| x + 1);
+    }
+
+    /**
+     * Take the next viewers off the queue
+     */
+    pub fn next(&mut self, count: usize) -> Vec<QueueEntry> {
+        let count = count.min(self.entries.len());
+        return self.entries.drain(..count).collect();
+    }
+}
+
+fn queue_key(channel_id: i32) -> String {
+    return format!("channel:{}:queue", channel_id);
+}
+
+async fn get_queue(channel_id: i32, redis: &mut cache::redis::aio::Connection) -> Result<Queue, Error> {
+    let queue = cache::get_as::<Queue>(&queue_key(channel_id), redis).await?;
+    return Ok(queue.unwrap_or_default());
+}
+
+
+/**
+ * !join puts the viewer into the open queue
+ */
+pub async fn join(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let entry = QueueEntry {
+        user_id: invocation.tags.user_id,
+        name: String::from(&invocation.tags.display_name),
+        subscriber: invocation.tags.subscriber,
+    };
+
+    let text = cache::update_as(
+        &queue_key(channel_id),
+        &mut *ctx.redis.lock().await,
+        |x: &mut Option<Queue>| {
+            let queue = match x {
+                Some(x) if x.open => x,
+                _ => return String::from("The queue is closed"),
+            };
+            match queue.join(entry.clone()) {
+                Some(x) => format!("You joined the queue at position {}", x),
+                None => format!(
+                    "You are already in the queue at position {}",
+                    queue.position(entry.user_id).unwrap_or(0)
+                ),
+            }
+        },
+    )
+    .await?;
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !leave takes the viewer out of the queue
+ */
+pub async fn leave(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+
+    let left = cache::update_as(
+        &queue_key(channel_id),
+        &mut *ctx.redis.lock().await,
+        |x: &mut Option<Queue>| match x {
+            Some(x) => x.leave(invocation.tags.user_id),
+            None => false,
+        },
+    )
+    .await?;
+
+    let text = match left {
+        true => "You left the queue",
+        false => "You are not in the queue",
+    };
+    return ctx.reply(invocation, text).await;
+}
+
+/**
+ * !position the place of the viewer in the queue
+ */
+pub async fn position(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let queue = get_queue(invocation.tags.room_id, &mut *ctx.redis.lock().await).await?;
+
+    let text = match queue.position(invocation.tags.user_id) {
+        Some(x) => format!("You are at position {} of {}", x, queue.entries.len()),
+        None => String::from("You are not in the queue"),
+    };
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !next [n] takes the next viewers off the queue, moderators only
+ */
+pub async fn next(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let count = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(x) => match x.parse::<usize>() {
+            Ok(x) if (1..=MAX_NEXT).contains(&x) => x,
+            _ => {
+                return ctx
+                    .reply(invocation, &format!("Usage: !next [1-{}]", MAX_NEXT))
+                    .await
+            }
+        },
+        None => 1,
+    };
+
+    let channel_id = invocation.tags.room_id;
+    let (picked, left) = cache::update_as(
+        &queue_key(channel_id),
+        &mut *ctx.redis.lock().await,
+        |x: &mut Option<Queue>| match x {
+            Some(x) => (x.next(count), x.entries.len()),
+            None => (vec![], 0),
+        },
+    )
+    .await?;
+
+    if picked.is_empty() {
+        return ctx.reply(invocation, "The queue is empty").await;
+    }
+
+    let names = picked.iter().map(|x| format!("@{}", x.name)).collect::<Vec<String>>();
+    audit(
+        invocation,
+        "queue.next",
+        &names.join(" "),
+        json!({ "users": picked.iter().map(|x| x.user_id).collect::<Vec<i32>>() }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .say(
+            &invocation.channel_name,
+            &format!("Up next: {}, {} left in the queue", names.join(" "), left),
+        )
+        .await;
+}
+
+/**
+ * !queue open|close|clear, moderators only, !queue alone shows who is waiting
+ */
+pub async fn handle_queue(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let action = invocation
+        .args
+        .iter()
+        .find(|x| !x.is_empty())
+        .map(|x| x.to_lowercase());
+
+    if action.is_none() {
+        let queue = get_queue(channel_id, &mut *ctx.redis.lock().await).await?;
+        let state = match queue.open {
+            true => "open, type !join to join",
+            false => "closed",
+        };
+        if queue.entries.is_empty() {
+            return ctx
+                .reply(invocation, &format!("The queue is {} and empty", state))
+                .await;
+        }
+
+        let names = queue
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("{}. {}", i + 1, x.name))
+            .collect::<Vec<String>>();
+        return ctx
+            .reply(
+                invocation,
+                &format!(
+                    "The queue is {}: {}",
+                    state,
+                    join_limited(&names, MAX_LIST_LENGTH)
+                ),
+            )
+            .await;
+    }
+
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let text = match action.as_deref() {
+        Some("open") => "The queue is open, type !join to join",
+        Some("close") => "The queue is closed",
+        Some("clear") => "The queue was cleared",
+        _ => return ctx.reply(invocation, "Usage: !queue open|close|clear").await,
+    };
+    cache::update_as(
+        &queue_key(channel_id),
+        &mut *ctx.redis.lock().await,
+        |x: &mut Option<Queue>| {
+            let queue = x.get_or_insert_with(Queue::default);
+            match action.as_deref() {
+                Some("open") => queue.open = true,
+                Some("close") => queue.open = false,
+                _ => queue.entries.clear(),
+            }
+        },
+    )
+    .await?;
+
+    audit(
+        invocation,
+        &format!("queue.{}", action.unwrap_or_default()),
+        &invocation.channel_name,
+        json!({}),
+        ctx,
+    )
+    .await?;
+
+    return ctx.say(&invocation.channel_name, text).await;
+}
@@ -0,0 +1,239 @@
+use anyhow::{Error, Result};
+use database::entity::quote as quote_entity;
+use database::entity::sea_orm_active_enums::Permission;
+use database::sea_orm::ActiveValue;
+use serde_json::json;
+
+use crate::builtins::{audit, ensure_user};
+use crate::builtins::management::join_limited;
+use crate::context::{BotContext, Invocation};
+use crate::lookup::current_stream;
+use crate::permission::has_permission;
+
+const MAX_SEARCH_RESULTS: u64 = 10;
+const MAX_LIST_LENGTH: usize = 200;
+
+/**
+ * Split `[@user] text` into the quoted user and the quote, without a user
+ * the broadcaster is quoted
+ */
+pub fn parse_quote(args: &[String], channel_name: &str) -> Option<(String, String)> {
+    let mut args = args.iter().filter(|x| !x.is_empty()).peekable();
+
+    let user = match args.peek() {
+        Some(x) if x.starts_with('@') && x.len() > 1 => {
+            let user = x.trim_start_matches('@').to_string();
+            args.next();
+            user
+        }
+        _ => String::from(channel_name),
+    };
+    let text = args.map(|x| x.as_str()).collect::<Vec<&str>>().join(" ");
+    let text = text.trim().trim_matches('"').trim();
+
+    if text.is_empty() {
+        return None;
+    }
+
+    return Some((user, String::from(text)));
+}
+
+/**
+ * Format a quote for chat like `#42: "text" - user [game] (2024-01-31)`
+ */
+pub fn format_quote(quote: &quote_entity::Model) -> String {
+    let mut text = format!("#{}: \"{}\"", quote.number, quote.text);
+
+    if let Some(user) = &quote.quoted_user {
+        text.push_str(&format!(" - {}", user));
+    }
+    if let Some(game) = quote.game.as_ref().filter(|x| !x.is_empty()) {
+        text.push_str(&format!(" [{}]", game));
+    }
+    text.push_str(&format!(" ({})", quote.created_at.format("%Y-%m-%d")));
+
+    return text;
+}
+
+fn quote_details(quote: &quote_entity::Model) -> serde_json::Value {
+    return json!({
+        "number": quote.number,
+        "text": quote.text,
+        "quoted_user": quote.quoted_user,
+        "game": quote.game,
+        "added_by": quote.added_by,
+    });
+}
+
+/**
+ * !quote shows a random quote, !quote 42 a quote by number, !quote search
+ * text the first quote containing the text, !quote add and !quote del work
+ * like !addquote and !delquote
+ */
+pub async fn handle_quote(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let args = invocation
+        .args
+        .iter()
+        .filter(|x| !x.is_empty())
+        .cloned()
+        .collect::<Vec<String>>();
+    let first = args.first().map(|x| x.to_lowercase());
+
+    let quote = match first.as_deref() {
+        None => database::handler::quote::get_random_quote(channel_id, &ctx.db).await?,
+        Some("add") => return add_quote(&args[1..], invocation, ctx).await,
+        Some("del") | Some("delete") | Some("remove") => {
+            return delete_quote(&args[1..], invocation, ctx).await
+        }
+        Some("search") => return search_quote(&args[1..], invocation, ctx).await,
+        Some(x) => match x.trim_start_matches('#').parse::<i32>() {
+            Ok(number) => database::handler::quote::get_quote(channel_id, number, &ctx.db).await?,
+            Err(_) => return search_quote(&args, invocation, ctx).await,
+        },
+    };
+
+    let text = match (quote, first) {
+        (Some(x), _) => format_quote(&x),
+        (None, None) => String::from("There are no quotes yet"),
+        (None, Some(x)) => format!("Quote {} does not exist", x),
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !quote search text
+ */
+async fn search_quote(args: &[String], invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let text = args
+        .iter()
+        .filter(|x| !x.is_empty())
+        .map(|x| x.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    if text.is_empty() {
+        return ctx.reply(invocation, "Usage: !quote search text").await;
+    }
+
+    let quotes = database::handler::quote::search_quotes(
+        invocation.tags.room_id,
+        &text,
+        MAX_SEARCH_RESULTS,
+        &ctx.db,
+    )
+    .await?;
+
+    let text = match quotes.split_first() {
+        None => format!("No quote contains \"{}\"", text),
+        Some((quote, [])) => format_quote(quote),
+        Some((quote, rest)) => {
+            let numbers = rest
+                .iter()
+                .map(|x| format!("#{}", x.number))
+                .collect::<Vec<String>>();
+            format!(
+                "{} | also {}",
+                format_quote(quote),
+                join_limited(&numbers, MAX_LIST_LENGTH)
+            )
+        }
+    };
+
+    return ctx.reply(invocation, &text).await;
+}
+
+/**
+ * !addquote [@user] text, moderators only
+ */
+pub async fn handle_add_quote(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    return add_quote(&invocation.args, invocation, ctx).await;
+}
+
+async fn add_quote(args: &[String], invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let (user, text) = match parse_quote(args, &invocation.channel_name) {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "Usage: !addquote [@user] text").await,
+    };
+    let channel_id = invocation.tags.room_id;
+    let game = current_stream(channel_id, &ctx.db, &ctx.redis)
+        .await?
+        .map(|x| x.game);
+
+    // the quote references the user who added it
+    ensure_user(invocation, ctx).await?;
+
+    let quote = quote_entity::ActiveModel {
+        channel_id: ActiveValue::Set(channel_id),
+        text: ActiveValue::Set(text),
+        quoted_user: ActiveValue::Set(Some(user)),
+        game: ActiveValue::Set(game),
+        added_by: ActiveValue::Set(invocation.tags.user_id),
+        ..Default::default()
+    };
+    let quote = database::handler::quote::create_quote(quote, &ctx.db).await?;
+
+    audit(
+        invocation,
+        "quote.add",
+        &format!("#{}", quote.number),
+        json!({ "after": quote_details(&quote) }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(invocation, &format!("Quote #{} added", quote.number))
+        .await;
+}
+
+/**
+ * !delquote number, moderators only
+ */
+pub async fn handle_delete_quote(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    return delete_quote(&invocation.args, invocation, ctx).await;
+}
+
+async fn delete_quote(args: &[String], invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let number = match args
+        .iter()
+        .find(|x| !x.is_empty())
+        .and_then(|x| x.trim_start_matches('#').parse::<i32>().ok())
+    {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "Usage: !delquote number").await,
+    };
+
+    let quote =
+        match database::handler::quote::get_quote(invocation.tags.room_id, number, &ctx.db).await? {
+            Some(x) => x,
+            None => {
+                return ctx
+                    .reply(invocation, &format!("Quote #{} does not exist", number))
+                    .await
+            }
+        };
+
+    database::handler::quote::delete_quote(&quote.id, &ctx.db).await?;
+
+    audit(
+        invocation,
+        "quote.delete",
+        &format!("#{}", quote.number),
+        json!({ "before": quote_details(&quote) }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(invocation, &format!("Quote #{} deleted", quote.number))
+        .await;
+}
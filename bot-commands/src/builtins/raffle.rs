@@ -0,0 +1,381 @@
+use anyhow::{Error, Result};
+use database::entity::raffle as raffle_entity;
+use database::entity::sea_orm_active_enums::{Permission, RaffleStatus};
+use database::sea_orm::ActiveValue;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::builtins::{audit, ensure_user};
+use crate::context::{BotContext, Invocation};
+use crate::lookup::{followed_at, watch_time};
+use crate::permission::has_permission;
+
+const OPEN_RAFFLE_TTL: usize = 60;
+const MAX_SUBSCRIBER_WEIGHT: i32 = 10;
+const USAGE: &str =
+    "Usage: !raffle start keyword [-followers] [-subs] [-watch=60] [-subluck=2] [-fresh], !raffle draw or !raffle cancel";
+
+/**
+ * How a raffle is entered and who may enter, given to !raffle start
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaffleOptions {
+    pub keyword: String,
+    pub followers_only: bool,
+    pub subscribers_only: bool,
+    pub min_watch_time: i32,
+    pub subscriber_weight: i32,
+    pub exclude_winners: bool,
+}
+
+/**
+ * The open raffle of a channel as cached in redis for every chat message
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct OpenRaffle {
+    id: String,
+    keyword: String,
+}
+
+fn open_raffle_key(channel_id: i32) -> String {
+    return format!("channel:{}:raffle", channel_id);
+}
+
+/**
+ * Parse `keyword [-followers] [-subs] [-watch=minutes] [-subluck=entries]
+ * [-fresh]`, the errors are meant to be shown in chat
+ */
+pub fn parse_raffle_options(args: &[String]) -> Result<RaffleOptions, String> {
+    let mut options = RaffleOptions {
+        keyword: String::new(),
+        followers_only: false,
+        subscribers_only: false,
+        min_watch_time: 0,
+        subscriber_weight: 1,
+        exclude_winners: false,
+    };
+
+    for arg in args.iter().filter(|x| !x.is_empty()) {
+        let flag = match arg.strip_prefix('-') {
+            Some(x) => x.to_lowercase(),
+            None => {
+                if !options.keyword.is_empty() {
+                    return Err(String::from("The keyword has to be a single word"));
+                }
+                options.keyword = arg.to_lowercase();
+                continue;
+            }
+        };
+        let (flag, value) = match flag.split_once('=') {
+            Some((flag, value)) => (String::from(flag), Some(String::from(value))),
+            None => (flag, None),
+        };
+
+        match (flag.as_str(), value) {
+            ("followers", None) => options.followers_only = true,
+            ("subs" | "subscribers", None) => options.subscribers_only = true,
+            ("fresh", None) => options.exclude_winners = true,
+            ("watch", Some(x)) => match x.trim_end_matches('m').parse::<i32>() {
+                Ok(x) if x >= 0 => options.min_watch_time = x,
+                _ => return Err(String::from("-watch needs minutes")),
+            },
+            ("subluck", Some(x)) => match x.parse::<i32>() {
+                Ok(x) if (1..=MAX_SUBSCRIBER_WEIGHT).contains(&x) => options.subscriber_weight = x,
+                _ => {
+                    return Err(format!(
+                        "-subluck needs entries between 1 and {}",
+                        MAX_SUBSCRIBER_WEIGHT
+                    ))
+                }
+            },
+            _ => return Err(format!("Unknown option -{}", flag)),
+        }
+    }
+
+    if options.keyword.is_empty() {
+        return Err(String::from(USAGE));
+    }
+
+    return Ok(options);
+}
+
+/**
+ * Get the open raffle of a channel, cached in redis as it is checked for
+ * every chat message
+ */
+async fn open_raffle(channel_id: i32, ctx: &BotContext) -> Result<Option<OpenRaffle>, Error> {
+    let key = open_raffle_key(channel_id);
+    if let Some(x) = cache::get_as::<Option<OpenRaffle>>(&key, &mut *ctx.redis.lock().await).await? {
+        return Ok(x);
+    }
+
+    let raffle = database::handler::raffle::get_open_raffle(channel_id, &ctx.db)
+        .await?
+        .map(|x| OpenRaffle {
+            id: x.id,
+            keyword: x.keyword,
+        });
+    cache::set_with_ttl(
+        &key,
+        &serde_json::to_string(&raffle)?,
+        OPEN_RAFFLE_TTL,
+        &mut *ctx.redis.lock().await,
+    )
+    .await?;
+
+    return Ok(raffle);
+}
+
+async fn forget_open_raffle(channel_id: i32, ctx: &BotContext) -> Result<(), Error> {
+    return cache::delete(&open_raffle_key(channel_id), &mut *ctx.redis.lock().await).await;
+}
+
+/**
+ * Check if a viewer may enter a raffle, returns the weight of the entry
+ */
+async fn entry_weight(
+    raffle: &raffle_entity::Model,
+    invocation: &Invocation,
+    ctx: &BotContext,
+) -> Result<Option<i32>, Error> {
+    let tags = &invocation.tags;
+    let channel_id = tags.room_id;
+    let subscriber = tags.subscriber || has_permission(tags, &Permission::Broadcaster);
+
+    if raffle.subscribers_only == 1 && !subscriber {
+        return Ok(None);
+    }
+    if raffle.followers_only == 1
+        && !has_permission(tags, &Permission::Broadcaster)
+        && followed_at(channel_id, tags.user_id, &ctx.db, &ctx.redis, &ctx.twitch)
+            .await?
+            .is_none()
+    {
+        return Ok(None);
+    }
+    if raffle.min_watch_time > 0
+        && watch_time(channel_id, tags.user_id, &ctx.db, &ctx.redis).await?
+            < raffle.min_watch_time as i64 * 60
+    {
+        return Ok(None);
+    }
+    if raffle.exclude_winners == 1
+        && database::handler::raffle::has_won(channel_id, tags.user_id, &ctx.db).await?
+    {
+        return Ok(None);
+    }
+
+    return Ok(Some(match subscriber {
+        true => raffle.subscriber_weight.max(1),
+        false => 1,
+    }));
+}
+
+/**
+ * Enter the viewer into the open raffle if the message is its keyword,
+ * returns false if the message is not an entry
+ */
+pub async fn handle_raffle_entry(invocation: &Invocation, ctx: &BotContext) -> Result<bool, Error> {
+    let channel_id = invocation.tags.room_id;
+    let open = match open_raffle(channel_id, ctx).await? {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+    if !invocation.text.trim().eq_ignore_ascii_case(&open.keyword) {
+        return Ok(false);
+    }
+
+    let raffle = match database::handler::raffle::get_raffle(&open.id, &ctx.db).await? {
+        Some(x) if x.status == RaffleStatus::Open => x,
+        _ => {
+            forget_open_raffle(channel_id, ctx).await?;
+            return Ok(true);
+        }
+    };
+    let weight = match entry_weight(&raffle, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(true),
+    };
+
+    ensure_user(invocation, ctx).await?;
+    database::handler::raffle::add_entry(&raffle.id, invocation.tags.user_id, weight, &ctx.db)
+        .await?;
+
+    return Ok(true);
+}
+
+/**
+ * !raffle start|draw|cancel, moderators only, !raffle alone shows the state
+ */
+pub async fn handle_raffle(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let mut args = invocation.args.iter().filter(|x| !x.is_empty());
+    let action = args.next().map(|x| x.to_lowercase());
+    let rest = args.cloned().collect::<Vec<String>>();
+    let channel_id = invocation.tags.room_id;
+
+    if action.is_none() {
+        let text = match database::handler::raffle::get_open_raffle(channel_id, &ctx.db).await? {
+            Some(x) => {
+                let entries = database::handler::raffle::get_entries(&x.id, &ctx.db).await?;
+                format!(
+                    "Type {} to enter the raffle, {} entered so far",
+                    x.keyword,
+                    entries.len()
+                )
+            }
+            None => String::from("There is no raffle running"),
+        };
+        return ctx.reply(invocation, &text).await;
+    }
+
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    return match action.as_deref() {
+        Some("start") => start_raffle(&rest, invocation, ctx).await,
+        Some("draw") => draw_raffle(invocation, ctx).await,
+        Some("cancel") => cancel_raffle(invocation, ctx).await,
+        _ => ctx.reply(invocation, USAGE).await,
+    };
+}
+
+async fn start_raffle(args: &[String], invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let options = match parse_raffle_options(args) {
+        Ok(x) => x,
+        Err(e) => return ctx.reply(invocation, &e).await,
+    };
+    let channel_id = invocation.tags.room_id;
+    if database::handler::raffle::get_open_raffle(channel_id, &ctx.db)
+        .await?
+        .is_some()
+    {
+        return ctx
+            .reply(invocation, "A raffle is already running, draw or cancel it first")
+            .await;
+    }
+
+    let mut seed = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut seed);
+
+    ensure_user(invocation, ctx).await?;
+    let raffle = raffle_entity::ActiveModel {
+        channel_id: ActiveValue::Set(channel_id),
+        keyword: ActiveValue::Set(String::from(&options.keyword)),
+        followers_only: ActiveValue::Set(options.followers_only as i8),
+        subscribers_only: ActiveValue::Set(options.subscribers_only as i8),
+        min_watch_time: ActiveValue::Set(options.min_watch_time),
+        subscriber_weight: ActiveValue::Set(options.subscriber_weight),
+        exclude_winners: ActiveValue::Set(options.exclude_winners as i8),
+        seed: ActiveValue::Set(seed.iter().map(|x| format!("{:02x}", x)).collect()),
+        started_by: ActiveValue::Set(invocation.tags.user_id),
+        ended_at: ActiveValue::Set(None),
+        ..Default::default()
+    };
+    let raffle = database::handler::raffle::create_raffle(raffle, &ctx.db).await?;
+    forget_open_raffle(channel_id, ctx).await?;
+
+    audit(
+        invocation,
+        "raffle.start",
+        &raffle.id,
+        json!({
+            "keyword": raffle.keyword,
+            "followers_only": options.followers_only,
+            "subscribers_only": options.subscribers_only,
+            "min_watch_time": options.min_watch_time,
+            "subscriber_weight": options.subscriber_weight,
+            "exclude_winners": options.exclude_winners,
+            "seed_hash": raffle.seed_hash,
+        }),
+        ctx,
+    )
+    .await?;
+
+    let mut rules: Vec<String> = Vec::new();
+    if options.followers_only {
+        rules.push(String::from("followers only"));
+    }
+    if options.subscribers_only {
+        rules.push(String::from("subscribers only"));
+    }
+    if options.min_watch_time > 0 {
+        rules.push(format!("{} minutes watched", options.min_watch_time));
+    }
+    if options.subscriber_weight > 1 {
+        rules.push(format!("subscribers get {} entries", options.subscriber_weight));
+    }
+    if options.exclude_winners {
+        rules.push(String::from("previous winners excluded"));
+    }
+    let rules = match rules.is_empty() {
+        true => String::new(),
+        false => format!(" ({})", rules.join(", ")),
+    };
+
+    return ctx
+        .say(
+            &invocation.channel_name,
+            &format!(
+                "A raffle started! Type {} to enter{}. Seed hash: {}",
+                raffle.keyword, rules, raffle.seed_hash
+            ),
+        )
+        .await;
+}
+
+async fn draw_raffle(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let raffle = match database::handler::raffle::get_latest_raffle(channel_id, &ctx.db).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "There is no raffle to draw").await,
+    };
+
+    let winner = database::handler::raffle::draw_winner(&raffle.id, &ctx.db).await?;
+    forget_open_raffle(channel_id, ctx).await?;
+
+    let winner = match winner {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "Nobody is left to draw").await,
+    };
+    let name = match database::handler::user::get_user(winner.user_id, &ctx.db).await? {
+        Some(x) => x.display_name,
+        None => winner.user_id.to_string(),
+    };
+
+    audit(
+        invocation,
+        "raffle.draw",
+        &raffle.id,
+        json!({ "draw_number": winner.draw_number, "winner": winner.user_id }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .say(
+            &invocation.channel_name,
+            &format!(
+                "{} won the raffle! Draw #{}, seed: {}",
+                name,
+                winner.draw_number.unwrap_or(1),
+                raffle.seed
+            ),
+        )
+        .await;
+}
+
+async fn cancel_raffle(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let channel_id = invocation.tags.room_id;
+    let raffle = match database::handler::raffle::get_open_raffle(channel_id, &ctx.db).await? {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "There is no raffle running").await,
+    };
+
+    database::handler::raffle::cancel_raffle(&raffle.id, &ctx.db).await?;
+    forget_open_raffle(channel_id, ctx).await?;
+    audit(invocation, "raffle.cancel", &raffle.id, json!({}), ctx).await?;
+
+    return ctx.reply(invocation, "The raffle was cancelled").await;
+}
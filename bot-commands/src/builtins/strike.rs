@@ -0,0 +1,301 @@
+use anyhow::{Error, Result};
+use chrono::{Duration, Utc};
+use database::entity::sea_orm_active_enums::{ModerationActionType, Permission};
+use database::entity::strike_settings as strike_settings_entity;
+use database::entity::user as user_entity;
+use database::handler::strike::{parse_ladder, strike_step, StrikeStep, DEFAULT_LADDER};
+use serde_json::json;
+
+use crate::builtins::audit;
+use crate::context::{BotContext, Invocation};
+use crate::enforcement::{moderate, Target};
+use crate::format::format_duration;
+use crate::permission::has_permission;
+
+/**
+ * Parse `user [reason]` as given to !warn
+ */
+pub fn parse_warn(args: &[String]) -> Option<(String, Option<String>)> {
+    let mut args = args.iter().filter(|x| !x.is_empty());
+
+    let user = args.next()?.trim_start_matches('@').to_lowercase();
+    let reason = args.map(|x| x.as_str()).collect::<Vec<&str>>().join(" ");
+    if user.is_empty() {
+        return None;
+    }
+
+    return match reason.is_empty() {
+        true => Some((user, None)),
+        false => Some((user, Some(reason))),
+    };
+}
+
+/**
+ * Describe a step of the ladder like `a 600s timeout`
+ */
+pub fn describe_step(step: &StrikeStep) -> String {
+    return match step {
+        StrikeStep::Warn => String::from("a warning"),
+        StrikeStep::Timeout(x) => format!("a {} timeout", format_duration(*x as i64)),
+        StrikeStep::Ban => String::from("a ban"),
+    };
+}
+
+fn ladder(settings: &strike_settings_entity::Model) -> Vec<StrikeStep> {
+    return match parse_ladder(&settings.ladder) {
+        Ok(x) if !x.is_empty() => x,
+        _ => {
+            println!(
+                "Invalid strike ladder of {}: {}",
+                settings.channel_id, settings.ladder
+            );
+            parse_ladder(DEFAULT_LADDER).unwrap_or_default()
+        }
+    };
+}
+
+/**
+ * Strikes given before this time have decayed
+ */
+fn decay_start(settings: &strike_settings_entity::Model) -> chrono::NaiveDateTime {
+    return (Utc::now() - Duration::hours(settings.decay_hours as i64)).naive_utc();
+}
+
+/**
+ * Find the user a moderator named, replies if there is none
+ */
+async fn find_user(
+    nick: &str,
+    invocation: &Invocation,
+    ctx: &BotContext,
+) -> Result<Option<user_entity::Model>, Error> {
+    let user = database::handler::user::get_user_by_nick(nick, &ctx.db).await?;
+    if user.is_none() {
+        ctx.reply(invocation, "I have never seen that user").await?;
+    }
+
+    return Ok(user);
+}
+
+/**
+ * The broadcaster and the moderators of a channel do not get strikes, their
+ * last message tells if they are a moderator
+ */
+async fn is_exempt(channel_id: i32, user_id: i32, ctx: &BotContext) -> Result<bool, Error> {
+    if user_id == channel_id {
+        return Ok(true);
+    }
+
+    let message = database::handler::chat::get_last_message(channel_id, user_id, &ctx.db).await?;
+    return Ok(match message {
+        Some(x) => x.moderator == 1 || x.admin == 1,
+        None => false,
+    });
+}
+
+/**
+ * !warn user [reason] gives a strike and takes the next step of the ladder,
+ * moderators only
+ */
+pub async fn warn(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let (nick, reason) = match parse_warn(&invocation.args) {
+        Some(x) => x,
+        None => return ctx.reply(invocation, "Usage: !warn user [reason]").await,
+    };
+    let user = match find_user(&nick, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let channel_id = invocation.tags.room_id;
+    if is_exempt(channel_id, user.id, ctx).await? {
+        return ctx
+            .reply(
+                invocation,
+                "The broadcaster and moderators do not get strikes",
+            )
+            .await;
+    }
+
+    let settings = database::handler::strike::get_strike_settings(channel_id, &ctx.db).await?;
+    database::handler::strike::add_strike(
+        channel_id,
+        user.id,
+        Some(invocation.tags.user_id),
+        reason.clone(),
+        &ctx.db,
+    )
+    .await?;
+    let strikes = database::handler::strike::get_active_strikes(
+        channel_id,
+        user.id,
+        decay_start(&settings),
+        &ctx.db,
+    )
+    .await?
+    .len();
+    let step = strike_step(&ladder(&settings), strikes);
+    let reason = reason.unwrap_or_else(|| String::from("no reason given"));
+
+    let target = Target {
+        channel_id,
+        user_id: user.id,
+        nick: None,
+        display_name: None,
+        msg_id: None,
+    };
+    let moderator_id = Some(invocation.tags.user_id);
+    let outcome = match step {
+        StrikeStep::Warn => String::from("this is a warning"),
+        StrikeStep::Timeout(x) => {
+            moderate(
+                &target,
+                ModerationActionType::Timeout,
+                Some(x),
+                &reason,
+                moderator_id,
+                ctx,
+            )
+            .await?;
+            format!("timed out for {}", format_duration(x as i64))
+        }
+        StrikeStep::Ban => {
+            moderate(
+                &target,
+                ModerationActionType::Ban,
+                None,
+                &reason,
+                moderator_id,
+                ctx,
+            )
+            .await?;
+            String::from("banned")
+        }
+    };
+
+    audit(
+        invocation,
+        "strike.warn",
+        &user.nick,
+        json!({ "reason": reason, "strikes": strikes, "step": format!("{:?}", step) }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .say(
+            &invocation.channel_name,
+            &format!(
+                "@{} strike {}, {}: {}",
+                user.display_name, strikes, outcome, reason
+            ),
+        )
+        .await;
+}
+
+/**
+ * !strikes user shows the active strikes of a viewer, moderators only
+ */
+pub async fn strikes(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let nick = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(x) => x.trim_start_matches('@').to_lowercase(),
+        None => return ctx.reply(invocation, "Usage: !strikes user").await,
+    };
+    let user = match find_user(&nick, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let channel_id = invocation.tags.room_id;
+
+    let settings = database::handler::strike::get_strike_settings(channel_id, &ctx.db).await?;
+    let strikes = database::handler::strike::get_active_strikes(
+        channel_id,
+        user.id,
+        decay_start(&settings),
+        &ctx.db,
+    )
+    .await?;
+    let next = describe_step(&strike_step(&ladder(&settings), strikes.len() + 1));
+
+    let latest = match strikes.first() {
+        Some(x) => format!(
+            ", latest {} ago: {}",
+            format_duration((Utc::now().naive_utc() - x.created_at).num_seconds()),
+            x.reason.as_deref().unwrap_or("no reason given")
+        ),
+        None => String::new(),
+    };
+
+    return ctx
+        .reply(
+            invocation,
+            &format!(
+                "{} has {} active strikes, the next one is {}{}",
+                user.display_name,
+                strikes.len(),
+                next,
+                latest
+            ),
+        )
+        .await;
+}
+
+/**
+ * !pardon user clears the active strikes of a viewer, moderators only
+ */
+pub async fn pardon(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let nick = match invocation.args.iter().find(|x| !x.is_empty()) {
+        Some(x) => x.trim_start_matches('@').to_lowercase(),
+        None => return ctx.reply(invocation, "Usage: !pardon user").await,
+    };
+    let user = match find_user(&nick, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let channel_id = invocation.tags.room_id;
+
+    let settings = database::handler::strike::get_strike_settings(channel_id, &ctx.db).await?;
+    let pardoned = database::handler::strike::pardon_strikes(
+        channel_id,
+        user.id,
+        decay_start(&settings),
+        invocation.tags.user_id,
+        &ctx.db,
+    )
+    .await?;
+    if pardoned == 0 {
+        return ctx
+            .reply(
+                invocation,
+                &format!("{} has no active strikes", user.display_name),
+            )
+            .await;
+    }
+
+    audit(
+        invocation,
+        "strike.pardon",
+        &user.nick,
+        json!({ "strikes": pardoned }),
+        ctx,
+    )
+    .await?;
+
+    return ctx
+        .reply(
+            invocation,
+            &format!("Pardoned {} strikes of {}", pardoned, user.display_name),
+        )
+        .await;
+}
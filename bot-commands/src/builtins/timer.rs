@@ -0,0 +1,292 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::Permission;
+use database::entity::timer as timer_entity;
+use database::sea_orm::ActiveValue;
+use serde_json::json;
+
+use crate::builtins::audit;
+use crate::builtins::management::{join_limited, normalize_name};
+use crate::context::{BotContext, Invocation};
+use crate::permission::has_permission;
+
+const MAX_INTERVAL: i32 = 24 * 60;
+const MAX_LIST_LENGTH: usize = 450;
+const USAGE: &str = "Usage: !timer add|edit|del|on|off|move|list name [minutes] [-lines=5] [body]";
+
+/**
+ * A parsed !timer subcommand
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimerAction {
+    Add {
+        name: String,
+        interval: i32,
+        min_lines: Option<i32>,
+        body: String,
+    },
+    Edit {
+        name: String,
+        interval: Option<i32>,
+        min_lines: Option<i32>,
+        body: Option<String>,
+    },
+    Delete {
+        name: String,
+    },
+    Enable {
+        name: String,
+        enabled: bool,
+    },
+    Move {
+        name: String,
+        position: usize,
+    },
+    List,
+}
+
+fn parse_interval(value: &str) -> Result<i32, String> {
+    return match value.trim_end_matches('m').parse::<i32>() {
+        Ok(x) if (1..=MAX_INTERVAL).contains(&x) => Ok(x),
+        _ => Err(format!("The interval needs minutes between 1 and {}", MAX_INTERVAL)),
+    };
+}
+
+fn parse_lines(value: &str) -> Result<i32, String> {
+    return match value.parse::<i32>() {
+        Ok(x) if x >= 0 => Ok(x),
+        _ => Err(String::from("-lines needs a number of chat lines")),
+    };
+}
+
+/**
+ * Parse `!timer add name minutes [-lines=5] body`, `!timer edit name
+ * [-interval=10] [-lines=5] [body]`, `!timer del|on|off name`, `!timer move
+ * name position` and `!timer list`, the errors are meant to be shown in chat
+ */
+pub fn parse_timer_action(args: &[String]) -> Result<TimerAction, String> {
+    let mut args = args.iter().filter(|x| !x.is_empty()).peekable();
+
+    let action = match args.next() {
+        Some(x) => x.to_lowercase(),
+        None => return Err(String::from(USAGE)),
+    };
+    if action == "list" {
+        return Ok(TimerAction::List);
+    }
+
+    let name = match args.next() {
+        Some(x) => normalize_name(x)?,
+        None => return Err(String::from(USAGE)),
+    };
+
+    let interval = match action.as_str() {
+        "add" => match args.next() {
+            Some(x) => Some(parse_interval(x)?),
+            None => return Err(String::from(USAGE)),
+        },
+        _ => None,
+    };
+
+    let mut edit_interval: Option<i32> = None;
+    let mut min_lines: Option<i32> = None;
+    while let Some(arg) = args.peek() {
+        let (flag, value) = match arg.strip_prefix('-').and_then(|x| x.split_once('=')) {
+            Some(x) => x,
+            None => break,
+        };
+
+        match flag.to_lowercase().as_str() {
+            "lines" => min_lines = Some(parse_lines(value)?),
+            "interval" => edit_interval = Some(parse_interval(value)?),
+            _ => return Err(format!("Unknown option -{}", flag)),
+        }
+
+        args.next();
+    }
+    let rest = args.map(|x| x.as_str()).collect::<Vec<&str>>().join(" ");
+
+    return match action.as_str() {
+        "add" if !rest.is_empty() => Ok(TimerAction::Add {
+            name,
+            interval: edit_interval.or(interval).unwrap_or(MAX_INTERVAL),
+            min_lines,
+            body: rest,
+        }),
+        "edit" if edit_interval.is_some() || min_lines.is_some() || !rest.is_empty() => {
+            Ok(TimerAction::Edit {
+                name,
+                interval: edit_interval,
+                min_lines,
+                body: match rest.is_empty() {
+                    true => None,
+                    false => Some(rest),
+                },
+            })
+        }
+        "del" | "delete" | "remove" => Ok(TimerAction::Delete { name }),
+        "on" | "enable" => Ok(TimerAction::Enable {
+            name,
+            enabled: true,
+        }),
+        "off" | "disable" => Ok(TimerAction::Enable {
+            name,
+            enabled: false,
+        }),
+        "move" => match rest.parse::<usize>() {
+            Ok(x) if x > 0 => Ok(TimerAction::Move {
+                name,
+                position: x,
+            }),
+            _ => Err(String::from("Usage: !timer move name position")),
+        },
+        _ => Err(String::from(USAGE)),
+    };
+}
+
+fn timer_details(timer: &timer_entity::Model) -> serde_json::Value {
+    return json!({
+        "name": timer.name,
+        "body": timer.body,
+        "interval": timer.interval,
+        "min_lines": timer.min_lines,
+        "position": timer.position,
+        "enabled": timer.enabled == 1,
+    });
+}
+
+/**
+ * !timer manages the timed announcements of a channel, moderators only
+ */
+pub async fn handle_timer(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    if !has_permission(&invocation.tags, &Permission::Moderator) {
+        return Ok(());
+    }
+
+    let action = match parse_timer_action(&invocation.args) {
+        Ok(x) => x,
+        Err(e) => return ctx.reply(invocation, &e).await,
+    };
+    let channel_id = invocation.tags.room_id;
+
+    let name = match &action {
+        TimerAction::List => {
+            let timers = database::handler::timer::get_timers(channel_id, &ctx.db).await?;
+            if timers.is_empty() {
+                return ctx.reply(invocation, "There are no timers yet").await;
+            }
+
+            let names = timers
+                .iter()
+                .map(|x| match x.enabled == 1 {
+                    true => format!("{} ({}m)", x.name, x.interval),
+                    false => format!("{} (off)", x.name),
+                })
+                .collect::<Vec<String>>();
+
+            return ctx
+                .reply(invocation, &format!("Timers: {}", join_limited(&names, MAX_LIST_LENGTH)))
+                .await;
+        }
+        TimerAction::Add { name, .. }
+        | TimerAction::Edit { name, .. }
+        | TimerAction::Delete { name }
+        | TimerAction::Enable { name, .. }
+        | TimerAction::Move { name, .. } => String::from(name),
+    };
+
+    let existing = database::handler::timer::get_timer(channel_id, &name, &ctx.db).await?;
+    let before = match (existing, &action) {
+        (Some(_), TimerAction::Add { .. }) => {
+            return ctx
+                .reply(invocation, &format!("Timer {} already exists", name))
+                .await
+        }
+        (None, TimerAction::Add { .. }) => None,
+        (None, _) => {
+            return ctx
+                .reply(invocation, &format!("Timer {} does not exist", name))
+                .await
+        }
+        (Some(x), _) => Some(x),
+    };
+
+    let (audit_action, text) = match (action, before.clone()) {
+        (
+            TimerAction::Add {
+                interval,
+                min_lines,
+                body,
+                ..
+            },
+            _,
+        ) => {
+            let timer = timer_entity::ActiveModel {
+                channel_id: ActiveValue::Set(channel_id),
+                name: ActiveValue::Set(String::from(&name)),
+                body: ActiveValue::Set(body),
+                interval: ActiveValue::Set(interval),
+                min_lines: ActiveValue::Set(min_lines.unwrap_or(0)),
+                enabled: ActiveValue::Set(true as i8),
+                ..Default::default()
+            };
+            database::handler::timer::create_timer(timer, &ctx.db).await?;
+            ("timer.add", format!("Timer {} added", name))
+        }
+        (
+            TimerAction::Edit {
+                interval,
+                min_lines,
+                body,
+                ..
+            },
+            Some(before),
+        ) => {
+            let mut timer: timer_entity::ActiveModel = before.into();
+            if let Some(x) = interval {
+                timer.interval = ActiveValue::Set(x);
+            }
+            if let Some(x) = min_lines {
+                timer.min_lines = ActiveValue::Set(x);
+            }
+            if let Some(x) = body {
+                timer.body = ActiveValue::Set(x);
+            }
+            database::handler::timer::update_timer(timer, &ctx.db).await?;
+            ("timer.edit", format!("Timer {} updated", name))
+        }
+        (TimerAction::Delete { .. }, Some(before)) => {
+            database::handler::timer::delete_timer(&before.id, &ctx.db).await?;
+            ("timer.delete", format!("Timer {} deleted", name))
+        }
+        (TimerAction::Enable { enabled, .. }, Some(before)) => {
+            let mut timer: timer_entity::ActiveModel = before.into();
+            timer.enabled = ActiveValue::Set(enabled as i8);
+            database::handler::timer::update_timer(timer, &ctx.db).await?;
+            match enabled {
+                true => ("timer.enable", format!("Timer {} enabled", name)),
+                false => ("timer.disable", format!("Timer {} disabled", name)),
+            }
+        }
+        (TimerAction::Move { position, .. }, Some(before)) => {
+            database::handler::timer::move_timer(channel_id, &before.id, position - 1, &ctx.db)
+                .await?;
+            ("timer.move", format!("Timer {} moved to position {}", name, position))
+        }
+        _ => return Ok(()),
+    };
+
+    let after = database::handler::timer::get_timer(channel_id, &name, &ctx.db).await?;
+    audit(
+        invocation,
+        audit_action,
+        &name,
+        json!({
+            "before": before.as_ref().map(timer_details),
+            "after": after.as_ref().map(timer_details),
+        }),
+        ctx,
+    )
+    .await?;
+
+    return ctx.reply(invocation, &text).await;
+}
@@ -0,0 +1,58 @@
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use database::sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/**
+ * Something a channel has a list of in the db, like its spam filters
+ */
+#[async_trait]
+pub trait ChannelLoad: Sized + Send + Sync {
+    async fn load(channel_id: i32, db: &DatabaseConnection) -> Result<Vec<Self>, Error>;
+}
+
+type Items<T> = Arc<Vec<T>>;
+
+/**
+ * A list per channel, reloaded from the db once it is older than the ttl or
+ * forgotten after a change so changes show up without a restart
+ */
+pub struct ChannelCache<T> {
+    ttl: Duration,
+    channels: Mutex<HashMap<i32, (Instant, Items<T>)>>,
+}
+
+impl<T: ChannelLoad> ChannelCache<T> {
+    pub fn new(ttl: Duration) -> ChannelCache<T> {
+        return ChannelCache {
+            ttl,
+            channels: Mutex::new(HashMap::new()),
+        };
+    }
+
+    pub async fn get(&self, channel_id: i32, db: &DatabaseConnection) -> Result<Items<T>, Error> {
+        let mut channels = self.channels.lock().await;
+        if let Some((loaded, items)) = channels.get(&channel_id) {
+            if loaded.elapsed() < self.ttl {
+                return Ok(Arc::clone(items));
+            }
+        }
+
+        let items = Arc::new(T::load(channel_id, db).await?);
+
+        channels.insert(channel_id, (Instant::now(), Arc::clone(&items)));
+
+        return Ok(items);
+    }
+
+    /**
+     * Reload the list of a channel on its next use
+     */
+    pub async fn forget(&self, channel_id: i32) {
+        self.channels.lock().await.remove(&channel_id);
+    }
+}
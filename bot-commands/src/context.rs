@@ -0,0 +1,88 @@
+use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
+use database::entity::spam_filter as spam_filter_entity;
+use database::sea_orm::DatabaseConnection;
+use parser::privmsg_tag::PrivMsgTags;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use websocket::pool::ConnectionPool;
+
+use crate::channel_cache::ChannelCache;
+use crate::phrases::CompiledPhrase;
+use crate::responder::CompiledResponder;
+use crate::template::Templates;
+use crate::variables::TwitchCredentials;
+
+/**
+ * A chat command sent by a user
+ */
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    pub tags: PrivMsgTags,
+    pub nick: String,
+    pub channel_name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub text: String,
+}
+
+/**
+ * Everything the command handlers need to answer a message
+ */
+pub struct BotContext {
+    pub db: DatabaseConnection,
+    pub redis: Arc<Mutex<Connection>>,
+    pub pool: Arc<Mutex<ConnectionPool>>,
+    pub templates: Templates,
+    pub responders: ChannelCache<CompiledResponder>,
+    pub spam_filters: ChannelCache<spam_filter_entity::Model>,
+    pub banned_phrases: ChannelCache<CompiledPhrase>,
+    pub twitch: TwitchCredentials,
+    pub bot_id: i32,
+}
+
+impl BotContext {
+    /**
+     * Send a chat message to a channel
+     */
+    pub async fn say(&self, channel: &str, text: &str) -> Result<(), Error> {
+        return self.pool.lock().await.send_privmsg(channel, text);
+    }
+
+    /**
+     * Answer a chat command as a reply to it
+     */
+    pub async fn reply(&self, invocation: &Invocation, text: &str) -> Result<(), Error> {
+        return self
+            .pool
+            .lock()
+            .await
+            .reply(&invocation.channel_name, &invocation.tags.id, text);
+    }
+
+    /**
+     * Get a valid token of the bot
+     */
+    pub async fn bot_token(&self) -> Result<String, Error> {
+        let mut redis = self.redis.lock().await;
+
+        return auth::token::get_bot_token(
+            &self.twitch.bot_name,
+            &self.twitch.client_id,
+            &self.twitch.client_secret,
+            &self.db,
+            &mut redis,
+        )
+        .await;
+    }
+
+    /**
+     * Whisper a user as the bot
+     */
+    pub async fn whisper(&self, user_id: i32, text: &str) -> Result<(), Error> {
+        let token = self.bot_token().await?;
+
+        return twitch_api::send_whisper(&self.twitch.client_id, &token, self.bot_id, user_id, text)
+            .await;
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
+
+/**
+ * The cooldowns of something that answers in chat, `key` is the redis key
+ * prefix of it like `command:{id}`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cooldown {
+    pub key: String,
+    pub global: i32,
+    pub user: i32,
+}
+
+impl Cooldown {
+    fn global_key(&self) -> String {
+        return format!("{}:cooldown", self.key);
+    }
+
+    fn user_key(&self, user_id: i32) -> String {
+        return format!("{}:cooldown:{}", self.key, user_id);
+    }
+
+    fn whisper_key(&self, user_id: i32) -> String {
+        return format!("{}:cooldown_whisper:{}", self.key, user_id);
+    }
+}
+
+/**
+ * Get the seconds until the user may use it again, None if it is ready
+ */
+pub async fn remaining_cooldown(
+    cooldown: &Cooldown,
+    user_id: i32,
+    redis: &mut Connection,
+) -> Result<Option<usize>, Error> {
+    let mut remaining: Option<usize> = None;
+
+    if cooldown.global > 0 {
+        remaining = cache::ttl(&cooldown.global_key(), redis).await?;
+    }
+    if cooldown.user > 0 {
+        if let Some(x) = cache::ttl(&cooldown.user_key(user_id), redis).await? {
+            remaining = Some(remaining.unwrap_or(0).max(x));
+        }
+    }
+
+    return Ok(remaining);
+}
+
+/**
+ * Start the cooldowns, returns false if one of them is running, both are set
+ * at once so a running user cooldown does not start the global one
+ */
+pub async fn start_cooldown(
+    cooldown: &Cooldown,
+    user_id: i32,
+    redis: &mut Connection,
+) -> Result<bool, Error> {
+    let global_key = cooldown.global_key();
+    let user_key = cooldown.user_key(user_id);
+
+    let mut keys: Vec<(&str, &str, usize)> = Vec::new();
+    if cooldown.global > 0 {
+        keys.push((&global_key, "1", cooldown.global as usize));
+    }
+    if cooldown.user > 0 {
+        keys.push((&user_key, "1", cooldown.user as usize));
+    }
+    if keys.is_empty() {
+        return Ok(true);
+    }
+
+    return cache::set_all_if_absent_with_ttl(&keys, redis).await;
+}
+
+/**
+ * Claim the cooldown whisper of a user until the cooldown ends, returns false
+ * if they were already told about it
+ */
+pub async fn claim_cooldown_whisper(
+    cooldown: &Cooldown,
+    user_id: i32,
+    remaining: usize,
+    redis: &mut Connection,
+) -> Result<bool, Error> {
+    return cache::set_if_absent_with_ttl(
+        &cooldown.whisper_key(user_id),
+        "1",
+        remaining.max(1),
+        redis,
+    )
+    .await;
+}
@@ -0,0 +1,141 @@
+use anyhow::{Error, Result};
+use chrono::Utc;
+use database::entity::sea_orm_active_enums::ModerationActionType;
+use database::entity::user as user_entity;
+use database::handler::moderation::NewModerationAction;
+use database::sea_orm::ActiveValue;
+
+use crate::context::{BotContext, Invocation};
+
+/**
+ * Who a moderation action is taken against, the message is only known when
+ * the action answers a message. The names are known for a target taken from
+ * chat, whose user may not be saved yet
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub channel_id: i32,
+    pub user_id: i32,
+    pub nick: Option<String>,
+    pub display_name: Option<String>,
+    pub msg_id: Option<String>,
+}
+
+/**
+ * Delete the message of a target, time the target out or ban them as the bot
+ * and record the action with its reason, without a moderator the action
+ * counts as automated and gives the target a strike
+ */
+pub async fn moderate(
+    target: &Target,
+    action: ModerationActionType,
+    duration: Option<i32>,
+    reason: &str,
+    moderator_id: Option<i32>,
+    ctx: &BotContext,
+) -> Result<(), Error> {
+    let token = ctx.bot_token().await?;
+    let client_id = &ctx.twitch.client_id;
+
+    let duration = match action {
+        ModerationActionType::Delete => {
+            let msg_id = match &target.msg_id {
+                Some(x) => x,
+                None => return Err(Error::msg("No message to delete")),
+            };
+            twitch_api::delete_chat_message(client_id, &token, ctx.bot_id, target.channel_id, msg_id)
+                .await?;
+            None
+        }
+        ModerationActionType::Timeout => {
+            let duration = duration.unwrap_or(1).max(1);
+            twitch_api::ban_user(
+                client_id,
+                &token,
+                ctx.bot_id,
+                target.channel_id,
+                target.user_id,
+                Some(duration),
+                reason,
+            )
+            .await?;
+            Some(duration)
+        }
+        ModerationActionType::Ban => {
+            twitch_api::ban_user(
+                client_id,
+                &token,
+                ctx.bot_id,
+                target.channel_id,
+                target.user_id,
+                None,
+                reason,
+            )
+            .await?;
+            None
+        }
+        ModerationActionType::Clear => return Err(Error::msg("The bot does not clear chat")),
+    };
+
+    if let Some(nick) = &target.nick {
+        let user = user_entity::ActiveModel {
+            id: ActiveValue::Set(target.user_id),
+            nick: ActiveValue::Set(String::from(nick)),
+            display_name: ActiveValue::Set(String::from(
+                target.display_name.as_deref().unwrap_or(nick),
+            )),
+            ..Default::default()
+        };
+        database::handler::user::create_user(user, &ctx.db).await?;
+    }
+
+    database::handler::moderation::record_moderation_action(
+        NewModerationAction {
+            channel_id: target.channel_id,
+            target_user_id: Some(target.user_id),
+            action,
+            duration,
+            reason: Some(String::from(reason)),
+            msg_id: target.msg_id.clone(),
+            moderator_id: Some(moderator_id.unwrap_or(ctx.bot_id)),
+            automated: moderator_id.is_none(),
+            timestamp: Utc::now(),
+        },
+        &ctx.db,
+    )
+    .await?;
+
+    if moderator_id.is_none() {
+        database::handler::strike::add_strike(
+            target.channel_id,
+            target.user_id,
+            None,
+            Some(String::from(reason)),
+            &ctx.db,
+        )
+        .await?;
+    }
+
+    return Ok(());
+}
+
+/**
+ * Act on the message of an invocation and its sender as the bot
+ */
+pub async fn enforce(
+    invocation: &Invocation,
+    action: ModerationActionType,
+    duration: Option<i32>,
+    reason: &str,
+    ctx: &BotContext,
+) -> Result<(), Error> {
+    let target = Target {
+        channel_id: invocation.tags.room_id,
+        user_id: invocation.tags.user_id,
+        nick: Some(String::from(&invocation.nick)),
+        display_name: Some(String::from(&invocation.tags.display_name)),
+        msg_id: Some(String::from(&invocation.tags.id)),
+    };
+
+    return moderate(&target, action, duration, reason, None, ctx).await;
+}
@@ -0,0 +1,40 @@
+/**
+ * Format seconds as a human friendly duration like "1 day 2 hours 5 minutes",
+ * at most the two largest units are shown
+ */
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let units = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ];
+
+    let mut rest = seconds;
+    let mut parts: Vec<String> = Vec::new();
+    for (size, name) in units.iter() {
+        let count = rest / size;
+        rest %= size;
+
+        if count > 0 {
+            parts.push(match count {
+                1 => format!("1 {}", name),
+                _ => format!("{} {}s", count, name),
+            });
+        } else if !parts.is_empty() {
+            break;
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+
+    if parts.is_empty() {
+        return String::from("0 seconds");
+    }
+
+    return parts.join(" ");
+}
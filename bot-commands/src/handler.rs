@@ -0,0 +1,217 @@
+use anyhow::{Error, Result};
+use chrono::Utc;
+use database::entity::sea_orm_active_enums::Permission;
+use parser::irc_parser::ParsedMessage;
+
+use crate::builtins::counter::handle_counter;
+use crate::builtins::handle_builtin;
+use crate::builtins::raffle::handle_raffle_entry;
+use crate::context::{BotContext, Invocation};
+use crate::cooldown::{claim_cooldown_whisper, remaining_cooldown, start_cooldown, Cooldown};
+use crate::format::format_duration;
+use crate::permission::has_permission;
+use crate::phrases::handle_banned_phrases;
+use crate::spam::handle_spam;
+use crate::template::TemplateContext;
+use crate::timers::count_line;
+
+/**
+ * A templated answer and the conditions under which it may be sent
+ */
+#[derive(Debug, Clone)]
+pub struct Response<'a> {
+    pub name: String,
+    pub body: &'a str,
+    pub permission: &'a Permission,
+    pub cooldown: Cooldown,
+    pub cooldown_whisper: bool,
+    pub count: i32,
+}
+
+/**
+ * Enforce the banned phrases and spam filters, then answer a chat message
+ * with a built-in, a counter, a custom command of the channel, a raffle entry
+ * or the first matching auto responder
+ */
+pub async fn handle_message(msg: &ParsedMessage, ctx: &BotContext) -> Result<(), Error> {
+    let text = match &msg.params {
+        Some(x) => String::from(x),
+        None => return Err(Error::msg("No message")),
+    };
+    let tags = match msg.privmsg_tags() {
+        Some(x) => x,
+        None => return Err(Error::msg("No tags")),
+    };
+    let channel_name = match msg.command.params.first() {
+        Some(x) => x.replace("#", ""),
+        None => return Err(Error::msg("No channel")),
+    };
+
+    let (command, args) = match &msg.chat_command {
+        Some(x) => (String::from(&x.command), x.params.to_vec()),
+        None => (String::new(), text.split(' ').map(|x| x.to_string()).collect()),
+    };
+    let invocation = Invocation {
+        tags,
+        nick: String::from(&msg.source.nick),
+        channel_name,
+        command,
+        args,
+        text,
+    };
+
+    if let Err(e) = count_line(invocation.tags.room_id, ctx).await {
+        println!("Failed to count chat line: {:?}", e);
+    }
+
+    if handle_banned_phrases(&invocation, ctx).await? {
+        return Ok(());
+    }
+    if handle_spam(&invocation, ctx).await? {
+        return Ok(());
+    }
+
+    if msg.chat_command.is_some() {
+        if handle_builtin(&invocation, ctx).await? {
+            return Ok(());
+        }
+        if handle_counter(&invocation, ctx).await? {
+            return Ok(());
+        }
+        if handle_custom_command(&invocation, ctx).await? {
+            return Ok(());
+        }
+    }
+
+    if handle_raffle_entry(&invocation, ctx).await? {
+        return Ok(());
+    }
+
+    return handle_auto_responders(&invocation, ctx).await;
+}
+
+/**
+ * Answer with the custom command of the channel, returns false if there is none
+ */
+async fn handle_custom_command(invocation: &Invocation, ctx: &BotContext) -> Result<bool, Error> {
+    let command = database::handler::command::get_command(
+        invocation.tags.room_id,
+        &invocation.command,
+        &ctx.db,
+    )
+    .await?;
+    let command = match command {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+
+    let response = Response {
+        name: format!("!{}", command.name),
+        body: &command.body,
+        permission: &command.permission,
+        cooldown: Cooldown {
+            key: format!("command:{}", command.id),
+            global: command.global_cooldown,
+            user: command.user_cooldown,
+        },
+        cooldown_whisper: command.cooldown_whisper == 1,
+        count: command.invoke_count + 1,
+    };
+
+    if respond(invocation, &response, ctx).await? {
+        database::handler::command::record_invocation(&command.id, Utc::now(), &ctx.db).await?;
+    }
+
+    return Ok(true);
+}
+
+/**
+ * Answer with the first enabled auto responder of the channel matching the message
+ */
+async fn handle_auto_responders(invocation: &Invocation, ctx: &BotContext) -> Result<(), Error> {
+    let responders = ctx
+        .responders
+        .get(invocation.tags.room_id, &ctx.db)
+        .await?;
+    let responder = match responders.iter().find(|x| x.matches(&invocation.text)) {
+        Some(x) => &x.model,
+        None => return Ok(()),
+    };
+
+    let response = Response {
+        name: String::from(&responder.name),
+        body: &responder.body,
+        permission: &responder.permission,
+        cooldown: Cooldown {
+            key: format!("auto_responder:{}", responder.id),
+            global: responder.global_cooldown,
+            user: responder.user_cooldown,
+        },
+        cooldown_whisper: false,
+        count: responder.invoke_count + 1,
+    };
+
+    if respond(invocation, &response, ctx).await? {
+        database::handler::auto_responder::record_invocation(&responder.id, Utc::now(), &ctx.db)
+            .await?;
+    }
+
+    return Ok(());
+}
+
+/**
+ * Render and send a response if the user has the permission and it is not
+ * on cooldown, returns if it was sent
+ */
+pub async fn respond(
+    invocation: &Invocation,
+    response: &Response<'_>,
+    ctx: &BotContext,
+) -> Result<bool, Error> {
+    let tags = &invocation.tags;
+    if !has_permission(tags, response.permission) {
+        return Ok(false);
+    }
+
+    // moderators skip the cooldowns but still start them for everyone else
+    let moderator = has_permission(tags, &Permission::Moderator);
+    let mut redis = ctx.redis.lock().await;
+    if !moderator {
+        if let Some(remaining) =
+            remaining_cooldown(&response.cooldown, tags.user_id, &mut redis).await?
+        {
+            let whisper = response.cooldown_whisper
+                && claim_cooldown_whisper(&response.cooldown, tags.user_id, remaining, &mut redis)
+                    .await?;
+            drop(redis);
+            if whisper {
+                let text = format!(
+                    "{} is on cooldown for {}",
+                    response.name,
+                    format_duration(remaining as i64)
+                );
+                ctx.whisper(tags.user_id, &text).await?;
+            }
+            return Ok(false);
+        }
+    }
+    let started = start_cooldown(&response.cooldown, tags.user_id, &mut redis).await?;
+    drop(redis);
+    if !started && !moderator {
+        return Ok(false);
+    }
+
+    let template_ctx = TemplateContext {
+        channel_id: tags.room_id,
+        channel_name: String::from(&invocation.channel_name),
+        user_id: tags.user_id,
+        user_name: String::from(&tags.display_name),
+        args: invocation.args.to_vec(),
+        count: response.count,
+    };
+    let reply = ctx.templates.render(response.body, &template_ctx).await;
+
+    ctx.say(&invocation.channel_name, &reply).await?;
+
+    return Ok(true);
+}
@@ -0,0 +1,130 @@
+use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
+use chrono::{DateTime, Utc};
+use database::sea_orm::DatabaseConnection;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::variables::TwitchCredentials;
+
+const WATCH_TIME_TTL: usize = 60;
+const STREAM_TTL: usize = 60;
+const FOLLOWER_TTL: usize = 10 * 60;
+
+/**
+ * What viewers may ask about the stream a channel is live with
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrentStream {
+    pub game: String,
+    pub title: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/**
+ * Get a value from redis or fetch and store it for ttl seconds, redis is
+ * not locked while fetching
+ */
+async fn cached<T, F, Fut>(
+    key: &str,
+    ttl: usize,
+    redis: &Arc<Mutex<Connection>>,
+    fetch: F,
+) -> Result<T, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let value = cache::get_as::<T>(key, &mut *redis.lock().await).await;
+    match value {
+        Ok(Some(x)) => return Ok(x),
+        Ok(None) => {}
+        Err(e) => println!("Failed to read {} from cache: {:?}", key, e),
+    }
+
+    let value = fetch().await?;
+    let json = serde_json::to_string(&value)?;
+    if let Err(e) = cache::set_with_ttl(key, &json, ttl, &mut *redis.lock().await).await {
+        println!("Failed to cache {}: {:?}", key, e);
+    }
+
+    return Ok(value);
+}
+
+/**
+ * Seconds a user watched a channel over all sessions, open ones count until now
+ */
+pub async fn watch_time(
+    channel_id: i32,
+    user_id: i32,
+    db: &DatabaseConnection,
+    redis: &Arc<Mutex<Connection>>,
+) -> Result<i64, Error> {
+    let key = format!("channel:{}:watchtime:{}", channel_id, user_id);
+
+    return cached(&key, WATCH_TIME_TTL, redis, || {
+        database::handler::watchtime::get_watch_time(channel_id, user_id, db)
+    })
+    .await;
+}
+
+/**
+ * The open stream of a channel, None if it is offline
+ */
+pub async fn current_stream(
+    channel_id: i32,
+    db: &DatabaseConnection,
+    redis: &Arc<Mutex<Connection>>,
+) -> Result<Option<CurrentStream>, Error> {
+    let key = format!("channel:{}:stream", channel_id);
+
+    return cached(&key, STREAM_TTL, redis, || async {
+        let stream = database::handler::stream_history::get_current_stream(channel_id, db).await?;
+
+        return Ok(stream.map(|x| CurrentStream {
+            game: x.game,
+            title: x.title,
+            started_at: x.started_at,
+        }));
+    })
+    .await;
+}
+
+/**
+ * When a user followed a channel, asks the twitch api with the bot token so
+ * the bot has to be a moderator of the channel
+ */
+pub async fn followed_at(
+    channel_id: i32,
+    user_id: i32,
+    db: &DatabaseConnection,
+    redis: &Arc<Mutex<Connection>>,
+    twitch: &TwitchCredentials,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    let key = format!("channel:{}:follower:{}", channel_id, user_id);
+
+    return cached(&key, FOLLOWER_TTL, redis, || async {
+        let token = auth::token::get_bot_token(
+            &twitch.bot_name,
+            &twitch.client_id,
+            &twitch.client_secret,
+            db,
+            &mut *redis.lock().await,
+        )
+        .await?;
+
+        let follower =
+            twitch_api::get_follower(&twitch.client_id, &token, channel_id, user_id).await?;
+
+        return match follower {
+            Some(x) => Ok(Some(
+                DateTime::parse_from_rfc3339(&x.followed_at)?.with_timezone(&Utc),
+            )),
+            None => Ok(None),
+        };
+    })
+    .await;
+}
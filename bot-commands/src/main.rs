@@ -0,0 +1,171 @@
+mod bans;
+mod builtins;
+mod channel_cache;
+mod context;
+mod cooldown;
+mod enforcement;
+mod format;
+mod handler;
+mod lookup;
+mod permission;
+mod phrases;
+mod responder;
+mod spam;
+mod template;
+mod timers;
+mod variables;
+
+#[cfg(test)]
+mod test;
+
+use anyhow::{Error, Result};
+use database::entity::bot as bot_entity;
+use dotenvy::dotenv;
+use parser::irc_parser::IRCCommandType;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use websocket::connection::{ConnectionConfig, ConnectionEvent};
+use websocket::pool::{ConnectionPool, PoolEvent};
+
+use crate::channel_cache::ChannelCache;
+use crate::context::BotContext;
+use crate::variables::TwitchCredentials;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    dotenv().ok();
+    let bot_name = std::env::var("TWITCH_BOT").expect("TWITCH_BOT not set");
+    let ws_endpoint = std::env::var("TWITCH_WS_ENDPOINT").expect("WS_ENDPOINT not set");
+    let db_endpoint = std::env::var("DATABASE_URL").expect("DB_URL not set");
+    let redis_endpoint = std::env::var("REDIS_URL").expect("REDIS_URL not set");
+    let client_id = std::env::var("TWITCH_CLIENT_ID").expect("CLIENT_ID not set");
+    let client_secret = std::env::var("TWITCH_CLIENT_SECRET").expect("CLIENT_SECRET not set");
+    let channels_per_connection = match std::env::var("TWITCH_CHANNELS_PER_CONNECTION") {
+        Ok(x) => x.parse::<usize>().expect("TWITCH_CHANNELS_PER_CONNECTION is not a number"),
+        Err(_) => 50,
+    };
+    let channel_sync_interval = match std::env::var("TWITCH_CHANNEL_SYNC_INTERVAL") {
+        Ok(x) => x.parse::<u64>().expect("TWITCH_CHANNEL_SYNC_INTERVAL is not a number"),
+        Err(_) => 300,
+    };
+    let timer_check_interval = match std::env::var("TIMER_CHECK_INTERVAL") {
+        Ok(x) => x.parse::<u64>().expect("TIMER_CHECK_INTERVAL is not a number"),
+        Err(_) => 30,
+    };
+    let poll_check_interval = match std::env::var("POLL_CHECK_INTERVAL") {
+        Ok(x) => x.parse::<u64>().expect("POLL_CHECK_INTERVAL is not a number"),
+        Err(_) => 5,
+    };
+
+    let db = match database::connect(&db_endpoint).await {
+        Ok(x) => x,
+        Err(_) => return Err(Error::msg("Failed to connect to database")),
+    };
+
+    let bot: bot_entity::Model = match database::handler::bot::get_bot(&bot_name, &db).await {
+        Ok(x) => x,
+        Err(_) => return Err(Error::msg("Failed to get bot")),
+    };
+
+    let redis = match cache::connect(&redis_endpoint).await {
+        Ok(x) => Arc::new(Mutex::new(x)),
+        Err(_) => return Err(Error::msg("Failed to connect to redis")),
+    };
+
+    let twitch = TwitchCredentials {
+        bot_name: String::from(&bot_name),
+        client_id: String::from(&client_id),
+        client_secret: String::from(&client_secret),
+    };
+    let templates = variables::default_templates(&db, &redis, &twitch);
+
+    let channels = match websocket::client::get_active_channel_names(&db).await {
+        Ok(x) => x,
+        Err(_) => return Err(Error::msg("Failed to get channels")),
+    };
+
+    let (pool, mut events) = ConnectionPool::new(
+        ConnectionConfig::new(
+            &ws_endpoint,
+            &bot_name,
+            &bot.nick,
+            &client_id,
+            &client_secret,
+            &redis_endpoint,
+        ),
+        db.clone(),
+        channels,
+        channels_per_connection,
+    );
+    let pool = Arc::new(Mutex::new(pool));
+    let _control = websocket::control::spawn(
+        Arc::clone(&pool),
+        &redis_endpoint,
+        db.clone(),
+        std::time::Duration::from_secs(channel_sync_interval),
+    );
+
+    let ctx = Arc::new(BotContext {
+        db: db.clone(),
+        redis,
+        pool,
+        templates,
+        responders: ChannelCache::new(std::time::Duration::from_secs(60)),
+        spam_filters: ChannelCache::new(std::time::Duration::from_secs(60)),
+        banned_phrases: ChannelCache::new(std::time::Duration::from_secs(60)),
+        twitch,
+        bot_id: bot.twitch_id,
+    });
+    let _timers = timers::spawn(
+        Arc::clone(&ctx),
+        std::time::Duration::from_secs(timer_check_interval),
+    );
+    let _polls = builtins::poll::spawn(
+        Arc::clone(&ctx),
+        std::time::Duration::from_secs(poll_check_interval),
+    );
+
+    while let Some(PoolEvent { connection, event }) = events.recv().await {
+        let message = match event {
+            ConnectionEvent::Message(x) => x,
+            ConnectionEvent::Connected => {
+                println!("Connection {} connected to twitch irc", connection);
+                continue;
+            }
+            ConnectionEvent::Disconnected(reason) => {
+                println!("Connection {} disconnected from twitch irc: {}", connection, reason);
+                continue;
+            }
+            ConnectionEvent::Reconnecting { attempt, delay } => {
+                println!(
+                    "Connection {} reconnecting to twitch irc in {:?} (attempt {})",
+                    connection, delay, attempt
+                );
+                continue;
+            }
+        };
+        let message = message.as_str();
+
+        let parsed_message = match parser::irc_parser::parse(message).await {
+            Ok(x) => x,
+            Err(e) => {
+                println!("Error parsing message: {}", message);
+                println!("Error: {:?}", e);
+                continue;
+            }
+        };
+
+        let result = match parsed_message.command.command {
+            IRCCommandType::PRIVMSG => handler::handle_message(&parsed_message, &ctx).await,
+            IRCCommandType::CLEARCHAT => bans::handle_clearchat(&parsed_message, &ctx).await,
+            _ => continue,
+        };
+
+        if let Err(e) = result {
+            println!("Error handling message: {}", message);
+            println!("Error: {:?}", e);
+        }
+    }
+
+    return Ok(());
+}
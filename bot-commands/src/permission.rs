@@ -0,0 +1,69 @@
+use database::entity::sea_orm_active_enums::Permission;
+use parser::privmsg_tag::PrivMsgTags;
+
+/**
+ * Rank of a permission level, higher levels include the lower ones
+ */
+fn rank(permission: &Permission) -> u8 {
+    return match permission {
+        Permission::Everyone => 0,
+        Permission::Subscriber => 1,
+        Permission::Vip => 2,
+        Permission::Moderator => 3,
+        Permission::Broadcaster => 4,
+    };
+}
+
+/**
+ * Get the highest permission level of the sender of a message
+ */
+pub fn user_permission(tags: &PrivMsgTags) -> Permission {
+    if tags.admin {
+        return Permission::Broadcaster;
+    }
+    if tags.moderator {
+        return Permission::Moderator;
+    }
+    if tags.vip {
+        return Permission::Vip;
+    }
+    if tags.subscriber {
+        return Permission::Subscriber;
+    }
+
+    return Permission::Everyone;
+}
+
+/**
+ * Check if the sender of a message may use something that requires the given level
+ */
+pub fn has_permission(tags: &PrivMsgTags, required: &Permission) -> bool {
+    return rank(&user_permission(tags)) >= rank(required);
+}
+
+/**
+ * Name of a permission level as used in chat
+ */
+pub fn permission_name(permission: &Permission) -> &'static str {
+    return match permission {
+        Permission::Everyone => "everyone",
+        Permission::Subscriber => "subscriber",
+        Permission::Vip => "vip",
+        Permission::Moderator => "moderator",
+        Permission::Broadcaster => "broadcaster",
+    };
+}
+
+/**
+ * Parse a permission level from chat, accepts the common short forms
+ */
+pub fn parse_permission(name: &str) -> Option<Permission> {
+    return match name.to_lowercase().as_str() {
+        "everyone" | "all" => Some(Permission::Everyone),
+        "subscriber" | "sub" => Some(Permission::Subscriber),
+        "vip" => Some(Permission::Vip),
+        "moderator" | "mod" => Some(Permission::Moderator),
+        "broadcaster" | "owner" => Some(Permission::Broadcaster),
+        _ => None,
+    };
+}
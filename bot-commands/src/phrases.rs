@@ -0,0 +1,234 @@
+use anyhow::{Error, Result};
+use chrono::Utc;
+use database::entity::banned_phrase as banned_phrase_entity;
+use database::entity::sea_orm_active_enums::{
+    BannedPhraseAction, BannedPhraseType, ModerationActionType, Permission,
+};
+use database::sea_orm::DatabaseConnection;
+use async_trait::async_trait;
+use regex::{Regex, RegexBuilder};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::channel_cache::ChannelLoad;
+use crate::context::{BotContext, Invocation};
+use crate::enforcement::enforce;
+use crate::permission::has_permission;
+use crate::spam::is_combining_mark;
+
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/**
+ * Latin letters that Cyrillic and Greek lookalikes stand in for
+ */
+fn fold_confusable(c: char) -> char {
+    return match c {
+        'а' | 'α' => 'a',
+        'в' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ё' | 'ε' => 'e',
+        'ɡ' => 'g',
+        'н' => 'h',
+        'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'ӏ' => 'l',
+        'м' => 'm',
+        'η' => 'n',
+        'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'υ' => 'u',
+        'ν' => 'v',
+        'ԝ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' => 'y',
+        _ => c,
+    };
+}
+
+fn is_invisible(c: char) -> bool {
+    return matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{FEFF}'
+            | '\u{E0000}'..='\u{E007F}'
+    );
+}
+
+/**
+ * Normalize a message before it is matched so lookalike characters do not
+ * dodge the banned phrases: NFKC folds full-width and styled letters,
+ * zero-width characters and stacked marks are dropped and Cyrillic and Greek
+ * lookalikes become Latin letters
+ */
+pub fn normalize_text(text: &str) -> String {
+    return text
+        .nfkc()
+        .filter(|x| !is_invisible(*x) && !is_combining_mark(*x))
+        .flat_map(|x| x.to_lowercase())
+        .map(fold_confusable)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+}
+
+/**
+ * Normalize the literal characters of a regex like the messages, ascii is
+ * kept as is so escapes like `\W` keep their meaning
+ */
+pub fn normalize_regex(pattern: &str) -> String {
+    let mut normalized = String::new();
+    for c in pattern.nfkc().filter(|x| !is_invisible(*x)) {
+        match c.is_ascii() {
+            true => normalized.push(c),
+            false => normalized.extend(c.to_lowercase().map(fold_confusable)),
+        }
+    }
+
+    return normalized;
+}
+
+/**
+ * Build the pattern of a banned phrase, terms and wildcards are normalized
+ * like the messages and only match whole words, `*` in a wildcard matches
+ * anything and `?` a single character
+ */
+fn phrase_pattern(phrase: &str, match_type: BannedPhraseType) -> String {
+    let phrase = match match_type {
+        BannedPhraseType::Regex => return normalize_regex(phrase),
+        _ => normalize_text(phrase),
+    };
+
+    let mut pattern = String::new();
+    for c in phrase.chars() {
+        match (c, match_type) {
+            ('*', BannedPhraseType::Wildcard) => pattern.push_str(".*"),
+            ('?', BannedPhraseType::Wildcard) => pattern.push('.'),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    let boundary = |x: Option<char>| match x {
+        Some(x) if x.is_alphanumeric() || x == '_' => r"\b",
+        _ => "",
+    };
+    return format!(
+        "{}{}{}",
+        boundary(phrase.chars().next()),
+        pattern,
+        boundary(phrase.chars().last())
+    );
+}
+
+/**
+ * Build the regex of a banned phrase, fails for an invalid regex
+ */
+pub fn build_regex(phrase: &str, match_type: BannedPhraseType) -> Result<Regex, Error> {
+    let regex = RegexBuilder::new(&phrase_pattern(phrase, match_type))
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()?;
+
+    return Ok(regex);
+}
+
+/**
+ * A banned phrase with its pattern ready to match normalized messages
+ */
+#[derive(Debug, Clone)]
+pub struct CompiledPhrase {
+    pub model: banned_phrase_entity::Model,
+    regex: Regex,
+}
+
+impl CompiledPhrase {
+    pub fn compile(model: banned_phrase_entity::Model) -> Result<CompiledPhrase, Error> {
+        let regex = build_regex(&model.phrase, model.match_type)?;
+
+        return Ok(CompiledPhrase { model, regex });
+    }
+
+    /**
+     * Check a message that went through `normalize_text`
+     */
+    pub fn matches(&self, normalized: &str) -> bool {
+        return self.regex.is_match(normalized);
+    }
+}
+
+/**
+ * Act on the banned phrases a message matches, phrases in a dry run only log
+ * the match, returns false if no phrase acted on the message
+ */
+pub async fn handle_banned_phrases(invocation: &Invocation, ctx: &BotContext) -> Result<bool, Error> {
+    let tags = &invocation.tags;
+    if has_permission(tags, &Permission::Moderator) {
+        return Ok(false);
+    }
+
+    let phrases = ctx.banned_phrases.get(tags.room_id, &ctx.db).await?;
+    if phrases.is_empty() {
+        return Ok(false);
+    }
+
+    let text = normalize_text(&invocation.text);
+    for phrase in phrases.iter().filter(|x| x.matches(&text)) {
+        let model = &phrase.model;
+        database::handler::banned_phrase::record_match(&model.id, Utc::now(), &ctx.db).await?;
+
+        if model.dry_run == 1 {
+            println!(
+                "Banned phrase {} would {:?} {} in {} (dry run): {}",
+                model.phrase, model.action, tags.user_id, invocation.channel_name, invocation.text
+            );
+            continue;
+        }
+
+        let (action, duration) = match model.action {
+            BannedPhraseAction::Delete => (ModerationActionType::Delete, None),
+            BannedPhraseAction::Timeout => (ModerationActionType::Timeout, Some(model.timeout)),
+            BannedPhraseAction::Ban => (ModerationActionType::Ban, None),
+        };
+        let reason = format!("banned phrase: {}", model.phrase);
+        enforce(invocation, action, duration, &reason, ctx).await?;
+        println!(
+            "Banned phrase {} in {}: {:?} for {}",
+            model.phrase, invocation.channel_name, model.action, tags.user_id
+        );
+
+        return Ok(true);
+    }
+
+    return Ok(false);
+}
+
+#[async_trait]
+impl ChannelLoad for CompiledPhrase {
+    async fn load(channel_id: i32, db: &DatabaseConnection) -> Result<Vec<CompiledPhrase>, Error> {
+        let models = database::handler::banned_phrase::get_banned_phrases(channel_id, db).await?;
+        let phrases = models
+            .into_iter()
+            .filter_map(|x| {
+                let phrase = String::from(&x.phrase);
+                match CompiledPhrase::compile(x) {
+                    Ok(x) => Some(x),
+                    Err(e) => {
+                        println!("Invalid banned phrase {}: {:?}", phrase, e);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<CompiledPhrase>>();
+
+        return Ok(phrases);
+    }
+}
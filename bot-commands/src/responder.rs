@@ -0,0 +1,85 @@
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use database::entity::auto_responder as auto_responder_entity;
+use database::entity::sea_orm_active_enums::AutoResponderType;
+use database::sea_orm::DatabaseConnection;
+use regex::{Regex, RegexBuilder};
+
+use crate::channel_cache::ChannelLoad;
+
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Keywords(Vec<String>),
+    Regex(Regex),
+}
+
+/**
+ * An auto responder with its pattern ready to match messages, keyword
+ * patterns are comma separated and match anywhere in the message ignoring case
+ */
+#[derive(Debug, Clone)]
+pub struct CompiledResponder {
+    pub model: auto_responder_entity::Model,
+    matcher: Matcher,
+}
+
+impl CompiledResponder {
+    pub fn compile(model: auto_responder_entity::Model) -> Result<CompiledResponder, Error> {
+        let matcher = match model.match_type {
+            AutoResponderType::Keyword => Matcher::Keywords(
+                model
+                    .pattern
+                    .split(',')
+                    .map(|x| x.trim().to_lowercase())
+                    .filter(|x| !x.is_empty())
+                    .collect(),
+            ),
+            AutoResponderType::Regex => Matcher::Regex(
+                RegexBuilder::new(&model.pattern)
+                    .case_insensitive(true)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .build()?,
+            ),
+        };
+
+        return Ok(CompiledResponder { model, matcher });
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        return match &self.matcher {
+            Matcher::Keywords(keywords) => {
+                let text = text.to_lowercase();
+                keywords.iter().any(|x| text.contains(x.as_str()))
+            }
+            Matcher::Regex(regex) => regex.is_match(text),
+        };
+    }
+}
+
+#[async_trait]
+impl ChannelLoad for CompiledResponder {
+    async fn load(
+        channel_id: i32,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<CompiledResponder>, Error> {
+        let models =
+            database::handler::auto_responder::get_enabled_auto_responders(channel_id, db).await?;
+        let responders = models
+            .into_iter()
+            .filter_map(|x| {
+                let name = String::from(&x.name);
+                match CompiledResponder::compile(x) {
+                    Ok(x) => Some(x),
+                    Err(e) => {
+                        println!("Invalid auto responder {}: {:?}", name, e);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<CompiledResponder>>();
+
+        return Ok(responders);
+    }
+}
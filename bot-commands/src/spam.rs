@@ -0,0 +1,347 @@
+use anyhow::{Error, Result};
+use async_trait::async_trait;
+use database::entity::sea_orm_active_enums::{ModerationActionType, Permission, SpamFilterType};
+use database::entity::spam_filter as spam_filter_entity;
+use database::sea_orm::DatabaseConnection;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::channel_cache::ChannelLoad;
+use crate::context::{BotContext, Invocation};
+use crate::enforcement::enforce;
+use crate::permission::has_permission;
+
+const PERMIT_TTL: usize = 60;
+const REPEAT_WINDOW: usize = 5 * 60;
+const OFFENSE_WINDOW: usize = 60 * 60;
+const ZALGO_MARKS: usize = 3;
+
+static LINK: OnceLock<Regex> = OnceLock::new();
+
+/**
+ * What is done about a message that broke a filter
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpamAction {
+    Delete,
+    Timeout(i32),
+}
+
+/**
+ * The last message of a viewer and how often it was sent in a row
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LastMessage {
+    text: String,
+    count: i32,
+}
+
+fn permit_key(channel_id: i32, user_id: i32) -> String {
+    return format!("channel:{}:permit:{}", channel_id, user_id);
+}
+
+fn repeat_key(channel_id: i32, user_id: i32) -> String {
+    return format!("channel:{}:repeat:{}", channel_id, user_id);
+}
+
+fn offense_key(channel_id: i32, user_id: i32) -> String {
+    return format!("channel:{}:offenses:{}", channel_id, user_id);
+}
+
+/**
+ * Check if a message contains something that looks like a link
+ */
+pub fn contains_link(text: &str) -> bool {
+    let link = LINK.get_or_init(|| {
+        Regex::new(
+            r"(?i)(?:https?://|www\.)\S+|\b[a-z0-9-]+(?:\.[a-z0-9-]+)*\.(?:com|net|org|tv|gg|io|ly|me|co|xyz|ru|de|uk|info|link|live|app|dev|shop|site|online|click)\b",
+        )
+        .unwrap()
+    });
+
+    return link.is_match(text);
+}
+
+/**
+ * Parse the emotes tag like `25:0-4,12-16/1902:6-10` into the character
+ * ranges of the emotes
+ */
+pub fn emote_ranges(emotes: &str) -> Vec<(usize, usize)> {
+    return emotes
+        .split('/')
+        .filter_map(|x| x.split_once(':'))
+        .flat_map(|(_, ranges)| ranges.split(','))
+        .filter_map(|x| {
+            let (start, end) = x.split_once('-')?;
+            Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+        })
+        .collect();
+}
+
+/**
+ * The message without its emotes, so emote names do not count as caps or
+ * symbols
+ */
+pub fn strip_emotes(text: &str, emotes: &[(usize, usize)]) -> String {
+    return text
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| !emotes.iter().any(|(start, end)| i >= start && i <= end))
+        .map(|(_, x)| x)
+        .collect();
+}
+
+/**
+ * Check if a character is a mark that stacks on the one before it
+ */
+pub fn is_combining_mark(c: char) -> bool {
+    return matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{0483}'..='\u{0489}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    );
+}
+
+/**
+ * Check if a message stacks combining marks on top of each other
+ */
+pub fn has_zalgo(text: &str) -> bool {
+    let mut marks = 0;
+    for c in text.chars() {
+        marks = match is_combining_mark(c) {
+            true => marks + 1,
+            false => 0,
+        };
+        if marks >= ZALGO_MARKS {
+            return true;
+        }
+    }
+
+    return false;
+}
+
+/**
+ * The percent of `part` in `total`, None below the minimum length
+ */
+fn percent(part: usize, total: usize, min_length: i32) -> Option<usize> {
+    if total == 0 || total < min_length.max(0) as usize {
+        return None;
+    }
+
+    return Some(part * 100 / total);
+}
+
+/**
+ * Check a message against a filter that only needs the message itself,
+ * repeated messages are checked with the earlier messages in redis
+ */
+pub fn breaks_filter(filter: &spam_filter_entity::Model, text: &str, emotes: Option<&str>) -> bool {
+    let threshold = filter.threshold.max(0) as usize;
+    let emotes = emote_ranges(emotes.unwrap_or_default());
+
+    return match filter.filter {
+        SpamFilterType::Link => contains_link(text),
+        SpamFilterType::Caps => {
+            let text = strip_emotes(text, &emotes);
+            let letters = text.chars().filter(|x| x.is_alphabetic()).count();
+            let upper = text.chars().filter(|x| x.is_uppercase()).count();
+            percent(upper, letters, filter.min_length)
+                .map(|x| x >= threshold)
+                .unwrap_or(false)
+        }
+        SpamFilterType::Emotes => emotes.len() > threshold,
+        SpamFilterType::Symbols => {
+            let text = strip_emotes(text, &emotes);
+            let chars = text
+                .chars()
+                .filter(|x| !x.is_whitespace() && !is_combining_mark(*x))
+                .collect::<Vec<char>>();
+            let symbols = chars.iter().filter(|x| !x.is_alphanumeric()).count();
+            has_zalgo(&text)
+                || percent(symbols, chars.len(), filter.min_length)
+                    .map(|x| x >= threshold)
+                    .unwrap_or(false)
+        }
+        SpamFilterType::Repeat => false,
+        SpamFilterType::Length => text.chars().count() > threshold,
+    };
+}
+
+/**
+ * A first offense only deletes the message, the second times the viewer out
+ * and later ones get the long timeout
+ */
+pub fn spam_action(offense: i64, filter: &spam_filter_entity::Model) -> SpamAction {
+    return match offense {
+        x if x <= 1 => SpamAction::Delete,
+        2 => SpamAction::Timeout(filter.timeout),
+        _ => SpamAction::Timeout(filter.long_timeout.max(filter.timeout)),
+    };
+}
+
+/**
+ * The message as compared for repeats, twitch appends an invisible
+ * character to let a message be sent twice
+ */
+pub fn normalize_repeat(text: &str) -> String {
+    return text
+        .replace('\u{E0000}', "")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+}
+
+fn filter_reason(filter: &SpamFilterType) -> &'static str {
+    return match filter {
+        SpamFilterType::Link => "no links without a !permit",
+        SpamFilterType::Caps => "too many caps",
+        SpamFilterType::Emotes => "too many emotes",
+        SpamFilterType::Symbols => "too many symbols",
+        SpamFilterType::Repeat => "stop repeating yourself",
+        SpamFilterType::Length => "that message is too long",
+    };
+}
+
+/**
+ * Count the message towards the repeats of a viewer, returns how often it
+ * was sent in a row
+ */
+async fn count_repeat(invocation: &Invocation, ctx: &BotContext) -> Result<i32, Error> {
+    let key = repeat_key(invocation.tags.room_id, invocation.tags.user_id);
+    let text = normalize_repeat(&invocation.text);
+
+    let mut redis = ctx.redis.lock().await;
+    let count = match cache::get_as::<LastMessage>(&key, &mut redis).await? {
+        Some(x) if x.text == text => x.count + 1,
+        _ => 1,
+    };
+    let last = LastMessage { text, count };
+    cache::set_with_ttl(&key, &serde_json::to_string(&last)?, REPEAT_WINDOW, &mut redis).await?;
+
+    return Ok(count);
+}
+
+/**
+ * Allow a viewer to post one link in the next minute
+ */
+pub async fn permit(channel_id: i32, user_id: i32, ctx: &BotContext) -> Result<(), Error> {
+    return cache::set_with_ttl(
+        &permit_key(channel_id, user_id),
+        "1",
+        PERMIT_TTL,
+        &mut *ctx.redis.lock().await,
+    )
+    .await;
+}
+
+/**
+ * Use up the permit of a viewer, returns false if there is none
+ */
+async fn use_permit(channel_id: i32, user_id: i32, ctx: &BotContext) -> Result<bool, Error> {
+    let key = permit_key(channel_id, user_id);
+    let mut redis = ctx.redis.lock().await;
+    if cache::get::<String>(&key, &mut redis).await?.is_none() {
+        return Ok(false);
+    }
+    cache::delete(&key, &mut redis).await?;
+
+    return Ok(true);
+}
+
+/**
+ * Find the first enabled filter a message breaks
+ */
+async fn broken_filter<'a>(
+    filters: &'a [spam_filter_entity::Model],
+    invocation: &Invocation,
+    ctx: &BotContext,
+) -> Result<Option<&'a spam_filter_entity::Model>, Error> {
+    let tags = &invocation.tags;
+    let filters = filters
+        .iter()
+        .filter(|x| !has_permission(tags, &x.exempt_permission));
+
+    for filter in filters {
+        let broken = match filter.filter {
+            SpamFilterType::Repeat => count_repeat(invocation, ctx).await? >= filter.threshold,
+            _ => breaks_filter(filter, &invocation.text, tags.emotes.as_deref()),
+        };
+        if !broken {
+            continue;
+        }
+        if filter.filter == SpamFilterType::Link
+            && use_permit(tags.room_id, tags.user_id, ctx).await?
+        {
+            continue;
+        }
+
+        return Ok(Some(filter));
+    }
+
+    return Ok(None);
+}
+
+/**
+ * Run the filters of the channel on a message and delete it or time the
+ * viewer out, returns false if the message passed
+ */
+pub async fn handle_spam(invocation: &Invocation, ctx: &BotContext) -> Result<bool, Error> {
+    let tags = &invocation.tags;
+    // twitch does not let the bot time out moderators
+    if has_permission(tags, &Permission::Moderator) {
+        return Ok(false);
+    }
+
+    let filters = ctx.spam_filters.get(tags.room_id, &ctx.db).await?;
+    if filters.is_empty() {
+        return Ok(false);
+    }
+    let filter = match broken_filter(&filters, invocation, ctx).await? {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+
+    let offense = {
+        let key = offense_key(tags.room_id, tags.user_id);
+        let mut redis = ctx.redis.lock().await;
+        let offense = cache::incr(&key, &mut redis).await?;
+        cache::expire(&key, OFFENSE_WINDOW, &mut redis).await?;
+        offense
+    };
+    let action = spam_action(offense, filter);
+    let reason = filter_reason(&filter.filter);
+
+    let (action_type, duration) = match action {
+        SpamAction::Delete => (ModerationActionType::Delete, None),
+        SpamAction::Timeout(x) => (ModerationActionType::Timeout, Some(x)),
+    };
+    enforce(invocation, action_type, duration, reason, ctx).await?;
+    println!(
+        "Spam filter {:?} in {}: {:?} for {}",
+        filter.filter, invocation.channel_name, action, tags.user_id
+    );
+
+    let notice = match action {
+        SpamAction::Delete => format!("@{} {} (warning)", tags.display_name, reason),
+        SpamAction::Timeout(x) => format!("@{} {} ({}s timeout)", tags.display_name, reason, x),
+    };
+    ctx.say(&invocation.channel_name, &notice).await?;
+
+    return Ok(true);
+}
+
+#[async_trait]
+impl ChannelLoad for spam_filter_entity::Model {
+    async fn load(
+        channel_id: i32,
+        db: &DatabaseConnection,
+    ) -> Result<Vec<spam_filter_entity::Model>, Error> {
+        return database::handler::spam_filter::get_enabled_spam_filters(channel_id, db).await;
+    }
+}
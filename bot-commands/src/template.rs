@@ -0,0 +1,207 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/**
+ * A piece of a command body, either plain text or a `$(name args...)` variable
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Variable { name: String, args: Vec<String> },
+}
+
+/**
+ * Errors of a variable, they are rendered into the reply instead of failing the command
+ */
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    #[error("unknown variable $({0})")]
+    UnknownVariable(String),
+    #[error("$({name}) expects {expected}")]
+    InvalidArguments { name: String, expected: String },
+    #[error("$({0}) is unavailable")]
+    Unavailable(String),
+}
+
+/**
+ * What a variable can know about the invocation of a command
+ */
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    pub channel_id: i32,
+    pub channel_name: String,
+    pub user_id: i32,
+    pub user_name: String,
+    pub args: Vec<String>,
+    pub count: i32,
+}
+
+/**
+ * Resolves one or more variables
+ */
+#[async_trait]
+pub trait VariableProvider: Send + Sync {
+    async fn resolve(
+        &self,
+        name: &str,
+        args: &[String],
+        ctx: &TemplateContext,
+    ) -> Result<String, TemplateError>;
+}
+
+/**
+ * Split a command body into text and variables, a `$(` without a closing
+ * parenthesis is kept as text
+ */
+pub fn parse(body: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut text = String::new();
+    let mut rest = body;
+
+    while let Some(start) = rest.find("$(") {
+        let end = match rest[start + 2..].find(')') {
+            Some(x) => start + 2 + x,
+            None => break,
+        };
+
+        text.push_str(&rest[..start]);
+
+        let mut parts = rest[start + 2..end].split_whitespace();
+        match parts.next() {
+            Some(name) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Variable {
+                    name: name.to_lowercase(),
+                    args: parts.map(|x| x.to_string()).collect(),
+                });
+            }
+            None => text.push_str(&rest[start..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    return segments;
+}
+
+/**
+ * Variable providers by the name of the variable
+ */
+#[derive(Clone, Default)]
+pub struct Templates {
+    providers: HashMap<String, Arc<dyn VariableProvider>>,
+}
+
+impl Templates {
+    pub fn register(&mut self, names: &[&str], provider: Arc<dyn VariableProvider>) {
+        for name in names {
+            self.providers.insert(name.to_string(), Arc::clone(&provider));
+        }
+    }
+
+    /**
+     * Render a command body, variables that fail show their error in brackets
+     */
+    pub async fn render(&self, body: &str, ctx: &TemplateContext) -> String {
+        let mut output = String::new();
+
+        for segment in parse(body) {
+            match segment {
+                Segment::Text(x) => output.push_str(&x),
+                Segment::Variable { name, args } => {
+                    let value = match self.providers.get(&name) {
+                        Some(provider) => provider.resolve(&name, &args, ctx).await,
+                        None => Err(TemplateError::UnknownVariable(name.to_string())),
+                    };
+
+                    match value {
+                        Ok(x) => output.push_str(&x),
+                        Err(e) => output.push_str(&format!("[{}]", e)),
+                    }
+                }
+            }
+        }
+
+        return output;
+    }
+}
+
+/**
+ * Variables that only need the invocation: user, touser, args, count and channel
+ */
+pub struct ContextProvider;
+
+#[async_trait]
+impl VariableProvider for ContextProvider {
+    async fn resolve(
+        &self,
+        name: &str,
+        args: &[String],
+        ctx: &TemplateContext,
+    ) -> Result<String, TemplateError> {
+        return match name {
+            "user" => Ok(ctx.user_name.to_string()),
+            "touser" => Ok(match ctx.args.first() {
+                Some(x) => x.trim_start_matches('@').to_string(),
+                None => ctx.user_name.to_string(),
+            }),
+            "args" => match args.first() {
+                None => Ok(ctx.args.join(" ")),
+                Some(x) => match x.parse::<usize>() {
+                    Ok(index) if index > 0 => Ok(ctx.args.get(index - 1).cloned().unwrap_or_default()),
+                    _ => Err(TemplateError::InvalidArguments {
+                        name: String::from(name),
+                        expected: String::from("a position starting at 1"),
+                    }),
+                },
+            },
+            "count" => Ok(ctx.count.to_string()),
+            "channel" => Ok(ctx.channel_name.to_string()),
+            _ => Err(TemplateError::UnknownVariable(String::from(name))),
+        };
+    }
+}
+
+/**
+ * `$(random min-max)` picks a whole number in the inclusive range
+ */
+pub struct RandomProvider;
+
+#[async_trait]
+impl VariableProvider for RandomProvider {
+    async fn resolve(
+        &self,
+        name: &str,
+        args: &[String],
+        _ctx: &TemplateContext,
+    ) -> Result<String, TemplateError> {
+        let invalid = || TemplateError::InvalidArguments {
+            name: String::from(name),
+            expected: String::from("a range like 1-100"),
+        };
+
+        let range = args.first().ok_or_else(invalid)?;
+        let (min, max) = match range.char_indices().skip(1).find(|(_, c)| *c == '-') {
+            Some((x, _)) => (&range[..x], &range[x + 1..]),
+            None => return Err(invalid()),
+        };
+        let min = min.parse::<i64>().map_err(|_| invalid())?;
+        let max = max.parse::<i64>().map_err(|_| invalid())?;
+        if min > max {
+            return Err(invalid());
+        }
+
+        let value = rand::Rng::gen_range(&mut rand::thread_rng(), min..=max);
+
+        return Ok(value.to_string());
+    }
+}
@@ -0,0 +1,628 @@
+use crate::bans::banned_user;
+use crate::builtins::timer::{parse_timer_action, TimerAction};
+use crate::builtins::counter::{parse_counter_command, CounterAction};
+use crate::builtins::points::parse_user_amount;
+use crate::builtins::poll::{parse_poll, parse_vote, poll_results, Poll};
+use crate::builtins::queue::{Queue, QueueEntry};
+use crate::builtins::raffle::{parse_raffle_options, RaffleOptions};
+use crate::builtins::banphrase::parse_banned_phrase;
+use crate::builtins::moderation::format_history;
+use crate::builtins::strike::{describe_step, parse_warn};
+use crate::builtins::quote::{format_quote, parse_quote};
+use crate::builtins::management::{join_limited, parse_options, CommandOptions};
+use crate::format::format_duration;
+use crate::permission::{has_permission, user_permission};
+use crate::phrases::{normalize_text, CompiledPhrase};
+use crate::responder::CompiledResponder;
+use crate::spam::{
+    breaks_filter, contains_link, emote_ranges, has_zalgo, normalize_repeat, spam_action,
+    strip_emotes, SpamAction,
+};
+use crate::template::{ContextProvider, RandomProvider, Segment, TemplateContext, Templates};
+use crate::timers::next_timer;
+use chrono::{Duration, TimeZone, Utc};
+use database::entity::auto_responder as auto_responder_entity;
+use database::entity::banned_phrase as banned_phrase_entity;
+use database::entity::moderation_action as moderation_action_entity;
+use database::handler::moderation::ActionCount;
+use database::entity::quote as quote_entity;
+use database::handler::counter::CounterChange;
+use database::entity::timer as timer_entity;
+use database::entity::sea_orm_active_enums::{
+    AutoResponderType, BannedPhraseAction, BannedPhraseType, ModerationActionType, Permission,
+    SpamFilterType,
+};
+use database::handler::spam_filter::default_spam_filter;
+use database::handler::strike::{parse_ladder, strike_step, StrikeStep, DEFAULT_LADDER};
+use parser::clearchat_tag::ClearChatTags;
+use parser::privmsg_tag::PrivMsgTags;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+async fn tags(badges: &str, moderator: bool, subscriber: bool, vip: bool) -> PrivMsgTags {
+    let input = format!(
+        "@badges={};color=;display-name=Foo;emotes=;id=1;mod={};room-id=1;subscriber={};tmi-sent-ts=1550868292494;user-id=2;user-type=normal{} :foo!foo@foo.tmi.twitch.tv PRIVMSG #bar :!hi",
+        badges,
+        moderator as i32,
+        subscriber as i32,
+        if vip { ";vip=1" } else { "" }
+    );
+
+    return parser::irc_parser::parse(&input)
+        .await
+        .unwrap()
+        .privmsg_tags()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn permission_test() {
+    let everyone = tags("", false, false, false).await;
+    let subscriber = tags("subscriber/1", false, true, false).await;
+    let vip = tags("vip/1", false, false, true).await;
+    let moderator = tags("moderator/1", true, false, false).await;
+    let broadcaster = tags("broadcaster/1", false, false, false).await;
+
+    assert_eq!(user_permission(&everyone), Permission::Everyone);
+    assert_eq!(user_permission(&subscriber), Permission::Subscriber);
+    assert_eq!(user_permission(&vip), Permission::Vip);
+    assert_eq!(user_permission(&moderator), Permission::Moderator);
+    assert_eq!(user_permission(&broadcaster), Permission::Broadcaster);
+
+    assert!(has_permission(&everyone, &Permission::Everyone));
+    assert!(!has_permission(&everyone, &Permission::Subscriber));
+    assert!(has_permission(&vip, &Permission::Subscriber));
+    assert!(!has_permission(&vip, &Permission::Moderator));
+    assert!(has_permission(&moderator, &Permission::Vip));
+    assert!(!has_permission(&moderator, &Permission::Broadcaster));
+    assert!(has_permission(&broadcaster, &Permission::Moderator));
+}
+
+#[test]
+fn template_parse_test() {
+    assert_eq!(
+        crate::template::parse("hi $(USER) $(args 1)!$( )$(open"),
+        vec![
+            Segment::Text("hi ".to_string()),
+            Segment::Variable {
+                name: "user".to_string(),
+                args: vec![],
+            },
+            Segment::Text(" ".to_string()),
+            Segment::Variable {
+                name: "args".to_string(),
+                args: vec!["1".to_string()],
+            },
+            Segment::Text("!$( )$(open".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn template_render_test() {
+    let mut templates = Templates::default();
+    templates.register(&["user", "touser", "args", "count", "channel"], Arc::new(ContextProvider));
+    templates.register(&["random"], Arc::new(RandomProvider));
+
+    let ctx = TemplateContext {
+        channel_id: 1,
+        channel_name: "bar".to_string(),
+        user_id: 2,
+        user_name: "Foo".to_string(),
+        args: vec!["@Baz".to_string(), "hello".to_string()],
+        count: 7,
+    };
+
+    assert_eq!(
+        templates.render("$(user) -> $(touser) in $(channel) #$(count): $(args 2)$(args 3)", &ctx).await,
+        "Foo -> Baz in bar #7: hello"
+    );
+    assert_eq!(
+        templates.render("$(nope) $(args x) $(random 5)", &ctx).await,
+        "[unknown variable $(nope)] [$(args) expects a position starting at 1] [$(random) expects a range like 1-100]"
+    );
+
+    let value = templates.render("$(random -5-5)", &ctx).await.parse::<i64>().unwrap();
+    assert!((-5..=5).contains(&value));
+}
+
+#[test]
+fn format_duration_test() {
+    assert_eq!(format_duration(0), "0 seconds");
+    assert_eq!(format_duration(61), "1 minute 1 second");
+    assert_eq!(format_duration(2 * 86400 + 3 * 3600 + 120), "2 days 3 hours");
+    assert_eq!(format_duration(3600 + 5), "1 hour");
+}
+
+fn args(text: &str) -> Vec<String> {
+    return text.split(' ').map(|x| x.to_string()).collect();
+}
+
+#[test]
+fn command_options_test() {
+    assert_eq!(
+        parse_options(&args("!Discord -ul=sub -cd=30s -ucd=5 -whisper=on join  -cd=1 us")),
+        Ok(CommandOptions {
+            name: "discord".to_string(),
+            permission: Some(Permission::Subscriber),
+            global_cooldown: Some(30),
+            user_cooldown: Some(5),
+            whisper: Some(true),
+            rename: None,
+            body: "join -cd=1 us".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_options(&args("discord -name=!dc")).map(|x| x.rename),
+        Ok(Some("dc".to_string()))
+    );
+    assert!(parse_options(&args("!discord -ul=king hi")).is_err());
+    assert!(parse_options(&args("!discord -cd=-1 hi")).is_err());
+    assert!(parse_options(&args("!discord -foo=1 hi")).is_err());
+    assert!(parse_options(&[]).is_err());
+}
+
+#[test]
+fn join_limited_test() {
+    let names = vec!["!a".to_string(), "!bb".to_string(), "!ccc".to_string()];
+
+    assert_eq!(join_limited(&names, 100), "!a, !bb, !ccc");
+    assert_eq!(join_limited(&names, 8), "!a, !bb, ...");
+}
+
+fn auto_responder(match_type: AutoResponderType, pattern: &str) -> auto_responder_entity::Model {
+    return auto_responder_entity::Model {
+        id: "1".to_string(),
+        channel_id: 1,
+        name: "schedule".to_string(),
+        match_type,
+        pattern: pattern.to_string(),
+        body: "Streams are on mondays".to_string(),
+        permission: Permission::Everyone,
+        global_cooldown: 0,
+        user_cooldown: 0,
+        enabled: 1,
+        invoke_count: 0,
+        last_invoked: None,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+}
+
+#[test]
+fn auto_responder_match_test() {
+    let keywords = CompiledResponder::compile(auto_responder(
+        AutoResponderType::Keyword,
+        "when is the stream, Schedule,",
+    ))
+    .unwrap();
+    assert!(keywords.matches("hey WHEN is the stream today?"));
+    assert!(keywords.matches("schedule pls"));
+    assert!(!keywords.matches("when is the next stream"));
+    assert!(!keywords.matches(""));
+
+    let regex = CompiledResponder::compile(auto_responder(
+        AutoResponderType::Regex,
+        r"when\s+(is|does)\s+.*stream",
+    ))
+    .unwrap();
+    assert!(regex.matches("So when does the stream start"));
+    assert!(!regex.matches("stream when"));
+
+    assert!(CompiledResponder::compile(auto_responder(AutoResponderType::Regex, "(")).is_err());
+}
+
+fn timer(id: &str, interval: i32, min_lines: i32, last_posted: Option<i64>) -> timer_entity::Model {
+    return timer_entity::Model {
+        id: id.to_string(),
+        channel_id: 1,
+        name: format!("timer{}", id),
+        body: "Follow the channel".to_string(),
+        interval,
+        min_lines,
+        position: id.parse().unwrap(),
+        enabled: 1,
+        last_posted: last_posted.map(|x| Utc.timestamp_opt(x, 0).unwrap()),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+}
+
+#[test]
+fn next_timer_test() {
+    let now = Utc.timestamp_opt(10_000, 0).unwrap();
+
+    // nothing posted yet, the first timer goes first
+    let timers = vec![timer("1", 5, 0, None), timer("2", 5, 0, None)];
+    assert_eq!(next_timer(&timers, 10, now).unwrap().id, "1");
+
+    // the rotation continues after the timer posted last
+    let timers = vec![timer("1", 5, 0, Some(9_000)), timer("2", 5, 0, Some(8_000))];
+    assert_eq!(next_timer(&timers, 10, now).unwrap().id, "2");
+
+    // the interval has not passed for the next one
+    let timers = vec![timer("1", 5, 0, Some(8_000)), timer("2", 60, 0, Some(9_000))];
+    assert_eq!(next_timer(&timers, 10, now).unwrap().id, "1");
+
+    // not enough chat lines since the last timer of the channel was posted
+    let timers = vec![timer("1", 5, 0, Some(9_900)), timer("3", 5, 5, Some(9_000))];
+    assert!(next_timer(&timers, 4, now).is_none());
+    assert_eq!(next_timer(&timers, 5, now).unwrap().id, "3");
+    assert!(next_timer(&timers, 0, now + Duration::hours(1))
+        .map(|x| x.id == "1")
+        .unwrap());
+
+    let mut disabled = timer("1", 5, 0, None);
+    disabled.enabled = 0;
+    assert!(next_timer(&[disabled], 10, now).is_none());
+}
+
+#[test]
+fn timer_action_test() {
+    assert_eq!(
+        parse_timer_action(&args("add discord 15 -lines=5 Join the $(channel) discord")),
+        Ok(TimerAction::Add {
+            name: "discord".to_string(),
+            interval: 15,
+            min_lines: Some(5),
+            body: "Join the $(channel) discord".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_timer_action(&args("edit discord -interval=30")),
+        Ok(TimerAction::Edit {
+            name: "discord".to_string(),
+            interval: Some(30),
+            min_lines: None,
+            body: None,
+        })
+    );
+    assert_eq!(
+        parse_timer_action(&args("move discord 2")),
+        Ok(TimerAction::Move {
+            name: "discord".to_string(),
+            position: 2,
+        })
+    );
+    assert_eq!(parse_timer_action(&args("list")), Ok(TimerAction::List));
+    assert!(parse_timer_action(&args("add discord 0 body")).is_err());
+    assert!(parse_timer_action(&args("add discord 15")).is_err());
+    assert!(parse_timer_action(&args("edit discord")).is_err());
+    assert!(parse_timer_action(&args("move discord 0")).is_err());
+}
+
+#[test]
+fn quote_test() {
+    assert_eq!(
+        parse_quote(&args("@Foo \"never again\""), "bar"),
+        Some(("Foo".to_string(), "never again".to_string()))
+    );
+    assert_eq!(
+        parse_quote(&args("never again"), "bar"),
+        Some(("bar".to_string(), "never again".to_string()))
+    );
+    assert_eq!(parse_quote(&args("@foo"), "bar"), None);
+
+    let mut quote = quote_entity::Model {
+        id: "1".to_string(),
+        channel_id: 1,
+        number: 42,
+        text: "never again".to_string(),
+        quoted_user: Some("foo".to_string()),
+        game: Some("Celeste".to_string()),
+        added_by: 2,
+        created_at: Utc.timestamp_opt(1_706_702_400, 0).unwrap().naive_utc(),
+        updated_at: Default::default(),
+    };
+    assert_eq!(format_quote(&quote), "#42: \"never again\" - foo [Celeste] (2024-01-31)");
+
+    quote.game = None;
+    assert_eq!(format_quote(&quote), "#42: \"never again\" - foo (2024-01-31)");
+}
+
+#[test]
+fn counter_command_test() {
+    let parse = |command: &str, text: &str| parse_counter_command(command, &args(text));
+
+    assert_eq!(parse("deaths", ""), Some(("deaths".to_string(), CounterAction::Show)));
+    assert_eq!(
+        parse("deaths+", ""),
+        Some(("deaths".to_string(), CounterAction::Change(CounterChange::Add(1))))
+    );
+    assert_eq!(
+        parse("deaths-", "3"),
+        Some(("deaths".to_string(), CounterAction::Change(CounterChange::Add(-3))))
+    );
+    assert_eq!(
+        parse("deaths", "set 5"),
+        Some(("deaths".to_string(), CounterAction::Change(CounterChange::Set(5))))
+    );
+    assert_eq!(
+        parse("deaths", "reset"),
+        Some(("deaths".to_string(), CounterAction::Change(CounterChange::Set(0))))
+    );
+    assert_eq!(parse("deaths", "set five"), None);
+    assert_eq!(parse("deaths+", "lots"), None);
+    assert_eq!(parse("deaths-", "-2147483648"), None);
+    assert_eq!(
+        parse("deaths-", "-2147483647"),
+        Some(("deaths".to_string(), CounterAction::Change(CounterChange::Add(i32::MAX))))
+    );
+}
+
+#[test]
+fn user_amount_test() {
+    assert_eq!(parse_user_amount(&args("@Foo 50")), Some(("foo".to_string(), 50)));
+    assert_eq!(parse_user_amount(&args("foo -10")), Some(("foo".to_string(), -10)));
+    assert_eq!(parse_user_amount(&args("foo")), None);
+    assert_eq!(parse_user_amount(&args("foo lots")), None);
+    assert_eq!(parse_user_amount(&args("foo 9223372036854775807")), None);
+    assert_eq!(parse_user_amount(&args("foo -9223372036854775808")), None);
+    assert_eq!(parse_user_amount(&args("foo 1000000000")), Some(("foo".to_string(), 1_000_000_000)));
+}
+
+#[test]
+fn raffle_options_test() {
+    assert_eq!(
+        parse_raffle_options(&args("-subs Enter -watch=60 -subluck=3 -fresh")),
+        Ok(RaffleOptions {
+            keyword: "enter".to_string(),
+            followers_only: false,
+            subscribers_only: true,
+            min_watch_time: 60,
+            subscriber_weight: 3,
+            exclude_winners: true,
+        })
+    );
+    assert!(parse_raffle_options(&args("-followers")).is_err());
+    assert!(parse_raffle_options(&args("enter now")).is_err());
+    assert!(parse_raffle_options(&args("enter -subluck=50")).is_err());
+    assert!(parse_raffle_options(&args("enter -luck")).is_err());
+}
+
+#[test]
+fn poll_test() {
+    let (question, options, duration) = parse_poll("\"Best game?\" Celeste | Hades|Tunic -time=5m").unwrap();
+    assert_eq!(question, "Best game?");
+    assert_eq!(options, vec!["Celeste", "Hades", "Tunic"]);
+    assert_eq!(duration, 300);
+    assert_eq!(parse_poll("\"Yes?\" yes|no").unwrap().2, 120);
+    assert!(parse_poll("Best game? a|b").is_err());
+    assert!(parse_poll("\"Best game?\" a").is_err());
+    assert!(parse_poll("\"Best game?\" a|b -time=soon").is_err());
+
+    assert_eq!(parse_vote(&options, "2"), Some(1));
+    assert_eq!(parse_vote(&options, "tunic"), Some(2));
+    assert_eq!(parse_vote(&options, "0"), None);
+    assert_eq!(parse_vote(&options, "4"), None);
+
+    let mut poll = Poll {
+        question,
+        options,
+        votes: HashMap::new(),
+        channel_name: "bar".to_string(),
+        started_by: 1,
+        ends_at: 0,
+    };
+    assert_eq!(poll_results(&poll), "Best game?: Celeste 0, Hades 0, Tunic 0, nobody voted");
+    poll.votes.insert(1, 0);
+    poll.votes.insert(2, 1);
+    poll.votes.insert(3, 1);
+    assert_eq!(
+        poll_results(&poll),
+        "Best game?: Celeste 1 (33%), Hades 2 (66%), Tunic 0 (0%), winner: Hades"
+    );
+    poll.votes.insert(4, 0);
+    assert!(poll_results(&poll).ends_with("tie: Celeste, Hades"));
+}
+
+#[test]
+fn queue_test() {
+    let entry = |user_id: i32, subscriber: bool| QueueEntry {
+        user_id,
+        name: user_id.to_string(),
+        subscriber,
+    };
+    let mut queue = Queue::default();
+
+    assert_eq!(queue.join(entry(1, false)), Some(1));
+    assert_eq!(queue.join(entry(2, false)), Some(2));
+    assert_eq!(queue.join(entry(3, true)), Some(1));
+    assert_eq!(queue.join(entry(4, true)), Some(2));
+    assert_eq!(queue.join(entry(1, false)), None);
+    assert_eq!(queue.position(2), Some(4));
+
+    assert!(queue.leave(4));
+    assert!(!queue.leave(4));
+    assert_eq!(queue.position(2), Some(3));
+
+    let next = queue.next(2);
+    assert_eq!(next.iter().map(|x| x.user_id).collect::<Vec<i32>>(), vec![3, 1]);
+    assert_eq!(queue.next(5).len(), 1);
+    assert!(queue.next(1).is_empty());
+}
+
+#[test]
+fn spam_filter_test() {
+    assert!(contains_link("check https://example.org/x"));
+    assert!(contains_link("go to www.example.org"));
+    assert!(contains_link("free followers at spam.gg now"));
+    assert!(!contains_link("see you at 5.30 tonight"));
+    assert!(!contains_link("nice. com on"));
+
+    assert_eq!(emote_ranges("25:0-4,12-16/1902:6-10"), vec![(0, 4), (12, 16), (6, 10)]);
+    assert_eq!(strip_emotes("Kappa Keepo Kappa", &emote_ranges("25:0-4,12-16")), " Keepo ");
+
+    assert!(has_zalgo("he\u{0301}\u{0302}\u{0303}llo"));
+    assert!(!has_zalgo("caf\u{00e9} cafe\u{0301}"));
+
+    let caps = default_spam_filter(1, SpamFilterType::Caps);
+    assert!(breaks_filter(&caps, "WHY IS NOBODY TALKING IN CHAT", None));
+    assert!(!breaks_filter(&caps, "WHY THO", None));
+    assert!(!breaks_filter(
+        &caps,
+        "PogChamp PogChamp PogChamp so good",
+        Some("305954156:0-7,9-16,18-25")
+    ));
+
+    let mut emotes = default_spam_filter(1, SpamFilterType::Emotes);
+    emotes.threshold = 2;
+    assert!(breaks_filter(&emotes, "Kappa Kappa Kappa", Some("25:0-4,6-10,12-16")));
+    assert!(!breaks_filter(&emotes, "Kappa Kappa", Some("25:0-4,6-10")));
+
+    let symbols = default_spam_filter(1, SpamFilterType::Symbols);
+    assert!(breaks_filter(&symbols, "!!!!!!!!!!! ?????????? hi", None));
+    assert!(breaks_filter(&symbols, "z\u{0300}\u{0301}\u{0302}", None));
+    assert!(!breaks_filter(&symbols, "is this the real life?", None));
+
+    let mut length = default_spam_filter(1, SpamFilterType::Length);
+    length.threshold = 10;
+    assert!(breaks_filter(&length, "hello there chat", None));
+    assert!(!breaks_filter(&length, "hello", None));
+
+    assert_eq!(normalize_repeat("Hello   Chat \u{E0000}"), "hello chat");
+
+    assert_eq!(spam_action(1, &caps), SpamAction::Delete);
+    assert_eq!(spam_action(2, &caps), SpamAction::Timeout(60));
+    assert_eq!(spam_action(5, &caps), SpamAction::Timeout(600));
+}
+
+#[test]
+fn banned_phrase_test() {
+    assert_eq!(normalize_text("ＦＲＥＥ  fоllоwеrs"), "free followers");
+    assert_eq!(normalize_text("f\u{200B}r\u{200D}ee 𝐛𝐢𝐭𝐬"), "free bits");
+    assert_eq!(normalize_text("ѕ\u{0336}р\u{0336}аm"), "spam");
+
+    let phrase = |phrase: &str, match_type: BannedPhraseType| {
+        CompiledPhrase::compile(banned_phrase_entity::Model {
+            id: "1".to_string(),
+            channel_id: 1,
+            phrase: phrase.to_string(),
+            match_type,
+            action: BannedPhraseAction::Delete,
+            timeout: 600,
+            dry_run: 0,
+            match_count: 0,
+            last_matched: None,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        })
+        .unwrap()
+    };
+
+    let term = phrase("Free Followers", BannedPhraseType::Term);
+    assert!(term.matches(&normalize_text("get ＦＲＥＥ fоllоwеrs now")));
+    assert!(!term.matches(&normalize_text("carefree followers")));
+
+    let wildcard = phrase("buy *views", BannedPhraseType::Wildcard);
+    assert!(wildcard.matches(&normalize_text("BUY cheap views")));
+    assert!(!wildcard.matches(&normalize_text("buying views")));
+
+    let regex = phrase(r"bit\.ly/\w+", BannedPhraseType::Regex);
+    assert!(regex.matches(&normalize_text("go to bit.ly/abc")));
+    let folded = phrase(r"free\s+ѕрам\W", BannedPhraseType::Regex);
+    assert!(folded.matches(&normalize_text("FREE ѕраm!")));
+    assert!(folded.matches(&normalize_text("free spam!")));
+    assert!(CompiledPhrase::compile(banned_phrase_entity::Model {
+        phrase: "(".to_string(),
+        ..regex.model.clone()
+    })
+    .is_err());
+
+    let options = parse_banned_phrase(&args("-wildcard -timeout=60 -dryrun buy *views")).unwrap();
+    assert_eq!(options.phrase, "buy *views");
+    assert_eq!(options.match_type, BannedPhraseType::Wildcard);
+    assert_eq!(options.action, BannedPhraseAction::Timeout);
+    assert_eq!(options.timeout, 60);
+    assert!(options.dry_run);
+    assert_eq!(parse_banned_phrase(&args("-ban -x")).unwrap().phrase, "-x");
+    assert!(parse_banned_phrase(&args("-ban")).is_err());
+    assert!(parse_banned_phrase(&args("-timeout=0 spam")).is_err());
+}
+
+#[test]
+fn history_test() {
+    let now = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+    assert_eq!(format_history(&[], &[], now), "a clean record");
+
+    let action = |action: ModerationActionType, duration: Option<i32>, reason: Option<&str>, days: i64| {
+        moderation_action_entity::Model {
+            id: "1".to_string(),
+            channel_id: 1,
+            target_user_id: Some(2),
+            action,
+            duration,
+            reason: reason.map(|x| x.to_string()),
+            msg_id: None,
+            moderator_id: None,
+            automated: 0,
+            echoed: 0,
+            timestamp: now - Duration::days(days),
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        }
+    };
+    let counts = vec![
+        ActionCount {
+            action: ModerationActionType::Timeout,
+            count: 2,
+        },
+        ActionCount {
+            action: ModerationActionType::Delete,
+            count: 1,
+        },
+    ];
+    let latest = vec![
+        action(ModerationActionType::Timeout, Some(600), Some("too many caps"), 3),
+        action(ModerationActionType::Delete, None, None, 5),
+    ];
+
+    assert_eq!(
+        format_history(&counts, &latest, now),
+        "2 timeouts, 1 deletion, latest: timeout 600s (too many caps) 3 days ago, deletion 5 days ago"
+    );
+}
+
+#[test]
+fn strike_test() {
+    let ladder = parse_ladder(DEFAULT_LADDER).unwrap();
+    assert_eq!(
+        ladder,
+        vec![
+            StrikeStep::Warn,
+            StrikeStep::Timeout(60),
+            StrikeStep::Timeout(600),
+            StrikeStep::Ban
+        ]
+    );
+    assert_eq!(strike_step(&ladder, 1), StrikeStep::Warn);
+    assert_eq!(strike_step(&ladder, 3), StrikeStep::Timeout(600));
+    assert_eq!(strike_step(&ladder, 7), StrikeStep::Ban);
+    assert_eq!(strike_step(&[], 2), StrikeStep::Warn);
+    assert_eq!(parse_ladder(" Warn, 300 ").unwrap(), vec![StrikeStep::Warn, StrikeStep::Timeout(300)]);
+    assert!(parse_ladder("warn,kick").is_err());
+    assert!(parse_ladder("warn,0").is_err());
+    assert!(parse_ladder("").is_err());
+
+    assert_eq!(describe_step(&StrikeStep::Timeout(600)), "a 10 minutes timeout");
+    assert_eq!(
+        parse_warn(&args("@Someone spamming links")),
+        Some(("someone".to_string(), Some("spamming links".to_string())))
+    );
+    assert_eq!(parse_warn(&args("someone")), Some(("someone".to_string(), None)));
+    assert_eq!(parse_warn(&[]), None);
+}
+
+#[test]
+fn banned_user_test() {
+    let tags = |target_user_id: Option<i32>, ban_duration: Option<i32>| ClearChatTags {
+        ban_duration,
+        room_id: 1,
+        target_user_id,
+        tmi_sent_ts: 0,
+    };
+
+    assert_eq!(banned_user(&tags(Some(2), None)), Some(2));
+    assert_eq!(banned_user(&tags(Some(2), Some(600))), None);
+    assert_eq!(banned_user(&tags(None, None)), None);
+}
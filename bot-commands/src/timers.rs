@@ -0,0 +1,139 @@
+use anyhow::{Error, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use database::entity::timer as timer_entity;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::context::BotContext;
+use crate::template::TemplateContext;
+
+fn channel_lines_key(channel_id: i32) -> String {
+    return format!("channel:{}:lines", channel_id);
+}
+
+fn timer_lines_key(channel_id: i32) -> String {
+    return format!("channel:{}:timer_lines", channel_id);
+}
+
+/**
+ * Count a chat line of a channel for the timers
+ */
+pub async fn count_line(channel_id: i32, ctx: &BotContext) -> Result<(), Error> {
+    let mut redis = ctx.redis.lock().await;
+    cache::incr(&channel_lines_key(channel_id), &mut redis).await?;
+
+    return Ok(());
+}
+
+/**
+ * Pick the timer to post next, timers take turns in their order starting
+ * after the one posted last and a timer is due once its interval passed and
+ * enough chat lines arrived since the last timer of the channel was posted
+ */
+pub fn next_timer(
+    timers: &[timer_entity::Model],
+    lines_since: i64,
+    now: DateTime<Utc>,
+) -> Option<&timer_entity::Model> {
+    let enabled = timers.iter().filter(|x| x.enabled == 1).collect::<Vec<&timer_entity::Model>>();
+    if enabled.is_empty() {
+        return None;
+    }
+
+    let start = enabled
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| x.last_posted.map(|posted| (posted, i)))
+        .max()
+        .map(|(_, i)| i + 1)
+        .unwrap_or(0);
+
+    return (0..enabled.len())
+        .map(|offset| enabled[(start + offset) % enabled.len()])
+        .find(|timer| {
+            let interval_passed = match timer.last_posted {
+                Some(posted) => now - posted >= ChronoDuration::minutes(timer.interval as i64),
+                None => true,
+            };
+
+            interval_passed && lines_since >= timer.min_lines as i64
+        });
+}
+
+/**
+ * Post the timers of all live channels every interval
+ */
+pub fn spawn(ctx: Arc<BotContext>, interval: Duration) -> JoinHandle<()> {
+    return tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+
+        loop {
+            tick.tick().await;
+
+            let channels = match database::handler::channel::get_live_channels(&ctx.db).await {
+                Ok(x) => x,
+                Err(e) => {
+                    println!("Failed to get live channels: {:?}", e);
+                    continue;
+                }
+            };
+
+            for channel in channels {
+                if let Err(e) = post_timer(channel.id, &ctx).await {
+                    println!("Failed to post timer in {}: {:?}", channel.id, e);
+                }
+            }
+        }
+    });
+}
+
+/**
+ * Post the next due timer of a channel, at most one per run
+ */
+async fn post_timer(channel_id: i32, ctx: &BotContext) -> Result<(), Error> {
+    let timers = database::handler::timer::get_timers(channel_id, &ctx.db).await?;
+    if timers.iter().all(|x| x.enabled == 0) {
+        return Ok(());
+    }
+
+    let mut redis = ctx.redis.lock().await;
+    let channel_lines = cache::get::<i64>(&channel_lines_key(channel_id), &mut redis)
+        .await?
+        .unwrap_or(0);
+    let posted_at = cache::get::<i64>(&timer_lines_key(channel_id), &mut redis)
+        .await?
+        .unwrap_or(0);
+    drop(redis);
+    // the counter starts over if redis lost it
+    let lines_since = match posted_at > channel_lines {
+        true => channel_lines,
+        false => channel_lines - posted_at,
+    };
+
+    let timer = match next_timer(&timers, lines_since, Utc::now()) {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let channel_name = match database::handler::user::get_user(channel_id, &ctx.db).await? {
+        Some(x) => x.nick,
+        None => return Err(Error::msg("No broadcaster")),
+    };
+
+    let template_ctx = TemplateContext {
+        channel_id,
+        channel_name: String::from(&channel_name),
+        user_id: channel_id,
+        user_name: String::from(&channel_name),
+        args: vec![],
+        count: 0,
+    };
+    let text = ctx.templates.render(&timer.body, &template_ctx).await;
+    ctx.say(&channel_name, &text).await?;
+
+    database::handler::timer::mark_posted(&timer.id, Utc::now(), &ctx.db).await?;
+    let mut redis = ctx.redis.lock().await;
+    cache::set(&timer_lines_key(channel_id), &channel_lines.to_string(), &mut redis).await?;
+
+    return Ok(());
+}
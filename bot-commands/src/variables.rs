@@ -0,0 +1,191 @@
+use async_trait::async_trait;
+use cache::redis::aio::Connection;
+use chrono::Utc;
+use database::sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::format::format_duration;
+use crate::lookup::{current_stream, followed_at, watch_time};
+use crate::template::{
+    ContextProvider, RandomProvider, TemplateContext, TemplateError, Templates, VariableProvider,
+};
+
+/**
+ * Credentials to call the twitch api as the bot
+ */
+#[derive(Debug, Clone)]
+pub struct TwitchCredentials {
+    pub bot_name: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/**
+ * Get the template variables with all providers registered
+ */
+pub fn default_templates(
+    db: &DatabaseConnection,
+    redis: &Arc<Mutex<Connection>>,
+    twitch: &TwitchCredentials,
+) -> Templates {
+    let mut templates = Templates::default();
+
+    templates.register(
+        &["user", "touser", "args", "count", "channel"],
+        Arc::new(ContextProvider),
+    );
+    templates.register(&["random"], Arc::new(RandomProvider));
+    templates.register(&["counter"], Arc::new(CounterProvider { db: db.clone() }));
+    templates.register(
+        &["uptime", "game", "title"],
+        Arc::new(StreamProvider {
+            db: db.clone(),
+            redis: Arc::clone(redis),
+        }),
+    );
+    templates.register(
+        &["watchtime"],
+        Arc::new(WatchTimeProvider {
+            db: db.clone(),
+            redis: Arc::clone(redis),
+        }),
+    );
+    templates.register(
+        &["followage"],
+        Arc::new(FollowAgeProvider {
+            db: db.clone(),
+            redis: Arc::clone(redis),
+            twitch: twitch.clone(),
+        }),
+    );
+
+    return templates;
+}
+
+/**
+ * `$(uptime)`, `$(game)` and `$(title)` of the current stream
+ */
+pub struct StreamProvider {
+    pub db: DatabaseConnection,
+    pub redis: Arc<Mutex<Connection>>,
+}
+
+#[async_trait]
+impl VariableProvider for StreamProvider {
+    async fn resolve(
+        &self,
+        name: &str,
+        _args: &[String],
+        ctx: &TemplateContext,
+    ) -> Result<String, TemplateError> {
+        let stream = current_stream(ctx.channel_id, &self.db, &self.redis)
+            .await
+            .map_err(|_| TemplateError::Unavailable(String::from(name)))?;
+        let stream = match stream {
+            Some(x) => x,
+            None => return Ok(format!("{} is offline", ctx.channel_name)),
+        };
+
+        return match name {
+            "uptime" => Ok(format_duration((Utc::now() - stream.started_at).num_seconds())),
+            "game" => Ok(stream.game),
+            "title" => Ok(stream.title),
+            _ => Err(TemplateError::UnknownVariable(String::from(name))),
+        };
+    }
+}
+
+/**
+ * `$(watchtime)` how long the user watched the channel
+ */
+pub struct WatchTimeProvider {
+    pub db: DatabaseConnection,
+    pub redis: Arc<Mutex<Connection>>,
+}
+
+#[async_trait]
+impl VariableProvider for WatchTimeProvider {
+    async fn resolve(
+        &self,
+        name: &str,
+        _args: &[String],
+        ctx: &TemplateContext,
+    ) -> Result<String, TemplateError> {
+        let seconds = watch_time(ctx.channel_id, ctx.user_id, &self.db, &self.redis)
+            .await
+            .map_err(|_| TemplateError::Unavailable(String::from(name)))?;
+
+        return Ok(format_duration(seconds));
+    }
+}
+
+/**
+ * `$(followage)` how long the user follows the channel
+ */
+pub struct FollowAgeProvider {
+    pub db: DatabaseConnection,
+    pub redis: Arc<Mutex<Connection>>,
+    pub twitch: TwitchCredentials,
+}
+
+#[async_trait]
+impl VariableProvider for FollowAgeProvider {
+    async fn resolve(
+        &self,
+        name: &str,
+        _args: &[String],
+        ctx: &TemplateContext,
+    ) -> Result<String, TemplateError> {
+        let followed_at = followed_at(ctx.channel_id, ctx.user_id, &self.db, &self.redis, &self.twitch)
+            .await
+            .map_err(|e| {
+                println!("Failed to get followage: {:?}", e);
+                TemplateError::Unavailable(String::from(name))
+            })?;
+
+        return Ok(match followed_at {
+            Some(x) => format_duration((Utc::now() - x).num_seconds()),
+            None => format!("{} is not following", ctx.user_name),
+        });
+    }
+}
+
+/**
+ * `$(counter name)` the value of a counter of the channel
+ */
+pub struct CounterProvider {
+    pub db: DatabaseConnection,
+}
+
+#[async_trait]
+impl VariableProvider for CounterProvider {
+    async fn resolve(
+        &self,
+        name: &str,
+        args: &[String],
+        ctx: &TemplateContext,
+    ) -> Result<String, TemplateError> {
+        let counter = match args.first() {
+            Some(x) => x.trim_start_matches('!'),
+            None => {
+                return Err(TemplateError::InvalidArguments {
+                    name: String::from(name),
+                    expected: String::from("the name of a counter"),
+                })
+            }
+        };
+
+        let counter = database::handler::counter::get_counter(ctx.channel_id, counter, &self.db)
+            .await
+            .map_err(|_| TemplateError::Unavailable(String::from(name)))?;
+
+        return match counter {
+            Some(x) => Ok(x.value.to_string()),
+            None => Err(TemplateError::InvalidArguments {
+                name: String::from(name),
+                expected: String::from("the name of a counter"),
+            }),
+        };
+    }
+}
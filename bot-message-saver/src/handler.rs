@@ -1,19 +1,23 @@
 use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use database::sea_orm::ActiveValue;
 use parser::irc_parser::ParsedMessage;
-use tokio::net::TcpStream;
+use parser::usernotice_tag::UserNoticeEvent;
 use database::entity::chat_message as chat_message_entity;
+use database::entity::sea_orm_active_enums::ModerationActionType;
 use database::entity::user as user_entity;
-use websocket::tokio_tungstenite::MaybeTlsStream;
-use websocket::tokio_tungstenite::WebSocketStream;
+use database::entity::user_notice as user_notice_entity;
+use database::handler::moderation::NewModerationAction;
 use database::sea_orm::prelude::*;
 
 /**
- * Handle the ping event and sends a pong
+ * Get the current time truncated to seconds as stored in the db
  */
-pub async fn handle_ping(ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) -> Result<(), Error> {
-    return websocket::messages::send_message("PONG :tmi.twitch.tv", ws).await;
+fn current_time() -> NaiveDateTime {
+    return DateTime::<Utc>::from_timestamp(Utc::now().timestamp(), 0)
+        .unwrap_or_default()
+        .naive_utc();
 }
 
 /**
@@ -34,27 +38,22 @@ pub async fn handle_privmsg_save(
     };
     let nick = String::from(&msg.source.nick);
     let channel_name = msg.command.params[0].replace("#", "").to_string();
-    let naive_time = match tags.tmi_sent_ts.parse::<i64>() {
-        Ok(x) => NaiveDateTime::from_timestamp_millis(x),
+    let sent_time = match tags.tmi_sent_ts.parse::<i64>() {
+        Ok(x) => DateTime::<Utc>::from_timestamp_millis(x),
         Err(e) => return Err(Error::new(e)),
     };
-    let time = match naive_time {
-        Some(x) => DateTime::<Utc>::from_utc(x, Utc),
-        None => Utc::now(),
-    };
-    let current_time = match NaiveDateTime::from_timestamp_opt(Utc::now().timestamp(), 0) {
+    let time = match sent_time {
         Some(x) => x,
-        None => return Err(Error::msg("Failed to get current time")),
+        None => Utc::now(),
     };
+    let current_time = current_time();
 
     if (users.iter().find(|x| x.id == ActiveValue::Set(tags.user_id))).is_none() {
         users.push(user_entity::ActiveModel {
             id: ActiveValue::Set(tags.user_id),
             nick: ActiveValue::Set(String::from(&nick)),
             display_name: ActiveValue::Set(String::from(&tags.display_name)),
-            updated_at: ActiveValue::Set(
-                NaiveDateTime::from_timestamp_opt(Utc::now().timestamp(), 0).unwrap()
-            ),
+            updated_at: ActiveValue::Set(current_time),
             ..Default::default()
         });
     }
@@ -87,39 +86,97 @@ pub async fn handle_privmsg_save(
         deleted_timestamp: ActiveValue::Set(None),
         created_at: ActiveValue::Set(current_time),
         updated_at: ActiveValue::Set(current_time),
-        ..Default::default()
     });
 
     return Ok(());
 }
 
+/**
+ * Handle the usernotice event and pushes notices and users to their respected vector
+ */
+pub async fn handle_usernotice_save(
+    msg: &ParsedMessage,
+    notice_vec: &mut Vec<user_notice_entity::ActiveModel>,
+    users: &mut Vec<user_entity::ActiveModel>,
+) -> Result<(), Error> {
+    let tags = match msg.usernotice_tags() {
+        Some(x) => x,
+        None => return Err(Error::msg("No tags")),
+    };
+    let channel_name = msg.command.params[0].replace("#", "").to_string();
+    let sent_time = match tags.tmi_sent_ts.parse::<i64>() {
+        Ok(x) => DateTime::<Utc>::from_timestamp_millis(x),
+        Err(e) => return Err(Error::new(e)),
+    };
+    let time = match sent_time {
+        Some(x) => x,
+        None => Utc::now(),
+    };
+    if let UserNoticeEvent::Unknown { msg_id, error: Some(e) } = &tags.event {
+        println!("Failed to parse {} event: {}", msg_id, e);
+    }
+    let event = serde_json::to_string(&tags.event)?;
+    let current_time = current_time();
+
+    if (users.iter().find(|x| x.id == ActiveValue::Set(tags.user_id))).is_none() {
+        users.push(user_entity::ActiveModel {
+            id: ActiveValue::Set(tags.user_id),
+            nick: ActiveValue::Set(String::from(&tags.login)),
+            display_name: ActiveValue::Set(String::from(&tags.display_name)),
+            updated_at: ActiveValue::Set(current_time),
+            ..Default::default()
+        });
+    }
+
+    notice_vec.push(user_notice_entity::ActiveModel {
+        msg_id: ActiveValue::Set(tags.id),
+        channel_id: ActiveValue::Set(tags.room_id),
+        channel_name: ActiveValue::Set(channel_name),
+        user_id: ActiveValue::Set(tags.user_id),
+        nick: ActiveValue::Set(tags.login),
+        display_name: ActiveValue::Set(tags.display_name),
+        notice_type: ActiveValue::Set(tags.msg_id),
+        system_msg: ActiveValue::Set(tags.system_msg),
+        body: ActiveValue::Set(msg.params.clone()),
+        event: ActiveValue::Set(event),
+        timestamp: ActiveValue::Set(time),
+        created_at: ActiveValue::Set(current_time),
+        updated_at: ActiveValue::Set(current_time),
+    });
+
+    return Ok(());
+}
 
 /**
- * Handle the clearmsg event, it marks messages as deleted in the db
+ * Handle the clearmsg event, it marks messages as deleted in the db and records the deletion
  */
 pub async fn handle_clearmsg_update(
     msg: &ParsedMessage,
-    msg_vec: &mut Vec<chat_message_entity::ActiveModel>,
+    msg_vec: &mut [chat_message_entity::ActiveModel],
     db: &DatabaseConnection,
 ) -> Result<(), Error> {
     let tags = match msg.clearmsg_tags() {
         Some(x) => x,
         None => return Err(Error::msg("No tags")),
     };
+    let time = match DateTime::<Utc>::from_timestamp_millis(tags.tmi_sent_ts) {
+        Some(x) => x,
+        None => Utc::now(),
+    };
 
-    match msg_vec
+    let (channel_id, user_id) = match msg_vec
         .iter_mut()
         .find(|x| x.msg_id == ActiveValue::Set(tags.target_msg_id.to_string()))
     {
         Some(x) => {
             x.deleted = ActiveValue::Set(1);
             x.deleted_timestamp = ActiveValue::Set(Some(Utc::now()));
-            x.updated_at = ActiveValue::Set(
-                NaiveDateTime::from_timestamp_opt(Utc::now().timestamp(), 0).unwrap(),
-            );
+            x.updated_at = ActiveValue::Set(current_time());
+
+            (*x.channel_id.as_ref(), *x.user_id.as_ref())
         }
         None => {
-            let chat_message = chat_message_entity::Entity::find_by_id(tags.target_msg_id)
+            let chat_message = chat_message_entity::Entity::find_by_id(tags.target_msg_id.to_string())
                 .one(db)
                 .await?;
 
@@ -127,20 +184,170 @@ pub async fn handle_clearmsg_update(
                 Some(x) => x,
                 None => return Err(Error::msg("No chat message")),
             };
+            let ids = (chat_message.channel_id, chat_message.user_id);
 
             let mut chat_message: chat_message_entity::ActiveModel = chat_message.into();
 
             chat_message.deleted = ActiveValue::Set(1);
             chat_message.deleted_timestamp = ActiveValue::Set(Some(Utc::now()));
-            chat_message.updated_at = ActiveValue::Set(
-                NaiveDateTime::from_timestamp_opt(Utc::now().timestamp(), 0).unwrap(),
-            );
+            chat_message.updated_at = ActiveValue::Set(current_time());
 
             chat_message.update(db).await?;
+
+            ids
+        }
+    };
+
+    // the user of a buffered message may not be saved yet
+    database::handler::user::create_user(
+        user_entity::ActiveModel {
+            id: ActiveValue::Set(user_id),
+            nick: ActiveValue::Set(String::from(&tags.login)),
+            display_name: ActiveValue::Set(String::from(&tags.login)),
+            updated_at: ActiveValue::Set(current_time()),
+            ..Default::default()
+        },
+        db,
+    )
+    .await?;
+
+    database::handler::moderation::record_moderation_action(
+        NewModerationAction {
+            channel_id,
+            target_user_id: Some(user_id),
+            action: ModerationActionType::Delete,
+            duration: None,
+            reason: None,
+            msg_id: Some(tags.target_msg_id),
+            moderator_id: None,
+            automated: false,
+            timestamp: time,
+        },
+        db,
+    )
+    .await?;
+
+    return Ok(());
+}
+
+/**
+ * Handle the clearchat event, it marks all messages of a timed out or banned user as deleted
+ * in the buffer and the db and records the moderation action
+ */
+pub async fn handle_clearchat_update(
+    msg: &ParsedMessage,
+    msg_vec: &mut [chat_message_entity::ActiveModel],
+    db: &DatabaseConnection,
+) -> Result<(), Error> {
+    let tags = match msg.clearchat_tags() {
+        Some(x) => x,
+        None => return Err(Error::msg("No tags")),
+    };
+    let time = match DateTime::<Utc>::from_timestamp_millis(tags.tmi_sent_ts) {
+        Some(x) => x,
+        None => Utc::now(),
+    };
+
+    let action = match (tags.target_user_id, tags.ban_duration) {
+        (None, _) => ModerationActionType::Clear,
+        (Some(_), Some(_)) => ModerationActionType::Timeout,
+        (Some(_), None) => ModerationActionType::Ban,
+    };
+
+    if let Some(target_user_id) = tags.target_user_id {
+        for x in msg_vec.iter_mut().filter(|x| {
+            x.channel_id == ActiveValue::Set(tags.room_id)
+                && x.user_id == ActiveValue::Set(target_user_id)
+        }) {
+            x.deleted = ActiveValue::Set(1);
+            x.deleted_timestamp = ActiveValue::Set(Some(Utc::now()));
+            x.updated_at = ActiveValue::Set(current_time());
         }
+
+        let nick = match &msg.params {
+            Some(x) => String::from(x),
+            None => return Err(Error::msg("No target user")),
+        };
+
+        database::handler::user::create_user(
+            user_entity::ActiveModel {
+                id: ActiveValue::Set(target_user_id),
+                nick: ActiveValue::Set(String::from(&nick)),
+                display_name: ActiveValue::Set(nick),
+                updated_at: ActiveValue::Set(current_time()),
+                ..Default::default()
+            },
+            db,
+        )
+        .await?;
+        database::handler::chat::delete_user_messages(tags.room_id, target_user_id, db).await?;
+    }
+
+    database::handler::moderation::record_moderation_action(
+        NewModerationAction {
+            channel_id: tags.room_id,
+            target_user_id: tags.target_user_id,
+            action,
+            duration: tags.ban_duration,
+            reason: None,
+            msg_id: None,
+            moderator_id: None,
+            automated: false,
+            timestamp: time,
+        },
+        db,
+    )
+    .await?;
+
+    return Ok(());
+}
+
+/**
+ * Handle the roomstate event, it updates the stored chat settings of the channel
+ */
+pub async fn handle_roomstate_update(
+    msg: &ParsedMessage,
+    db: &DatabaseConnection,
+    redis: &mut Connection,
+) -> Result<(), Error> {
+    let tags = match msg.roomstate_tags() {
+        Some(x) => x,
+        None => return Err(Error::msg("No tags")),
+    };
+
+    let mut state = websocket::chat_state::get_chat_state(tags.room_id, db, redis).await?;
+    state.apply_room_state(&tags);
+    websocket::chat_state::save_chat_state(&state, db, redis).await?;
+
+    return Ok(());
+}
+
+/**
+ * Handle the userstate event, it updates the stored standing of the bot in the channel
+ */
+pub async fn handle_userstate_update(
+    msg: &ParsedMessage,
+    db: &DatabaseConnection,
+    redis: &mut Connection,
+) -> Result<(), Error> {
+    let tags = match msg.userstate_tags() {
+        Some(x) => x,
+        None => return Err(Error::msg("No tags")),
     };
+    let channel_name = msg.command.params[0].replace("#", "").to_string();
+
+    let channel = match database::handler::channel::get_channel_by_name(&channel_name, db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg("No channel")),
+    };
+
+    let mut state = websocket::chat_state::get_chat_state(channel.id, db, redis).await?;
+    let previous = state.clone();
+    state.apply_user_state(&tags);
 
-    drop(msg_vec);
+    if state != previous {
+        websocket::chat_state::save_chat_state(&state, db, redis).await?;
+    }
 
     return Ok(());
 }
@@ -4,12 +4,13 @@ use anyhow::{Error, Result};
 use database::entity::bot as bot_entity;
 use database::entity::chat_message as Chat_Message;
 use database::entity::user as User;
+use database::entity::user_notice as User_Notice;
 use dotenvy::dotenv;
 use parser::irc_parser::IRCCommandType;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use websocket::client::connect_channels;
-use websocket::futures_util::StreamExt;
+use websocket::connection::{ConnectionConfig, ConnectionEvent};
+use websocket::pool::{ConnectionPool, PoolEvent};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -20,6 +21,14 @@ async fn main() -> Result<(), Error> {
     let redis_endpoint = std::env::var("REDIS_URL").expect("REDIS_URL not set");
     let client_id = std::env::var("TWITCH_CLIENT_ID").expect("CLIENT_ID not set");
     let client_secret = std::env::var("TWITCH_CLIENT_SECRET").expect("CLIENT_SECRET not set");
+    let channels_per_connection = match std::env::var("TWITCH_CHANNELS_PER_CONNECTION") {
+        Ok(x) => x.parse::<usize>().expect("TWITCH_CHANNELS_PER_CONNECTION is not a number"),
+        Err(_) => 50,
+    };
+    let channel_sync_interval = match std::env::var("TWITCH_CHANNEL_SYNC_INTERVAL") {
+        Ok(x) => x.parse::<u64>().expect("TWITCH_CHANNEL_SYNC_INTERVAL is not a number"),
+        Err(_) => 300,
+    };
 
     let db = match database::connect(&db_endpoint).await {
         Ok(x) => x,
@@ -36,42 +45,52 @@ async fn main() -> Result<(), Error> {
         Err(_) => return Err(Error::msg("Failed to connect to redis")),
     };
 
-    let token = match auth::token::get_bot_token(&bot_name, &client_id, &client_secret, &db, &mut redis).await {
-        Ok(x) => x,
-        Err(_) => return Err(Error::msg("Failed to get bot token")),
-    };
-
-    let mut ws = match websocket::client::connect(&ws_endpoint).await {
+    let channels = match websocket::client::get_active_channel_names(&db).await {
         Ok(x) => x,
-        Err(_) => return Err(Error::msg("Failed to connect to websocket")),
+        Err(_) => return Err(Error::msg("Failed to get channels")),
     };
 
-    match websocket::messages::auth_message(&token, &bot.nick, &mut ws).await {
-        Ok(_) => (),
-        Err(_) => return Err(Error::msg("Failed to send auth message")),
-    }
-
-    match connect_channels(&db, &mut ws).await {
-        Ok(_) => (),
-        Err(_) => return Err(Error::msg("Failed to join channels")),
-    }
+    let (pool, mut events) = ConnectionPool::new(
+        ConnectionConfig::new(
+            &ws_endpoint,
+            &bot_name,
+            &bot.nick,
+            &client_id,
+            &client_secret,
+            &redis_endpoint,
+        ),
+        db.clone(),
+        channels,
+        channels_per_connection,
+    );
+    let pool = Arc::new(Mutex::new(pool));
+    let _control = websocket::control::spawn(
+        Arc::clone(&pool),
+        &redis_endpoint,
+        db.clone(),
+        std::time::Duration::from_secs(channel_sync_interval),
+    );
 
     let chat_messages: Arc<Mutex<Vec<Chat_Message::ActiveModel>>> = Arc::new(Mutex::new(vec![]));
+    let user_notices: Arc<Mutex<Vec<User_Notice::ActiveModel>>> = Arc::new(Mutex::new(vec![]));
     let users: Arc<Mutex<Vec<User::ActiveModel>>> = Arc::new(Mutex::new(vec![]));
 
     let chat_messages_clone = Arc::clone(&chat_messages);
+    let user_notices_clone = Arc::clone(&user_notices);
     let users_clone = Arc::clone(&users);
     let db_clone = db.clone();
 
     let save_loop = tokio::spawn(async move {
         loop {
             let mut messages = chat_messages_clone.lock().await;
+            let mut notices = user_notices_clone.lock().await;
             let mut users = users_clone.lock().await;
 
-            if messages.len() > 0 {
+            if !messages.is_empty() || !notices.is_empty() {
                 let save = database::handler::chat::save_chat_messages(
                     &db_clone,
                     messages.clone(),
+                    notices.clone(),
                     users.clone(),
                 )
                 .await;
@@ -84,82 +103,145 @@ async fn main() -> Result<(), Error> {
             }
 
             messages.clear();
+            notices.clear();
             users.clear();
 
             drop(messages);
+            drop(notices);
             drop(users);
 
             tokio::time::sleep(std::time::Duration::from_secs(30)).await;
         }
     });
 
-    while let Some(msg) = ws.next().await {
-        let msg = match msg {
-            Ok(x) => x,
-            Err(_) => continue,
+    while let Some(PoolEvent { connection, event }) = events.recv().await {
+        let message = match event {
+            ConnectionEvent::Message(x) => x,
+            ConnectionEvent::Connected => {
+                println!("Connection {} connected to twitch irc", connection);
+                continue;
+            }
+            ConnectionEvent::Disconnected(reason) => {
+                println!("Connection {} disconnected from twitch irc: {}", connection, reason);
+                continue;
+            }
+            ConnectionEvent::Reconnecting { attempt, delay } => {
+                println!(
+                    "Connection {} reconnecting to twitch irc in {:?} (attempt {})",
+                    connection, delay, attempt
+                );
+                continue;
+            }
         };
+        let message = message.as_str();
 
-        let messages = match msg.into_text() {
+        let parsed_message = match parser::irc_parser::parse(message).await {
             Ok(x) => x,
-            Err(_) => continue,
+            Err(e) => {
+                println!("Error parsing message: {}", message);
+                println!("Error: {:?}", e);
+                continue;
+            }
         };
 
-        let messages = messages.split("\r\n").collect::<Vec<&str>>();
-
-        for message in messages {
-            let parsed_message = match parser::irc_parser::parse(&message.to_string()).await {
-                Ok(x) => x,
-                Err(e) => {
-                    println!("Error parsing message: {}", message);
-                    println!("Error: {:?}", e);
-                    continue;
-                }
-            };
-
-            let handle = match parsed_message.command.command {
-                IRCCommandType::PING => handler::handle_ping(&mut ws).await,
-                IRCCommandType::PRIVMSG => Ok({
-                    let mut chat_messages = chat_messages.lock().await;
-                    let mut users = users.lock().await;
-                    match handler::handle_privmsg_save(
-                        &parsed_message,
-                        &mut chat_messages,
-                        &mut users,
-                    )
+        let handle: Result<(), Error> = match parsed_message.command.command {
+            IRCCommandType::PRIVMSG => {
+                let mut chat_messages = chat_messages.lock().await;
+                let mut users = users.lock().await;
+                match handler::handle_privmsg_save(
+                    &parsed_message,
+                    &mut chat_messages,
+                    &mut users,
+                )
+                .await
+                {
+                    Ok(_) => (),
+                    Err(e) => {
+                        println!("Error handling privmsg: {}", message);
+                        println!("Error: {:?}", e);
+                    }
+                };
+                drop(chat_messages);
+                drop(users);
+                Ok(())
+            }
+            IRCCommandType::USERNOTICE => {
+                let mut user_notices = user_notices.lock().await;
+                let mut users = users.lock().await;
+                match handler::handle_usernotice_save(
+                    &parsed_message,
+                    &mut user_notices,
+                    &mut users,
+                )
+                .await
+                {
+                    Ok(_) => (),
+                    Err(e) => {
+                        println!("Error handling usernotice: {}", message);
+                        println!("Error: {:?}", e);
+                    }
+                };
+                drop(user_notices);
+                drop(users);
+                Ok(())
+            }
+            IRCCommandType::CLEARMSG => {
+                let mut chat_messages = chat_messages.lock().await;
+                match handler::handle_clearmsg_update(&parsed_message, &mut chat_messages, &db)
                     .await
-                    {
-                        Ok(_) => (),
-                        Err(e) => {
-                            println!("Error handling privmsg: {}", message);
-                            println!("Error: {:?}", e);
-                        }
-                    };
-                    drop(chat_messages);
-                    drop(users);
-                }),
-                IRCCommandType::CLEARMSG => Ok({
-                    let mut chat_messages = chat_messages.lock().await;
-                    match handler::handle_clearmsg_update(&parsed_message, &mut chat_messages, &db)
-                        .await
-                    {
-                        Ok(_) => (),
-                        Err(e) => {
-                            println!("Error handling clearmsg: {}", message);
-                            println!("Error: {:?}", e);
-                        }
-                    };
-                    drop(chat_messages);
-                }),
-                _ => continue,
-            };
-
-            match handle {
-                Ok(_) => continue,
-                Err(e) => {
-                    println!("Error handling message: {}", message);
-                    println!("Error: {:?}", e);
-                    continue;
-                }
+                {
+                    Ok(_) => (),
+                    Err(e) => {
+                        println!("Error handling clearmsg: {}", message);
+                        println!("Error: {:?}", e);
+                    }
+                };
+                drop(chat_messages);
+                Ok(())
+            }
+            IRCCommandType::CLEARCHAT => {
+                let mut chat_messages = chat_messages.lock().await;
+                match handler::handle_clearchat_update(&parsed_message, &mut chat_messages, &db)
+                    .await
+                {
+                    Ok(_) => (),
+                    Err(e) => {
+                        println!("Error handling clearchat: {}", message);
+                        println!("Error: {:?}", e);
+                    }
+                };
+                drop(chat_messages);
+                Ok(())
+            }
+            IRCCommandType::ROOMSTATE => {
+                match handler::handle_roomstate_update(&parsed_message, &db, &mut redis).await {
+                    Ok(_) => (),
+                    Err(e) => {
+                        println!("Error handling roomstate: {}", message);
+                        println!("Error: {:?}", e);
+                    }
+                };
+                Ok(())
+            }
+            IRCCommandType::USERSTATE => {
+                match handler::handle_userstate_update(&parsed_message, &db, &mut redis).await {
+                    Ok(_) => (),
+                    Err(e) => {
+                        println!("Error handling userstate: {}", message);
+                        println!("Error: {:?}", e);
+                    }
+                };
+                Ok(())
+            }
+            _ => continue,
+        };
+
+        match handle {
+            Ok(_) => continue,
+            Err(e) => {
+                println!("Error handling message: {}", message);
+                println!("Error: {:?}", e);
+                continue;
             }
         }
     }
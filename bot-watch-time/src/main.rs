@@ -1,5 +1,15 @@
 use anyhow::{Error, Result};
-use database::{entity::channel as channel_entity, sea_orm::{ActiveValue, TransactionTrait}};
+use chrono::{Duration as ChronoDuration, Utc};
+use database::handler::chat::ChatterActivity;
+use database::{
+    entity::channel as channel_entity,
+    sea_orm::{ActiveValue, DatabaseConnection, TransactionTrait},
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const INTERVAL: Duration = Duration::from_secs(2 * 60);
+const STATUS_WINDOW: ChronoDuration = ChronoDuration::days(7);
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -43,7 +53,7 @@ async fn main() -> Result<(), Error> {
 
             let mut users: Vec<database::entity::user::ActiveModel> = Vec::new();
             for chatter in chatters.clone() {
-                let user_id = i32::from_str_radix(&chatter.user_id, 10).unwrap();
+                let user_id = chatter.user_id.parse::<i32>().unwrap();
                 let user = database::entity::user::ActiveModel {
                     id: ActiveValue::Set(user_id),
                     nick: ActiveValue::Set(chatter.user_login),
@@ -84,14 +94,14 @@ async fn main() -> Result<(), Error> {
                 .iter()
                 .filter(|user| {
                     !chatters.iter().any(|chatter| {
-                        i32::from_str_radix(&chatter.user_id, 10).unwrap() == user.user_id
+                        chatter.user_id.parse::<i32>().unwrap() == user.user_id
                     })
                 })
-                .map(|user| user.user_id.clone())
+                .map(|user| user.user_id)
                 .collect::<Vec<i32>>();
             let new_watching = chatters
                 .iter()
-                .map(|chatter| i32::from_str_radix(&chatter.user_id, 10).unwrap())
+                .map(|chatter| chatter.user_id.parse::<i32>().unwrap())
                 .filter(|chatter| {
                     !currently_watching
                         .iter()
@@ -99,7 +109,7 @@ async fn main() -> Result<(), Error> {
                 })
                 .collect::<Vec<i32>>();
 
-            if no_longer_watching.len() > 0 {
+            if !no_longer_watching.is_empty() {
                 match database::handler::watchtime::stop_watching(
                     channel.id,
                     no_longer_watching,
@@ -113,7 +123,7 @@ async fn main() -> Result<(), Error> {
                     }
                 };
             }
-            if new_watching.len() > 0 {
+            if !new_watching.is_empty() {
                 match database::handler::watchtime::start_watching(channel.id, new_watching, &txn)
                     .await
                 {
@@ -131,8 +141,58 @@ async fn main() -> Result<(), Error> {
                     continue;
                 }
             };
+
+            let user_ids = chatters
+                .iter()
+                .map(|chatter| chatter.user_id.parse::<i32>().unwrap())
+                .collect::<Vec<i32>>();
+            if let Err(e) = accrue_points(channel.id, &user_ids, &db).await {
+                println!("Error: {:?}", e);
+            }
         }
 
-        tokio::time::sleep(std::time::Duration::from_secs(2 * 60)).await;
+        tokio::time::sleep(INTERVAL).await;
+    }
+}
+
+/**
+ * Give the present viewers their points for the interval, subscriber and vip
+ * status come from their recent chat messages
+ */
+async fn accrue_points(
+    channel_id: i32,
+    user_ids: &[i32],
+    db: &DatabaseConnection,
+) -> Result<(), Error> {
+    let settings = database::handler::points::get_points_settings(channel_id, db).await?;
+    if settings.enabled == 0 {
+        return Ok(());
     }
+
+    let now = Utc::now();
+    let activity =
+        database::handler::chat::get_chatter_activity(channel_id, now - STATUS_WINDOW, db)
+            .await?
+            .into_iter()
+            .map(|x| (x.user_id, x))
+            .collect::<HashMap<i32, ChatterActivity>>();
+
+    let chatted_since = now - ChronoDuration::from_std(INTERVAL)?;
+    let amounts = user_ids
+        .iter()
+        .map(|user_id| {
+            let points = match activity.get(user_id) {
+                Some(x) => database::handler::points::accrual(
+                    &settings,
+                    x.subscriber == 1,
+                    x.vip == 1,
+                    x.last_message >= chatted_since,
+                ),
+                None => database::handler::points::accrual(&settings, false, false, false),
+            };
+            (*user_id, points)
+        })
+        .collect::<Vec<(i32, i64)>>();
+
+    return database::handler::points::accrue_points(channel_id, &amounts, db).await;
 }
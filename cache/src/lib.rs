@@ -1,6 +1,7 @@
 use anyhow::{Error, Result};
-use redis::{aio::Connection, AsyncCommands, FromRedisValue};
+use redis::{aio::Connection, aio::PubSub, AsyncCommands, FromRedisValue};
 use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 pub extern crate redis;
 
@@ -46,6 +47,88 @@ pub async fn set_with_ttl(
     return Ok(());
 }
 
+/**
+ * Set a redis value with a ttl only if the key does not exist yet, returns if it was set
+ */
+pub async fn set_if_absent_with_ttl(
+    key: &str,
+    value: &str,
+    ttl: usize,
+    con: &mut Connection,
+) -> Result<bool, Error> {
+    let set: Option<String> = redis::cmd("SET")
+        .arg(key)
+        .arg(value)
+        .arg("NX")
+        .arg("EX")
+        .arg(ttl)
+        .query_async(con)
+        .await?;
+
+    return Ok(set.is_some());
+}
+
+/**
+ * Set redis values with their ttls only if none of the keys exist, all of them
+ * are set or none at once
+ */
+pub async fn set_all_if_absent_with_ttl(
+    values: &[(&str, &str, usize)],
+    con: &mut Connection,
+) -> Result<bool, Error> {
+    let script = redis::Script::new(
+        r"
+        for _, key in ipairs(KEYS) do
+            if redis.call('EXISTS', key) == 1 then
+                return 0
+            end
+        end
+        for i, key in ipairs(KEYS) do
+            redis.call('SET', key, ARGV[i * 2 - 1], 'EX', ARGV[i * 2])
+        end
+        return 1
+        ",
+    );
+
+    let mut invocation = script.prepare_invoke();
+    for (key, value, ttl) in values {
+        invocation.key(*key).arg(*value).arg(*ttl);
+    }
+    let set: i32 = invocation.invoke_async(con).await?;
+
+    return Ok(set == 1);
+}
+
+/**
+ * Let a redis value expire after a ttl
+ */
+pub async fn expire(key: &str, ttl: usize, con: &mut Connection) -> Result<(), Error> {
+    let _: () = con.expire(key, ttl).await?;
+
+    return Ok(());
+}
+
+/**
+ * Get the seconds until a redis value expires, None if it does not exist or never expires
+ */
+pub async fn ttl(key: &str, con: &mut Connection) -> Result<Option<usize>, Error> {
+    let ttl: i64 = con.ttl(key).await?;
+
+    return Ok(match ttl {
+        x if x >= 0 => Some(x as usize),
+        _ => None,
+    });
+}
+
+/**
+ * Increment a redis counter, returns the new value
+ */
+pub async fn incr(key: &str, con: &mut Connection) -> Result<i64, Error> {
+    let value: i64 = con.incr(key, 1).await?;
+
+    return Ok(value);
+}
+
 /**
  * Get a redis value
  */
@@ -55,12 +138,21 @@ pub async fn get<T: FromRedisValue>(key: &str, con: &mut Connection) -> Result<O
     return Ok(value);
 }
 
+/**
+ * Get the redis keys matching a pattern
+ */
+pub async fn keys(pattern: &str, con: &mut Connection) -> Result<Vec<String>, Error> {
+    let keys: Vec<String> = con.keys(pattern).await?;
+
+    return Ok(keys);
+}
+
 /**
  * Get a redis value based on a pattern
  */
 pub async fn mget<T: FromRedisValue>(pattern: &str, con: &mut Connection) -> Result<Vec<T>, Error> {
     let keys: Vec<String> = con.keys(pattern).await?;
-    if keys.len() == 0 {
+    if keys.is_empty() {
         return Ok(vec![]);
     }
     let values: Vec<T> = con.mget(keys).await?;
@@ -86,6 +178,48 @@ where
     };
 }
 
+/**
+ * Change a redis value as deserialized type, the change runs again if another
+ * client changed the value meanwhile. Nothing is written if the value did not
+ * change and a value changed to None is deleted
+ */
+pub async fn update_as<T, R, F>(key: &str, con: &mut Connection, mut change: F) -> Result<R, Error>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnMut(&mut Option<T>) -> R,
+{
+    loop {
+        redis::cmd("WATCH").arg(key).query_async::<_, ()>(con).await?;
+        let before: Option<String> = con.get(key).await?;
+        let mut value: Option<T> = match &before {
+            Some(x) => Some(serde_json::from_str(x)?),
+            None => None,
+        };
+
+        let result = change(&mut value);
+        let after = match &value {
+            Some(x) => Some(serde_json::to_string(x)?),
+            None => None,
+        };
+        if after == before {
+            redis::cmd("UNWATCH").query_async::<_, ()>(con).await?;
+            return Ok(result);
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        match &after {
+            Some(x) => pipe.set(key, x).ignore(),
+            None => pipe.del(key).ignore(),
+        };
+        // EXEC answers nil if the watched key changed
+        let written: Option<()> = pipe.query_async(con).await?;
+        if written.is_some() {
+            return Ok(result);
+        }
+    }
+}
+
 /**
  * Get a redis value as deserialized type vec
  */
@@ -94,6 +228,9 @@ where
     T: DeserializeOwned,
 {
     let keys: Vec<String> = con.keys(pattern).await?;
+    if keys.is_empty() {
+        return Ok(vec![]);
+    }
     let values: Vec<String> = con.mget(keys).await?;
     let values: Vec<T> = values
         .iter()
@@ -103,6 +240,33 @@ where
     return Ok(values);
 }
 
+/**
+ * Add a member to a redis set
+ */
+pub async fn add_to_set(key: &str, member: &str, con: &mut Connection) -> Result<(), Error> {
+    let _: () = con.sadd(key, member).await?;
+
+    return Ok(());
+}
+
+/**
+ * Remove a member from a redis set
+ */
+pub async fn remove_from_set(key: &str, member: &str, con: &mut Connection) -> Result<(), Error> {
+    let _: () = con.srem(key, member).await?;
+
+    return Ok(());
+}
+
+/**
+ * Get the members of a redis set
+ */
+pub async fn set_members(key: &str, con: &mut Connection) -> Result<Vec<String>, Error> {
+    let members: Vec<String> = con.smembers(key).await?;
+
+    return Ok(members);
+}
+
 /**
  * Delete a redis value
  */
@@ -111,3 +275,22 @@ pub async fn delete(key: &str, con: &mut Connection) -> Result<(), Error> {
 
     return Ok(());
 }
+
+/**
+ * Publish a message on a pub/sub channel
+ */
+pub async fn publish(channel: &str, message: &str, con: &mut Connection) -> Result<(), Error> {
+    let _: () = con.publish(channel, message).await?;
+
+    return Ok(());
+}
+
+/**
+ * Open a dedicated connection subscribed to a pub/sub channel
+ */
+pub async fn subscribe(url: &str, channel: &str) -> Result<PubSub, Error> {
+    let mut pubsub = connect(url).await?.into_pubsub();
+    pubsub.subscribe(channel).await?;
+
+    return Ok(pubsub);
+}
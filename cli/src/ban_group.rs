@@ -0,0 +1,159 @@
+use anyhow::{Error, Result};
+use database::entity::channel as channel_entity;
+use database::entity::sea_orm_active_enums::BanShareMode;
+use database::sea_orm::DatabaseConnection;
+
+const DEFAULT_LOG_LIMIT: u64 = 20;
+
+async fn find_channel(
+    name: Option<&String>,
+    db: &DatabaseConnection,
+) -> Result<channel_entity::Model, Error> {
+    let name = match name {
+        Some(x) => x.trim_start_matches('#').to_lowercase(),
+        None => return Err(Error::msg("Missing the channel")),
+    };
+
+    return match database::handler::channel::get_channel_by_name(&name, db).await? {
+        Some(x) => Ok(x),
+        None => Err(Error::msg(format!("Channel {} not found", name))),
+    };
+}
+
+async fn find_group(
+    name: Option<&String>,
+    db: &DatabaseConnection,
+) -> Result<database::entity::ban_group::Model, Error> {
+    let name = match name {
+        Some(x) => x,
+        None => return Err(Error::msg("Missing the group name")),
+    };
+
+    return match database::handler::ban_group::get_ban_group_by_name(name, db).await? {
+        Some(x) => Ok(x),
+        None => Err(Error::msg(format!("Ban group {} not found", name))),
+    };
+}
+
+async fn nick_of(user_id: i32, db: &DatabaseConnection) -> Result<String, Error> {
+    let nick = match database::handler::user::get_user(user_id, db).await? {
+        Some(x) => x.nick,
+        None => user_id.to_string(),
+    };
+
+    return Ok(nick);
+}
+
+fn parse_mode(value: Option<&String>) -> Result<BanShareMode, Error> {
+    return match value.map(|x| x.to_lowercase()).as_deref() {
+        Some("auto") => Ok(BanShareMode::Auto),
+        Some("approval") => Ok(BanShareMode::Approval),
+        _ => Err(Error::msg("--mode needs auto or approval")),
+    };
+}
+
+/**
+ * ban-group create <name>, join <name> <channel> [--mode auto|approval],
+ * leave <channel>, show <name> or log <name> [--limit N], channels of a
+ * group share their bans, with auto a channel takes the bans of the others
+ * right away and with approval its moderators decide with !banshare
+ */
+pub async fn ban_group(args: &[String], db: &DatabaseConnection) -> Result<(), Error> {
+    let mut args = args.iter();
+
+    return match args.next().map(|x| x.as_str()) {
+        Some("create") => {
+            let name = match args.next() {
+                Some(x) => x,
+                None => return Err(Error::msg("Missing the group name")),
+            };
+            if database::handler::ban_group::get_ban_group_by_name(name, db)
+                .await?
+                .is_some()
+            {
+                return Err(Error::msg(format!("Ban group {} exists already", name)));
+            }
+            database::handler::ban_group::create_ban_group(name, db).await?;
+            println!("Created ban group {}", name);
+            Ok(())
+        }
+        Some("join") => {
+            let group = find_group(args.next(), db).await?;
+            let channel = find_channel(args.next(), db).await?;
+            let mut mode = BanShareMode::Approval;
+            while let Some(flag) = args.next() {
+                match flag.as_str() {
+                    "--mode" => mode = parse_mode(args.next())?,
+                    _ => return Err(Error::msg(format!("Unknown option {}", flag))),
+                }
+            }
+            database::handler::ban_group::save_ban_group_member(&group.id, channel.id, mode, db)
+                .await?;
+            println!(
+                "{} shares bans in {} ({:?})",
+                nick_of(channel.id, db).await?,
+                group.name,
+                mode
+            );
+            Ok(())
+        }
+        Some("leave") => {
+            let channel = find_channel(args.next(), db).await?;
+            let nick = nick_of(channel.id, db).await?;
+            match database::handler::ban_group::delete_ban_group_member(channel.id, db).await? {
+                true => println!("{} left its ban group", nick),
+                false => println!("{} is not in a ban group", nick),
+            }
+            Ok(())
+        }
+        Some("show") => {
+            let group = find_group(args.next(), db).await?;
+            let members =
+                database::handler::ban_group::get_ban_group_members(&group.id, db).await?;
+            println!("{}: {} channels", group.name, members.len());
+            for member in members.iter() {
+                println!(
+                    "{} ({:?})",
+                    nick_of(member.channel_id, db).await?,
+                    member.mode
+                );
+            }
+            Ok(())
+        }
+        Some("log") => {
+            let group = find_group(args.next(), db).await?;
+            let mut limit = DEFAULT_LOG_LIMIT;
+            while let Some(flag) = args.next() {
+                match (
+                    flag.as_str(),
+                    args.next().and_then(|x| x.parse::<u64>().ok()),
+                ) {
+                    ("--limit", Some(x)) => limit = x,
+                    ("--limit", None) => return Err(Error::msg("--limit needs a number")),
+                    _ => return Err(Error::msg(format!("Unknown option {}", flag))),
+                }
+            }
+            let shared_bans =
+                database::handler::ban_group::get_group_shared_bans(&group.id, limit, db).await?;
+            for shared_ban in shared_bans.iter() {
+                let decided_by = match shared_ban.decided_by {
+                    Some(x) => nick_of(x, db).await?,
+                    None => String::from("-"),
+                };
+                println!(
+                    "{} {} {} -> {} {:?} by {}",
+                    shared_ban.created_at,
+                    nick_of(shared_ban.user_id, db).await?,
+                    nick_of(shared_ban.source_channel_id, db).await?,
+                    nick_of(shared_ban.channel_id, db).await?,
+                    shared_ban.status,
+                    decided_by
+                );
+            }
+            Ok(())
+        }
+        _ => Err(Error::msg(
+            "ban-group needs create, join, leave, show or log",
+        )),
+    };
+}
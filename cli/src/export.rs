@@ -0,0 +1,119 @@
+use anyhow::{Error, Result};
+use database::entity::quote as quote_entity;
+use database::sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::io::Write;
+
+/**
+ * A quote as it is exported
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportedQuote {
+    pub number: i32,
+    pub text: String,
+    pub quoted_user: Option<String>,
+    pub game: Option<String>,
+    pub added_by: i32,
+    pub created_at: String,
+}
+
+impl From<quote_entity::Model> for ExportedQuote {
+    fn from(quote: quote_entity::Model) -> Self {
+        return ExportedQuote {
+            number: quote.number,
+            text: quote.text,
+            quoted_user: quote.quoted_user,
+            game: quote.game,
+            added_by: quote.added_by,
+            created_at: quote.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+    }
+}
+
+/**
+ * Quote a csv field if it contains a separator, quote or line break
+ */
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+
+    return String::from(value);
+}
+
+/**
+ * Write quotes as csv with a header row
+ */
+pub fn quotes_to_csv(quotes: &[ExportedQuote]) -> String {
+    let mut csv = String::from("number,text,quoted_user,game,added_by,created_at\n");
+
+    for quote in quotes {
+        let fields = [
+            quote.number.to_string(),
+            csv_field(&quote.text),
+            csv_field(quote.quoted_user.as_deref().unwrap_or_default()),
+            csv_field(quote.game.as_deref().unwrap_or_default()),
+            quote.added_by.to_string(),
+            csv_field(&quote.created_at),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    return csv;
+}
+
+/**
+ * export-quotes <channel> [--format csv|json] [--output file], writes to
+ * stdout without an output file
+ */
+pub async fn export_quotes(args: &[String], db: &DatabaseConnection) -> Result<(), Error> {
+    let mut channel: Option<&str> = None;
+    let mut format = "csv";
+    let mut output: Option<&str> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().map(|x| x.as_str()) {
+                Some(x @ ("csv" | "json")) => format = x,
+                _ => return Err(Error::msg("--format needs csv or json")),
+            },
+            "--output" => match args.next() {
+                Some(x) => output = Some(x),
+                None => return Err(Error::msg("--output needs a file")),
+            },
+            x => channel = Some(x),
+        }
+    }
+
+    let channel = match channel {
+        Some(x) => x.trim_start_matches('#').to_lowercase(),
+        None => return Err(Error::msg("Missing the channel")),
+    };
+    let channel = match database::handler::channel::get_channel_by_name(&channel, db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg(format!("Channel {} not found", channel))),
+    };
+
+    let quotes = database::handler::quote::get_quotes(channel.id, db)
+        .await?
+        .into_iter()
+        .map(ExportedQuote::from)
+        .collect::<Vec<ExportedQuote>>();
+
+    let content = match format {
+        "json" => serde_json::to_string_pretty(&quotes)?,
+        _ => quotes_to_csv(&quotes),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, content)?;
+            eprintln!("Exported {} quotes to {}", quotes.len(), path);
+        }
+        None => std::io::stdout().write_all(content.as_bytes())?,
+    }
+
+    return Ok(());
+}
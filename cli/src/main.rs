@@ -0,0 +1,45 @@
+mod ban_group;
+mod export;
+mod raffle;
+mod settings;
+
+#[cfg(test)]
+mod test;
+
+use anyhow::{Error, Result};
+use dotenvy::dotenv;
+
+const USAGE: &str = "Usage:
+  bot-cli ban-group create <name>|join <name> <channel> [--mode auto|approval]|leave <channel>|show <name>|log <name> [--limit N]
+  bot-cli export-quotes <channel> [--format csv|json] [--output file]
+  bot-cli points-settings <channel> [--enabled on|off] [--per-interval N] [--subscriber percent] [--vip percent] [--chat-bonus N]
+  bot-cli spam-filter <channel> link|caps|emotes|symbols|repeat|length [--enabled on|off] [--threshold N] [--min-length N] [--exempt everyone|subscriber|vip|moderator] [--timeout seconds] [--long-timeout seconds]
+  bot-cli strike-settings <channel> [--decay-hours N] [--ladder warn,60,600,ban]
+  bot-cli verify-raffle <raffle id>";
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    dotenv().ok();
+    let db_endpoint = std::env::var("DATABASE_URL").expect("DB_URL not set");
+
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let command = match args.first() {
+        Some(x) => x.as_str(),
+        None => return Err(Error::msg(USAGE)),
+    };
+
+    let db = match database::connect(&db_endpoint).await {
+        Ok(x) => x,
+        Err(_) => return Err(Error::msg("Failed to connect to database")),
+    };
+
+    return match command {
+        "ban-group" => ban_group::ban_group(&args[1..], &db).await,
+        "export-quotes" => export::export_quotes(&args[1..], &db).await,
+        "points-settings" => settings::points_settings(&args[1..], &db).await,
+        "spam-filter" => settings::spam_filter(&args[1..], &db).await,
+        "strike-settings" => settings::strike_settings(&args[1..], &db).await,
+        "verify-raffle" => raffle::verify_raffle(&args[1..], &db).await,
+        _ => Err(Error::msg(USAGE)),
+    };
+}
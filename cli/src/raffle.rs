@@ -0,0 +1,36 @@
+use anyhow::{Error, Result};
+use database::sea_orm::DatabaseConnection;
+
+/**
+ * verify-raffle <raffle id>, replays the draws of a raffle from its revealed
+ * seed and prints the winners
+ */
+pub async fn verify_raffle(args: &[String], db: &DatabaseConnection) -> Result<(), Error> {
+    let id = match args.first() {
+        Some(x) => x,
+        None => return Err(Error::msg("Missing the raffle id")),
+    };
+    let raffle = match database::handler::raffle::get_raffle(id, db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg(format!("Raffle {} not found", id))),
+    };
+    let entries = database::handler::raffle::get_entries(&raffle.id, db).await?;
+
+    println!(
+        "seed: {}\nseed hash: {}\nentries: {}",
+        raffle.seed,
+        raffle.seed_hash,
+        entries.len()
+    );
+
+    let winners = match database::handler::raffle::verify_draws(&raffle, &entries) {
+        Ok(x) => x,
+        Err(e) => return Err(Error::msg(format!("Verification failed: {}", e))),
+    };
+    for (i, user_id) in winners.iter().enumerate() {
+        println!("draw {}: user {}", i + 1, user_id);
+    }
+    println!("All {} draws match the seed", winners.len());
+
+    return Ok(());
+}
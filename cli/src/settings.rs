@@ -0,0 +1,191 @@
+use anyhow::{Error, Result};
+use database::entity::sea_orm_active_enums::{Permission, SpamFilterType};
+use database::sea_orm::DatabaseConnection;
+
+fn parse_number(flag: &str, value: Option<&String>) -> Result<i32, Error> {
+    return match value.and_then(|x| x.parse::<i32>().ok()) {
+        Some(x) if x >= 0 => Ok(x),
+        _ => Err(Error::msg(format!("{} needs a number", flag))),
+    };
+}
+
+/**
+ * points-settings <channel> [--enabled on|off] [--per-interval N]
+ * [--subscriber percent] [--vip percent] [--chat-bonus N], prints the
+ * settings after changing the given ones
+ */
+pub async fn points_settings(args: &[String], db: &DatabaseConnection) -> Result<(), Error> {
+    let mut args = args.iter();
+    let channel = match args.next() {
+        Some(x) => x.trim_start_matches('#').to_lowercase(),
+        None => return Err(Error::msg("Missing the channel")),
+    };
+    let channel = match database::handler::channel::get_channel_by_name(&channel, db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg(format!("Channel {} not found", channel))),
+    };
+
+    let mut settings = database::handler::points::get_points_settings(channel.id, db).await?;
+    let mut changed = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--enabled" => match args.next().map(|x| x.as_str()) {
+                Some("on") => settings.enabled = true as i8,
+                Some("off") => settings.enabled = false as i8,
+                _ => return Err(Error::msg("--enabled needs on or off")),
+            },
+            "--per-interval" => settings.points_per_interval = parse_number(flag, args.next())?,
+            "--subscriber" => settings.subscriber_multiplier = parse_number(flag, args.next())?,
+            "--vip" => settings.vip_multiplier = parse_number(flag, args.next())?,
+            "--chat-bonus" => settings.chat_bonus = parse_number(flag, args.next())?,
+            _ => return Err(Error::msg(format!("Unknown option {}", flag))),
+        }
+        changed = true;
+    }
+
+    if changed {
+        database::handler::points::save_points_settings(settings.clone(), db).await?;
+    }
+
+    println!(
+        "enabled: {}\nper interval: {}\nsubscriber: {}%\nvip: {}%\nchat bonus: {}",
+        settings.enabled == 1,
+        settings.points_per_interval,
+        settings.subscriber_multiplier,
+        settings.vip_multiplier,
+        settings.chat_bonus
+    );
+
+    return Ok(());
+}
+
+fn parse_filter(value: Option<&String>) -> Result<SpamFilterType, Error> {
+    return match value.map(|x| x.to_lowercase()).as_deref() {
+        Some("link") => Ok(SpamFilterType::Link),
+        Some("caps") => Ok(SpamFilterType::Caps),
+        Some("emotes") => Ok(SpamFilterType::Emotes),
+        Some("symbols") => Ok(SpamFilterType::Symbols),
+        Some("repeat") => Ok(SpamFilterType::Repeat),
+        Some("length") => Ok(SpamFilterType::Length),
+        _ => Err(Error::msg(
+            "The filter has to be link, caps, emotes, symbols, repeat or length",
+        )),
+    };
+}
+
+fn parse_permission(value: Option<&String>) -> Result<Permission, Error> {
+    return match value.map(|x| x.to_lowercase()).as_deref() {
+        Some("everyone") => Ok(Permission::Everyone),
+        Some("subscriber") => Ok(Permission::Subscriber),
+        Some("vip") => Ok(Permission::Vip),
+        Some("moderator") => Ok(Permission::Moderator),
+        _ => Err(Error::msg("--exempt needs everyone, subscriber, vip or moderator")),
+    };
+}
+
+/**
+ * spam-filter <channel> <filter> [--enabled on|off] [--threshold N]
+ * [--min-length N] [--exempt permission] [--timeout seconds]
+ * [--long-timeout seconds], prints the filter after changing the given
+ * settings
+ */
+pub async fn spam_filter(args: &[String], db: &DatabaseConnection) -> Result<(), Error> {
+    let mut args = args.iter();
+    let channel = match args.next() {
+        Some(x) => x.trim_start_matches('#').to_lowercase(),
+        None => return Err(Error::msg("Missing the channel")),
+    };
+    let channel = match database::handler::channel::get_channel_by_name(&channel, db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg(format!("Channel {} not found", channel))),
+    };
+    let filter = parse_filter(args.next())?;
+
+    let mut settings =
+        database::handler::spam_filter::get_spam_filter(channel.id, filter, db).await?;
+    let mut changed = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--enabled" => match args.next().map(|x| x.as_str()) {
+                Some("on") => settings.enabled = true as i8,
+                Some("off") => settings.enabled = false as i8,
+                _ => return Err(Error::msg("--enabled needs on or off")),
+            },
+            "--threshold" => settings.threshold = parse_number(flag, args.next())?,
+            "--min-length" => settings.min_length = parse_number(flag, args.next())?,
+            "--exempt" => settings.exempt_permission = parse_permission(args.next())?,
+            "--timeout" => settings.timeout = parse_number(flag, args.next())?,
+            "--long-timeout" => settings.long_timeout = parse_number(flag, args.next())?,
+            _ => return Err(Error::msg(format!("Unknown option {}", flag))),
+        }
+        changed = true;
+    }
+
+    if settings.timeout < 1 || settings.long_timeout < 1 {
+        return Err(Error::msg("A timeout lasts at least 1 second"));
+    }
+    if changed {
+        database::handler::spam_filter::save_spam_filter(settings.clone(), db).await?;
+    }
+
+    println!(
+        "enabled: {}\nthreshold: {}\nmin length: {}\nexempt: {:?} and up\ntimeout: {}s\nlong timeout: {}s",
+        settings.enabled == 1,
+        settings.threshold,
+        settings.min_length,
+        settings.exempt_permission,
+        settings.timeout,
+        settings.long_timeout
+    );
+
+    return Ok(());
+}
+
+/**
+ * strike-settings <channel> [--decay-hours N] [--ladder warn,60,600,ban],
+ * prints the settings after changing the given ones
+ */
+pub async fn strike_settings(args: &[String], db: &DatabaseConnection) -> Result<(), Error> {
+    let mut args = args.iter();
+    let channel = match args.next() {
+        Some(x) => x.trim_start_matches('#').to_lowercase(),
+        None => return Err(Error::msg("Missing the channel")),
+    };
+    let channel = match database::handler::channel::get_channel_by_name(&channel, db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg(format!("Channel {} not found", channel))),
+    };
+
+    let mut settings = database::handler::strike::get_strike_settings(channel.id, db).await?;
+    let mut changed = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--decay-hours" => settings.decay_hours = parse_number(flag, args.next())?,
+            "--ladder" => match args.next() {
+                Some(x) => settings.ladder = x.to_lowercase(),
+                None => return Err(Error::msg("--ladder needs steps like warn,60,600,ban")),
+            },
+            _ => return Err(Error::msg(format!("Unknown option {}", flag))),
+        }
+        changed = true;
+    }
+
+    let ladder = match database::handler::strike::parse_ladder(&settings.ladder) {
+        Ok(x) if !x.is_empty() => x,
+        Ok(_) => return Err(Error::msg("The ladder needs at least one step")),
+        Err(e) => return Err(Error::msg(e)),
+    };
+    if settings.decay_hours < 1 {
+        return Err(Error::msg("Strikes last at least 1 hour"));
+    }
+    if changed {
+        database::handler::strike::save_strike_settings(settings.clone(), db).await?;
+    }
+
+    println!(
+        "decay: {} hours\nladder: {:?}",
+        settings.decay_hours, ladder
+    );
+
+    return Ok(());
+}
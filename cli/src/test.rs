@@ -0,0 +1,30 @@
+use crate::export::{quotes_to_csv, ExportedQuote};
+
+#[test]
+fn quotes_to_csv_test() {
+    let quotes = vec![
+        ExportedQuote {
+            number: 1,
+            text: "hello world".to_string(),
+            quoted_user: Some("foo".to_string()),
+            game: None,
+            added_by: 2,
+            created_at: "2024-01-31 12:00:00".to_string(),
+        },
+        ExportedQuote {
+            number: 2,
+            text: "he said \"hi, there\"".to_string(),
+            quoted_user: None,
+            game: Some("Just Chatting".to_string()),
+            added_by: 2,
+            created_at: "2024-02-01 12:00:00".to_string(),
+        },
+    ];
+
+    assert_eq!(
+        quotes_to_csv(&quotes),
+        "number,text,quoted_user,game,added_by,created_at\n\
+         1,hello world,foo,,2,2024-01-31 12:00:00\n\
+         2,\"he said \"\"hi, there\"\"\",,Just Chatting,2,2024-02-01 12:00:00\n"
+    );
+}
@@ -9,14 +9,53 @@ pub struct Model {
     pub id: i32,
     pub active: i8,
     pub live: i8,
+    pub last_quote_number: i32,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::audit_log::Entity")]
+    AuditLog,
+    #[sea_orm(has_many = "super::auto_responder::Entity")]
+    AutoResponder,
+    #[sea_orm(has_one = "super::ban_group_member::Entity")]
+    BanGroupMember,
+    #[sea_orm(has_many = "super::banned_phrase::Entity")]
+    BannedPhrase,
+    #[sea_orm(has_one = "super::channel_chat_state::Entity")]
+    ChannelChatState,
     #[sea_orm(has_many = "super::chat_message::Entity")]
     ChatMessage,
+    #[sea_orm(has_many = "super::command::Entity")]
+    Command,
+    #[sea_orm(has_many = "super::counter::Entity")]
+    Counter,
+    #[sea_orm(has_many = "super::moderation_action::Entity")]
+    ModerationAction,
+    #[sea_orm(has_many = "super::points_balance::Entity")]
+    PointsBalance,
+    #[sea_orm(has_many = "super::points_ledger::Entity")]
+    PointsLedger,
+    #[sea_orm(has_one = "super::points_settings::Entity")]
+    PointsSettings,
+    #[sea_orm(has_many = "super::quote::Entity")]
+    Quote,
+    #[sea_orm(has_many = "super::raffle::Entity")]
+    Raffle,
+    #[sea_orm(has_many = "super::shared_ban::Entity")]
+    SharedBan,
+    #[sea_orm(has_many = "super::spam_filter::Entity")]
+    SpamFilter,
+    #[sea_orm(has_many = "super::stream_history::Entity")]
+    StreamHistory,
+    #[sea_orm(has_many = "super::strike::Entity")]
+    Strike,
+    #[sea_orm(has_one = "super::strike_settings::Entity")]
+    StrikeSettings,
+    #[sea_orm(has_many = "super::timer::Entity")]
+    Timer,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::Id",
@@ -25,22 +64,144 @@ pub enum Relation {
         on_delete = "Restrict"
     )]
     User,
+    #[sea_orm(has_many = "super::user_notice::Entity")]
+    UserNotice,
     #[sea_orm(has_many = "super::watch_time::Entity")]
     WatchTime,
 }
 
+impl Related<super::audit_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuditLog.def()
+    }
+}
+
+impl Related<super::auto_responder::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AutoResponder.def()
+    }
+}
+
+impl Related<super::ban_group_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BanGroupMember.def()
+    }
+}
+
+impl Related<super::banned_phrase::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BannedPhrase.def()
+    }
+}
+
+impl Related<super::channel_chat_state::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChannelChatState.def()
+    }
+}
+
 impl Related<super::chat_message::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ChatMessage.def()
     }
 }
 
+impl Related<super::command::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Command.def()
+    }
+}
+
+impl Related<super::counter::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Counter.def()
+    }
+}
+
+impl Related<super::points_balance::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PointsBalance.def()
+    }
+}
+
+impl Related<super::points_ledger::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PointsLedger.def()
+    }
+}
+
+impl Related<super::points_settings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PointsSettings.def()
+    }
+}
+
+impl Related<super::quote::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Quote.def()
+    }
+}
+
+impl Related<super::raffle::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Raffle.def()
+    }
+}
+
+impl Related<super::shared_ban::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SharedBan.def()
+    }
+}
+
+impl Related<super::spam_filter::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SpamFilter.def()
+    }
+}
+
+impl Related<super::stream_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StreamHistory.def()
+    }
+}
+
+impl Related<super::strike::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Strike.def()
+    }
+}
+
+impl Related<super::strike_settings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StrikeSettings.def()
+    }
+}
+
+impl Related<super::timer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Timer.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
     }
 }
 
+impl Related<super::moderation_action::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ModerationAction.def()
+    }
+}
+
+impl Related<super::user_notice::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserNotice.def()
+    }
+}
+
 impl Related<super::watch_time::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::WatchTime.def()
@@ -13,6 +13,9 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub body: String,
     pub permission: Permission,
+    pub global_cooldown: i32,
+    pub user_cooldown: i32,
+    pub cooldown_whisper: i8,
     pub invoke_count: i32,
     pub last_invoked: Option<DateTimeUtc>,
     pub created_at: DateTime,
@@ -2,9 +2,31 @@
 
 pub mod prelude;
 
+pub mod audit_log;
+pub mod auto_responder;
+pub mod ban_group;
+pub mod ban_group_member;
+pub mod banned_phrase;
 pub mod bot;
 pub mod channel;
+pub mod channel_chat_state;
 pub mod chat_message;
+pub mod command;
+pub mod counter;
+pub mod moderation_action;
+pub mod points_balance;
+pub mod points_ledger;
+pub mod points_settings;
+pub mod quote;
+pub mod raffle;
+pub mod raffle_entry;
 pub mod sea_orm_active_enums;
+pub mod shared_ban;
+pub mod spam_filter;
+pub mod stream_history;
+pub mod strike;
+pub mod strike_settings;
+pub mod timer;
 pub mod user;
+pub mod user_notice;
 pub mod watch_time;
@@ -1,7 +1,29 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
+pub use super::audit_log::Entity as AuditLog;
+pub use super::auto_responder::Entity as AutoResponder;
+pub use super::ban_group::Entity as BanGroup;
+pub use super::ban_group_member::Entity as BanGroupMember;
+pub use super::banned_phrase::Entity as BannedPhrase;
 pub use super::bot::Entity as Bot;
 pub use super::channel::Entity as Channel;
+pub use super::channel_chat_state::Entity as ChannelChatState;
 pub use super::chat_message::Entity as ChatMessage;
+pub use super::command::Entity as Command;
+pub use super::counter::Entity as Counter;
+pub use super::moderation_action::Entity as ModerationAction;
+pub use super::points_balance::Entity as PointsBalance;
+pub use super::points_ledger::Entity as PointsLedger;
+pub use super::points_settings::Entity as PointsSettings;
+pub use super::quote::Entity as Quote;
+pub use super::raffle::Entity as Raffle;
+pub use super::raffle_entry::Entity as RaffleEntry;
+pub use super::shared_ban::Entity as SharedBan;
+pub use super::spam_filter::Entity as SpamFilter;
+pub use super::stream_history::Entity as StreamHistory;
+pub use super::strike::Entity as Strike;
+pub use super::strike_settings::Entity as StrikeSettings;
+pub use super::timer::Entity as Timer;
 pub use super::user::Entity as User;
+pub use super::user_notice::Entity as UserNotice;
 pub use super::watch_time::Entity as WatchTime;
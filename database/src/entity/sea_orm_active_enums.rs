@@ -14,3 +14,122 @@ pub enum UserType {
     #[sea_orm(string_value = "STAFF")]
     Staff,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "moderation_action_type"
+)]
+pub enum ModerationActionType {
+    #[sea_orm(string_value = "BAN")]
+    Ban,
+    #[sea_orm(string_value = "TIMEOUT")]
+    Timeout,
+    #[sea_orm(string_value = "CLEAR")]
+    Clear,
+    #[sea_orm(string_value = "DELETE")]
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "permission")]
+pub enum Permission {
+    #[sea_orm(string_value = "EVERYONE")]
+    Everyone,
+    #[sea_orm(string_value = "SUBSCRIBER")]
+    Subscriber,
+    #[sea_orm(string_value = "VIP")]
+    Vip,
+    #[sea_orm(string_value = "MODERATOR")]
+    Moderator,
+    #[sea_orm(string_value = "BROADCASTER")]
+    Broadcaster,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "auto_responder_type"
+)]
+pub enum AutoResponderType {
+    #[sea_orm(string_value = "KEYWORD")]
+    Keyword,
+    #[sea_orm(string_value = "REGEX")]
+    Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "raffle_status")]
+pub enum RaffleStatus {
+    #[sea_orm(string_value = "OPEN")]
+    Open,
+    #[sea_orm(string_value = "DRAWN")]
+    Drawn,
+    #[sea_orm(string_value = "CANCELLED")]
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "spam_filter_type")]
+pub enum SpamFilterType {
+    #[sea_orm(string_value = "LINK")]
+    Link,
+    #[sea_orm(string_value = "CAPS")]
+    Caps,
+    #[sea_orm(string_value = "EMOTES")]
+    Emotes,
+    #[sea_orm(string_value = "SYMBOLS")]
+    Symbols,
+    #[sea_orm(string_value = "REPEAT")]
+    Repeat,
+    #[sea_orm(string_value = "LENGTH")]
+    Length,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "banned_phrase_type")]
+pub enum BannedPhraseType {
+    #[sea_orm(string_value = "TERM")]
+    Term,
+    #[sea_orm(string_value = "WILDCARD")]
+    Wildcard,
+    #[sea_orm(string_value = "REGEX")]
+    Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "banned_phrase_action")]
+pub enum BannedPhraseAction {
+    #[sea_orm(string_value = "DELETE")]
+    Delete,
+    #[sea_orm(string_value = "TIMEOUT")]
+    Timeout,
+    #[sea_orm(string_value = "BAN")]
+    Ban,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "ban_share_mode")]
+pub enum BanShareMode {
+    #[sea_orm(string_value = "AUTO")]
+    Auto,
+    #[sea_orm(string_value = "APPROVAL")]
+    Approval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "shared_ban_status")]
+pub enum SharedBanStatus {
+    #[sea_orm(string_value = "PENDING")]
+    Pending,
+    #[sea_orm(string_value = "APPLIED")]
+    Applied,
+    #[sea_orm(string_value = "REJECTED")]
+    Rejected,
+    #[sea_orm(string_value = "FAILED")]
+    Failed,
+    #[sea_orm(string_value = "UNDONE")]
+    Undone,
+}
@@ -18,14 +18,40 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::audit_log::Entity")]
+    AuditLog,
     #[sea_orm(has_many = "super::channel::Entity")]
     Channel,
     #[sea_orm(has_many = "super::chat_message::Entity")]
     ChatMessage,
+    #[sea_orm(has_many = "super::moderation_action::Entity")]
+    ModerationAction,
+    #[sea_orm(has_many = "super::points_balance::Entity")]
+    PointsBalance,
+    #[sea_orm(has_many = "super::points_ledger::Entity")]
+    PointsLedger,
+    #[sea_orm(has_many = "super::quote::Entity")]
+    Quote,
+    #[sea_orm(has_many = "super::raffle::Entity")]
+    Raffle,
+    #[sea_orm(has_many = "super::raffle_entry::Entity")]
+    RaffleEntry,
+    #[sea_orm(has_many = "super::shared_ban::Entity")]
+    SharedBan,
+    #[sea_orm(has_many = "super::strike::Entity")]
+    Strike,
+    #[sea_orm(has_many = "super::user_notice::Entity")]
+    UserNotice,
     #[sea_orm(has_many = "super::watch_time::Entity")]
     WatchTime,
 }
 
+impl Related<super::audit_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuditLog.def()
+    }
+}
+
 impl Related<super::channel::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Channel.def()
@@ -38,6 +64,60 @@ impl Related<super::chat_message::Entity> for Entity {
     }
 }
 
+impl Related<super::moderation_action::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ModerationAction.def()
+    }
+}
+
+impl Related<super::points_balance::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PointsBalance.def()
+    }
+}
+
+impl Related<super::points_ledger::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PointsLedger.def()
+    }
+}
+
+impl Related<super::quote::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Quote.def()
+    }
+}
+
+impl Related<super::raffle::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Raffle.def()
+    }
+}
+
+impl Related<super::raffle_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RaffleEntry.def()
+    }
+}
+
+impl Related<super::shared_ban::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SharedBan.def()
+    }
+}
+
+impl Related<super::strike::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Strike.def()
+    }
+}
+
+impl Related<super::user_notice::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserNotice.def()
+    }
+}
+
 impl Related<super::watch_time::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::WatchTime.def()
@@ -0,0 +1,46 @@
+use crate::entity::audit_log as audit_log_entity;
+use anyhow::{Error, Result};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set};
+
+/**
+ * Record who changed what in a channel, details are free form (usually JSON)
+ */
+pub async fn create_audit_log<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    action: &str,
+    target: &str,
+    details: Option<String>,
+    db: &T,
+) -> Result<(), Error> {
+    let audit_log = audit_log_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        channel_id: Set(channel_id),
+        user_id: Set(user_id),
+        action: Set(String::from(action)),
+        target: Set(String::from(target)),
+        details: Set(details),
+        ..Default::default()
+    };
+
+    audit_log_entity::Entity::insert(audit_log).exec(db).await?;
+
+    return Ok(());
+}
+
+/**
+ * Get the latest audit log entries of a channel
+ */
+pub async fn get_audit_logs<T: ConnectionTrait>(
+    channel_id: i32,
+    limit: u64,
+    db: &T,
+) -> Result<Vec<audit_log_entity::Model>, Error> {
+    let audit_logs = audit_log_entity::Entity::find()
+        .filter(audit_log_entity::Column::ChannelId.eq(channel_id))
+        .order_by_desc(audit_log_entity::Column::CreatedAt)
+        .limit(limit)
+        .all(db)
+        .await?;
+    return Ok(audit_logs);
+}
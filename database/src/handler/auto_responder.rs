@@ -0,0 +1,44 @@
+use crate::entity::auto_responder as auto_responder_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, QueryOrder};
+
+/**
+ * Get the enabled auto responders of a channel ordered by name
+ */
+pub async fn get_enabled_auto_responders<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<auto_responder_entity::Model>, Error> {
+    let auto_responders = auto_responder_entity::Entity::find()
+        .filter(auto_responder_entity::Column::ChannelId.eq(channel_id))
+        .filter(auto_responder_entity::Column::Enabled.eq(true as i8))
+        .order_by_asc(auto_responder_entity::Column::Name)
+        .all(db)
+        .await?;
+    return Ok(auto_responders);
+}
+
+/**
+ * Count a response of an auto responder
+ */
+pub async fn record_invocation<T: ConnectionTrait>(
+    id: &str,
+    timestamp: DateTimeUtc,
+    db: &T,
+) -> Result<(), Error> {
+    auto_responder_entity::Entity::update_many()
+        .col_expr(
+            auto_responder_entity::Column::InvokeCount,
+            Expr::col(auto_responder_entity::Column::InvokeCount).add(1),
+        )
+        .col_expr(
+            auto_responder_entity::Column::LastInvoked,
+            Expr::value(timestamp),
+        )
+        .filter(auto_responder_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
@@ -0,0 +1,255 @@
+use crate::entity::ban_group as ban_group_entity;
+use crate::entity::ban_group_member as ban_group_member_entity;
+use crate::entity::sea_orm_active_enums::{BanShareMode, SharedBanStatus};
+use crate::entity::shared_ban as shared_ban_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{prelude::*, Condition, QueryOrder, QuerySelect, Set};
+
+/**
+ * Get a ban group by its name
+ */
+pub async fn get_ban_group_by_name<T: ConnectionTrait>(
+    name: &str,
+    db: &T,
+) -> Result<Option<ban_group_entity::Model>, Error> {
+    let ban_group = ban_group_entity::Entity::find()
+        .filter(ban_group_entity::Column::Name.eq(name))
+        .one(db)
+        .await?;
+    return Ok(ban_group);
+}
+
+/**
+ * Create an empty ban group
+ */
+pub async fn create_ban_group<T: ConnectionTrait>(
+    name: &str,
+    db: &T,
+) -> Result<ban_group_entity::Model, Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let ban_group = ban_group_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        name: Set(String::from(name)),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+    let ban_group = ban_group.insert(db).await?;
+
+    return Ok(ban_group);
+}
+
+/**
+ * Get the channels of a ban group
+ */
+pub async fn get_ban_group_members<T: ConnectionTrait>(
+    group_id: &str,
+    db: &T,
+) -> Result<Vec<ban_group_member_entity::Model>, Error> {
+    let members = ban_group_member_entity::Entity::find()
+        .filter(ban_group_member_entity::Column::GroupId.eq(group_id))
+        .order_by_asc(ban_group_member_entity::Column::CreatedAt)
+        .all(db)
+        .await?;
+    return Ok(members);
+}
+
+/**
+ * Get the membership of a channel, a channel is in at most one group
+ */
+pub async fn get_ban_group_member<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Option<ban_group_member_entity::Model>, Error> {
+    let member = ban_group_member_entity::Entity::find()
+        .filter(ban_group_member_entity::Column::ChannelId.eq(channel_id))
+        .one(db)
+        .await?;
+    return Ok(member);
+}
+
+/**
+ * Add a channel to a ban group or change how it takes the bans of the group,
+ * a channel in another group moves to this one
+ */
+pub async fn save_ban_group_member<T: ConnectionTrait>(
+    group_id: &str,
+    channel_id: i32,
+    mode: BanShareMode,
+    db: &T,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let member = ban_group_member_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        group_id: Set(String::from(group_id)),
+        channel_id: Set(channel_id),
+        mode: Set(mode),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    ban_group_member_entity::Entity::insert(member)
+        .on_conflict(
+            OnConflict::column(ban_group_member_entity::Column::ChannelId)
+                .update_columns([
+                    ban_group_member_entity::Column::GroupId,
+                    ban_group_member_entity::Column::Mode,
+                    ban_group_member_entity::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Remove a channel from its ban group, returns false if it was in none
+ */
+pub async fn delete_ban_group_member<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<bool, Error> {
+    let result = ban_group_member_entity::Entity::delete_many()
+        .filter(ban_group_member_entity::Column::ChannelId.eq(channel_id))
+        .exec(db)
+        .await?;
+
+    return Ok(result.rows_affected > 0);
+}
+
+/**
+ * Queue the ban of a user in one channel of a group for another channel
+ */
+pub async fn create_shared_ban<T: ConnectionTrait>(
+    group_id: &str,
+    channel_id: i32,
+    source_channel_id: i32,
+    user_id: i32,
+    reason: Option<String>,
+    db: &T,
+) -> Result<shared_ban_entity::Model, Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let shared_ban = shared_ban_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        group_id: Set(String::from(group_id)),
+        channel_id: Set(channel_id),
+        source_channel_id: Set(source_channel_id),
+        user_id: Set(user_id),
+        status: Set(SharedBanStatus::Pending),
+        reason: Set(reason),
+        decided_by: Set(None),
+        decided_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+    let shared_ban = shared_ban.insert(db).await?;
+
+    return Ok(shared_ban);
+}
+
+/**
+ * Get the shared bans of a user in a channel with one of the given statuses,
+ * newest first
+ */
+pub async fn get_shared_bans<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    statuses: &[SharedBanStatus],
+    db: &T,
+) -> Result<Vec<shared_ban_entity::Model>, Error> {
+    let shared_bans = shared_ban_entity::Entity::find()
+        .filter(shared_ban_entity::Column::ChannelId.eq(channel_id))
+        .filter(shared_ban_entity::Column::UserId.eq(user_id))
+        .filter(shared_ban_entity::Column::Status.is_in(statuses.iter().copied()))
+        .order_by_desc(shared_ban_entity::Column::CreatedAt)
+        .all(db)
+        .await?;
+    return Ok(shared_bans);
+}
+
+/**
+ * Get the shared bans that wait for the approval of a channel, oldest first
+ */
+pub async fn get_pending_shared_bans<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<shared_ban_entity::Model>, Error> {
+    let shared_bans = shared_ban_entity::Entity::find()
+        .filter(shared_ban_entity::Column::ChannelId.eq(channel_id))
+        .filter(shared_ban_entity::Column::Status.eq(SharedBanStatus::Pending))
+        .order_by_asc(shared_ban_entity::Column::CreatedAt)
+        .all(db)
+        .await?;
+    return Ok(shared_bans);
+}
+
+/**
+ * Get the pending and applied shared bans of a user that a channel started
+ * or received, these are the ones an undo in that channel reverts
+ */
+pub async fn get_open_shared_bans<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<Vec<shared_ban_entity::Model>, Error> {
+    let shared_bans = shared_ban_entity::Entity::find()
+        .filter(
+            Condition::any()
+                .add(shared_ban_entity::Column::ChannelId.eq(channel_id))
+                .add(shared_ban_entity::Column::SourceChannelId.eq(channel_id)),
+        )
+        .filter(shared_ban_entity::Column::UserId.eq(user_id))
+        .filter(
+            shared_ban_entity::Column::Status
+                .is_in([SharedBanStatus::Pending, SharedBanStatus::Applied]),
+        )
+        .all(db)
+        .await?;
+    return Ok(shared_bans);
+}
+
+/**
+ * Get the latest shared bans of a group, newest first
+ */
+pub async fn get_group_shared_bans<T: ConnectionTrait>(
+    group_id: &str,
+    limit: u64,
+    db: &T,
+) -> Result<Vec<shared_ban_entity::Model>, Error> {
+    let shared_bans = shared_ban_entity::Entity::find()
+        .filter(shared_ban_entity::Column::GroupId.eq(group_id))
+        .order_by_desc(shared_ban_entity::Column::CreatedAt)
+        .limit(limit)
+        .all(db)
+        .await?;
+    return Ok(shared_bans);
+}
+
+/**
+ * Record what happened to a shared ban, without a moderator the bot decided
+ */
+pub async fn set_shared_ban_status<T: ConnectionTrait>(
+    id: &str,
+    status: SharedBanStatus,
+    decided_by: Option<i32>,
+    db: &T,
+) -> Result<(), Error> {
+    shared_ban_entity::Entity::update_many()
+        .col_expr(shared_ban_entity::Column::Status, Expr::value(status))
+        .col_expr(shared_ban_entity::Column::DecidedBy, Expr::value(decided_by))
+        .col_expr(
+            shared_ban_entity::Column::DecidedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .col_expr(
+            shared_ban_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(shared_ban_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
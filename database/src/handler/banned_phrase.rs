@@ -0,0 +1,101 @@
+use crate::entity::banned_phrase as banned_phrase_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, QueryOrder, Set};
+
+/**
+ * Get the banned phrases of a channel in the order they were added
+ */
+pub async fn get_banned_phrases<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<banned_phrase_entity::Model>, Error> {
+    let banned_phrases = banned_phrase_entity::Entity::find()
+        .filter(banned_phrase_entity::Column::ChannelId.eq(channel_id))
+        .order_by_asc(banned_phrase_entity::Column::CreatedAt)
+        .all(db)
+        .await?;
+    return Ok(banned_phrases);
+}
+
+/**
+ * Get a banned phrase of a channel by its text
+ */
+pub async fn get_banned_phrase<T: ConnectionTrait>(
+    channel_id: i32,
+    phrase: &str,
+    db: &T,
+) -> Result<Option<banned_phrase_entity::Model>, Error> {
+    let banned_phrase = banned_phrase_entity::Entity::find()
+        .filter(banned_phrase_entity::Column::ChannelId.eq(channel_id))
+        .filter(banned_phrase_entity::Column::Phrase.eq(phrase))
+        .one(db)
+        .await?;
+    return Ok(banned_phrase);
+}
+
+/**
+ * Create a banned phrase with a new id
+ */
+pub async fn create_banned_phrase<T: ConnectionTrait>(
+    mut banned_phrase: banned_phrase_entity::ActiveModel,
+    db: &T,
+) -> Result<banned_phrase_entity::Model, Error> {
+    banned_phrase.id = Set(Uuid::new_v4().to_string());
+    let banned_phrase = banned_phrase.insert(db).await?;
+
+    return Ok(banned_phrase);
+}
+
+/**
+ * Turn the dry run of a banned phrase on or off, in a dry run matches are
+ * only logged
+ */
+pub async fn set_dry_run<T: ConnectionTrait>(id: &str, dry_run: bool, db: &T) -> Result<(), Error> {
+    banned_phrase_entity::Entity::update_many()
+        .col_expr(banned_phrase_entity::Column::DryRun, Expr::value(dry_run as i8))
+        .col_expr(
+            banned_phrase_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(banned_phrase_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Delete a banned phrase
+ */
+pub async fn delete_banned_phrase<T: ConnectionTrait>(id: &str, db: &T) -> Result<(), Error> {
+    banned_phrase_entity::Entity::delete_by_id(id.to_string())
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Count a message that matched a banned phrase
+ */
+pub async fn record_match<T: ConnectionTrait>(
+    id: &str,
+    timestamp: DateTimeUtc,
+    db: &T,
+) -> Result<(), Error> {
+    banned_phrase_entity::Entity::update_many()
+        .col_expr(
+            banned_phrase_entity::Column::MatchCount,
+            Expr::col(banned_phrase_entity::Column::MatchCount).add(1),
+        )
+        .col_expr(
+            banned_phrase_entity::Column::LastMatched,
+            Expr::value(timestamp),
+        )
+        .filter(banned_phrase_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
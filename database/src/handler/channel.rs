@@ -1,6 +1,32 @@
 use crate::entity::channel as channel_entity;
+use crate::entity::user as user_entity;
 use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
 use sea_orm::{prelude::*, ActiveValue};
+use serde::{Deserialize, Serialize};
+
+/**
+ * Redis pub/sub channel the running bots listen on to join or leave channels
+ */
+pub const CHANNEL_EVENTS: &str = "channel:events";
+
+/**
+ * A change of the channels the bots should be in
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelEvent {
+    Join { channel_id: i32, name: String },
+    Part { channel_id: i32, name: String },
+}
+
+/**
+ * Publish a channel event to the running bots
+ */
+pub async fn publish_channel_event(event: &ChannelEvent, redis: &mut Connection) -> Result<(), Error> {
+    cache::publish(CHANNEL_EVENTS, &serde_json::to_string(event)?, redis).await?;
+    return Ok(());
+}
 
 /**
  * Get a channel by id
@@ -13,6 +39,21 @@ pub async fn get_channel<T: ConnectionTrait>(
     return Ok(channel);
 }
 
+/**
+ * Get a channel by the nick of its broadcaster
+ */
+pub async fn get_channel_by_name<T: ConnectionTrait>(
+    name: &str,
+    db: &T,
+) -> Result<Option<channel_entity::Model>, Error> {
+    let channel = channel_entity::Entity::find()
+        .inner_join(user_entity::Entity)
+        .filter(user_entity::Column::Nick.eq(name))
+        .one(db)
+        .await?;
+    return Ok(channel);
+}
+
 /**
  * Get live channels
  */
@@ -28,19 +69,62 @@ pub async fn get_live_channels<T: ConnectionTrait>(
 }
 
 /**
- * Deactive a channel
+ * Activate a channel and tell the running bots to join it
  */
-pub async fn deactivate_channel<T: ConnectionTrait>(channel: i32, db: &T) -> Result<(), Error> {
+pub async fn activate_channel<T: ConnectionTrait>(
+    channel: i32,
+    db: &T,
+    redis: &mut Connection,
+) -> Result<(), Error> {
     let channel = channel_entity::Entity::find_by_id(channel).one(db).await?;
     let channel = match channel {
         Some(channel) => channel,
         None => return Ok(()),
     };
+    let user = channel.find_related(user_entity::Entity).one(db).await?;
+
+    let mut channel: channel_entity::ActiveModel = channel.into();
+    channel.active = ActiveValue::Set(true as i8);
+    let channel = channel.update(db).await?;
+
+    if let Some(user) = user {
+        let event = ChannelEvent::Join {
+            channel_id: channel.id,
+            name: user.nick,
+        };
+        publish_channel_event(&event, redis).await?;
+    }
+
+    return Ok(());
+}
+
+/**
+ * Deactive a channel and tell the running bots to leave it
+ */
+pub async fn deactivate_channel<T: ConnectionTrait>(
+    channel: i32,
+    db: &T,
+    redis: &mut Connection,
+) -> Result<(), Error> {
+    let channel = channel_entity::Entity::find_by_id(channel).one(db).await?;
+    let channel = match channel {
+        Some(channel) => channel,
+        None => return Ok(()),
+    };
+    let user = channel.find_related(user_entity::Entity).one(db).await?;
 
     let mut channel: channel_entity::ActiveModel = channel.into();
     channel.active = ActiveValue::Set(false as i8);
     channel.live = ActiveValue::Set(false as i8);
-    channel.update(db).await?;
+    let channel = channel.update(db).await?;
+
+    if let Some(user) = user {
+        let event = ChannelEvent::Part {
+            channel_id: channel.id,
+            name: user.nick,
+        };
+        publish_channel_event(&event, redis).await?;
+    }
 
     return Ok(());
 }
@@ -1,27 +1,138 @@
 use sea_orm::prelude::*;
-use sea_orm::TransactionTrait;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{FromQueryResult, QueryOrder, QuerySelect, TransactionTrait};
 use crate::entity::chat_message as chat_message_entity;
 use crate::entity::user as user_entity;
+use crate::entity::user_notice as user_notice_entity;
 use anyhow::{Result, Error};
 
 /**
- * Save chat messages to database
+ * Save chat messages and user notices to database
  */
 pub async fn save_chat_messages(
     db: &DatabaseConnection,
     chat_messages: Vec<chat_message_entity::ActiveModel>,
+    user_notices: Vec<user_notice_entity::ActiveModel>,
     users: Vec<user_entity::ActiveModel>,
 ) -> Result<(), Error> {
     let txn = db.begin().await?;
 
-    if users.len() > 0 {
+    if !users.is_empty() {
         crate::handler::user::create_many(users, &txn).await?;
     }
-    chat_message_entity::Entity::insert_many(chat_messages)
-        .exec(&txn)
-        .await?;
+    // a message delivered twice while a channel moves between connections
+    // must not fail the whole batch
+    if !chat_messages.is_empty() {
+        let insert = chat_message_entity::Entity::insert_many(chat_messages)
+            .on_conflict(
+                OnConflict::column(chat_message_entity::Column::MsgId)
+                    .update_column(chat_message_entity::Column::MsgId)
+                    .to_owned(),
+            )
+            .exec(&txn)
+            .await;
+        ignore_duplicates(insert)?;
+    }
+    if !user_notices.is_empty() {
+        let insert = user_notice_entity::Entity::insert_many(user_notices)
+            .on_conflict(
+                OnConflict::column(user_notice_entity::Column::MsgId)
+                    .update_column(user_notice_entity::Column::MsgId)
+                    .to_owned(),
+            )
+            .exec(&txn)
+            .await;
+        ignore_duplicates(insert)?;
+    }
 
     txn.commit().await?;
     
     return Ok(());
 }
+
+fn ignore_duplicates<T>(insert: Result<T, DbErr>) -> Result<(), Error> {
+    return match insert {
+        Ok(_) | Err(DbErr::RecordNotInserted) => Ok(()),
+        Err(e) => Err(Error::new(e)),
+    };
+}
+
+/**
+ * Mark all messages of a user in a channel as deleted
+ */
+pub async fn delete_user_messages<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<(), Error> {
+    chat_message_entity::Entity::update_many()
+        .col_expr(chat_message_entity::Column::Deleted, Expr::value(true as i8))
+        .col_expr(
+            chat_message_entity::Column::DeletedTimestamp,
+            Expr::value(chrono::Utc::now()),
+        )
+        .col_expr(
+            chat_message_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(chat_message_entity::Column::ChannelId.eq(channel_id))
+        .filter(chat_message_entity::Column::UserId.eq(user_id))
+        .filter(chat_message_entity::Column::Deleted.eq(false as i8))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Get the last message of a user in a channel
+ */
+pub async fn get_last_message<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<Option<chat_message_entity::Model>, Error> {
+    let message = chat_message_entity::Entity::find()
+        .filter(chat_message_entity::Column::ChannelId.eq(channel_id))
+        .filter(chat_message_entity::Column::UserId.eq(user_id))
+        .order_by_desc(chat_message_entity::Column::Timestamp)
+        .one(db)
+        .await?;
+    return Ok(message);
+}
+
+/**
+ * What the messages of a viewer tell about them
+ */
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ChatterActivity {
+    pub user_id: i32,
+    pub subscriber: i8,
+    pub vip: i8,
+    pub last_message: DateTimeUtc,
+}
+
+/**
+ * Get the viewers who chatted in a channel since a time, a viewer counts as
+ * subscriber or vip if any of those messages said so
+ */
+pub async fn get_chatter_activity<T: ConnectionTrait>(
+    channel_id: i32,
+    since: DateTimeUtc,
+    db: &T,
+) -> Result<Vec<ChatterActivity>, Error> {
+    let activity = chat_message_entity::Entity::find()
+        .select_only()
+        .column(chat_message_entity::Column::UserId)
+        .column_as(chat_message_entity::Column::Subscriber.max(), "subscriber")
+        .column_as(chat_message_entity::Column::Vip.max(), "vip")
+        .column_as(chat_message_entity::Column::Timestamp.max(), "last_message")
+        .filter(chat_message_entity::Column::ChannelId.eq(channel_id))
+        .filter(chat_message_entity::Column::Timestamp.gte(since))
+        .group_by(chat_message_entity::Column::UserId)
+        .into_model::<ChatterActivity>()
+        .all(db)
+        .await?;
+
+    return Ok(activity);
+}
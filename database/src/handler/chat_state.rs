@@ -0,0 +1,44 @@
+use crate::entity::channel_chat_state as channel_chat_state_entity;
+use anyhow::{Error, Result};
+use sea_orm::prelude::*;
+
+/**
+ * Get the chat state of a channel
+ */
+pub async fn get_chat_state<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Option<channel_chat_state_entity::Model>, Error> {
+    let chat_state = channel_chat_state_entity::Entity::find_by_id(channel_id)
+        .one(db)
+        .await?;
+    return Ok(chat_state);
+}
+
+/**
+ * Create or update the chat state of a channel
+ */
+pub async fn save_chat_state<T: ConnectionTrait>(
+    chat_state: channel_chat_state_entity::ActiveModel,
+    db: &T,
+) -> Result<(), Error> {
+    channel_chat_state_entity::Entity::insert(chat_state)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(channel_chat_state_entity::Column::ChannelId)
+                .update_columns([
+                    channel_chat_state_entity::Column::EmoteOnly,
+                    channel_chat_state_entity::Column::FollowersOnly,
+                    channel_chat_state_entity::Column::R9k,
+                    channel_chat_state_entity::Column::Slow,
+                    channel_chat_state_entity::Column::SubsOnly,
+                    channel_chat_state_entity::Column::BotModerator,
+                    channel_chat_state_entity::Column::BotVip,
+                    channel_chat_state_entity::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
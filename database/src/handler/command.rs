@@ -0,0 +1,94 @@
+use crate::entity::command as command_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, QueryOrder, Set};
+
+/**
+ * Get a command of a channel by its name
+ */
+pub async fn get_command<T: ConnectionTrait>(
+    channel_id: i32,
+    name: &str,
+    db: &T,
+) -> Result<Option<command_entity::Model>, Error> {
+    let command = command_entity::Entity::find()
+        .filter(command_entity::Column::ChannelId.eq(channel_id))
+        .filter(command_entity::Column::Name.eq(name.to_lowercase()))
+        .one(db)
+        .await?;
+    return Ok(command);
+}
+
+/**
+ * Count an invocation of a command, the count is incremented in the db so
+ * concurrent invocations are not lost
+ */
+pub async fn record_invocation<T: ConnectionTrait>(
+    id: &str,
+    timestamp: DateTimeUtc,
+    db: &T,
+) -> Result<(), Error> {
+    command_entity::Entity::update_many()
+        .col_expr(
+            command_entity::Column::InvokeCount,
+            Expr::col(command_entity::Column::InvokeCount).add(1),
+        )
+        .col_expr(command_entity::Column::LastInvoked, Expr::value(timestamp))
+        .filter(command_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Get all commands of a channel ordered by name
+ */
+pub async fn get_commands<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<command_entity::Model>, Error> {
+    let commands = command_entity::Entity::find()
+        .filter(command_entity::Column::ChannelId.eq(channel_id))
+        .order_by_asc(command_entity::Column::Name)
+        .all(db)
+        .await?;
+    return Ok(commands);
+}
+
+/**
+ * Create a command with a new id
+ */
+pub async fn create_command<T: ConnectionTrait>(
+    mut command: command_entity::ActiveModel,
+    db: &T,
+) -> Result<command_entity::Model, Error> {
+    command.id = Set(Uuid::new_v4().to_string());
+    let command = command.insert(db).await?;
+
+    return Ok(command);
+}
+
+/**
+ * Update the changed columns of a command
+ */
+pub async fn update_command<T: ConnectionTrait>(
+    mut command: command_entity::ActiveModel,
+    db: &T,
+) -> Result<command_entity::Model, Error> {
+    command.updated_at = Set(chrono::Utc::now().naive_utc());
+    let command = command.update(db).await?;
+
+    return Ok(command);
+}
+
+/**
+ * Delete a command
+ */
+pub async fn delete_command<T: ConnectionTrait>(id: &str, db: &T) -> Result<(), Error> {
+    command_entity::Entity::delete_by_id(id.to_string())
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
@@ -0,0 +1,150 @@
+use crate::entity::counter as counter_entity;
+use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+/**
+ * Redis pub/sub channel overlays listen on for counter changes
+ */
+pub const COUNTER_EVENTS: &str = "counter:events";
+
+/**
+ * A change of a counter
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CounterEvent {
+    Update {
+        channel_id: i32,
+        name: String,
+        value: i32,
+    },
+    Delete {
+        channel_id: i32,
+        name: String,
+    },
+}
+
+/**
+ * How to change the value of a counter
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterChange {
+    Add(i32),
+    Set(i32),
+}
+
+/**
+ * Publish a counter event to the overlays
+ */
+pub async fn publish_counter_event(event: &CounterEvent, redis: &mut Connection) -> Result<(), Error> {
+    cache::publish(COUNTER_EVENTS, &serde_json::to_string(event)?, redis).await?;
+    return Ok(());
+}
+
+/**
+ * Get a counter of a channel by its name
+ */
+pub async fn get_counter<T: ConnectionTrait>(
+    channel_id: i32,
+    name: &str,
+    db: &T,
+) -> Result<Option<counter_entity::Model>, Error> {
+    let counter = counter_entity::Entity::find()
+        .filter(counter_entity::Column::ChannelId.eq(channel_id))
+        .filter(counter_entity::Column::Name.eq(name.to_lowercase()))
+        .one(db)
+        .await?;
+    return Ok(counter);
+}
+
+/**
+ * Get the counters of a channel by name
+ */
+pub async fn get_counters<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<counter_entity::Model>, Error> {
+    let counters = counter_entity::Entity::find()
+        .filter(counter_entity::Column::ChannelId.eq(channel_id))
+        .order_by_asc(counter_entity::Column::Name)
+        .all(db)
+        .await?;
+    return Ok(counters);
+}
+
+/**
+ * Create a counter with a new id
+ */
+pub async fn create_counter<T: ConnectionTrait>(
+    mut counter: counter_entity::ActiveModel,
+    db: &T,
+) -> Result<counter_entity::Model, Error> {
+    counter.id = Set(Uuid::new_v4().to_string());
+    let counter = counter.insert(db).await?;
+
+    return Ok(counter);
+}
+
+/**
+ * Delete a counter and tell the overlays
+ */
+pub async fn delete_counter<T: ConnectionTrait>(
+    counter: &counter_entity::Model,
+    db: &T,
+    redis: &mut Connection,
+) -> Result<(), Error> {
+    counter_entity::Entity::delete_by_id(counter.id.to_string())
+        .exec(db)
+        .await?;
+
+    let event = CounterEvent::Delete {
+        channel_id: counter.channel_id,
+        name: String::from(&counter.name),
+    };
+    publish_counter_event(&event, redis).await?;
+
+    return Ok(());
+}
+
+/**
+ * Change the value of a counter in one statement so concurrent changes are
+ * not lost, tells the overlays and returns the counter with its new value
+ */
+pub async fn change_counter<T: ConnectionTrait>(
+    id: &str,
+    change: CounterChange,
+    db: &T,
+    redis: &mut Connection,
+) -> Result<counter_entity::Model, Error> {
+    let value = match change {
+        CounterChange::Add(x) => Expr::col(counter_entity::Column::Value).add(x),
+        CounterChange::Set(x) => Expr::value(x),
+    };
+
+    counter_entity::Entity::update_many()
+        .col_expr(counter_entity::Column::Value, value)
+        .col_expr(
+            counter_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(counter_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    let counter = match counter_entity::Entity::find_by_id(id.to_string()).one(db).await? {
+        Some(x) => x,
+        None => return Err(Error::msg("Counter not found")),
+    };
+
+    let event = CounterEvent::Update {
+        channel_id: counter.channel_id,
+        name: String::from(&counter.name),
+        value: counter.value,
+    };
+    publish_counter_event(&event, redis).await?;
+
+    return Ok(counter);
+}
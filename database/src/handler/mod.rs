@@ -3,4 +3,19 @@ pub mod token;
 pub mod bot;
 pub mod user;
 pub mod channel;
+pub mod chat_state;
 pub mod watchtime;
+pub mod moderation;
+pub mod command;
+pub mod stream_history;
+pub mod audit;
+pub mod auto_responder;
+pub mod timer;
+pub mod quote;
+pub mod counter;
+pub mod points;
+pub mod raffle;
+pub mod spam_filter;
+pub mod banned_phrase;
+pub mod strike;
+pub mod ban_group;
@@ -0,0 +1,189 @@
+use crate::entity::moderation_action as moderation_action_entity;
+use crate::entity::sea_orm_active_enums::ModerationActionType;
+use anyhow::{Error, Result};
+use chrono::Duration;
+use sea_orm::{prelude::*, FromQueryResult, QueryOrder, QuerySelect, Set};
+
+/**
+ * Seconds within which a CLEARCHAT is taken as the echo of an action the bot
+ * recorded itself
+ */
+const MERGE_WINDOW: i64 = 30;
+
+/**
+ * A moderation action to record, the acting moderator is only known for
+ * actions of the bot
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewModerationAction {
+    pub channel_id: i32,
+    pub target_user_id: Option<i32>,
+    pub action: ModerationActionType,
+    pub duration: Option<i32>,
+    pub reason: Option<String>,
+    pub msg_id: Option<String>,
+    pub moderator_id: Option<i32>,
+    pub automated: bool,
+    pub timestamp: DateTimeUtc,
+}
+
+/**
+ * How often an action was taken against a user
+ */
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ActionCount {
+    pub action: ModerationActionType,
+    pub count: i64,
+}
+
+/**
+ * Check if an action was seen in chat as CLEARMSG or CLEARCHAT, those do not
+ * tell who took the action
+ */
+fn is_echo(action: &NewModerationAction) -> bool {
+    return action.moderator_id.is_none() && !action.automated;
+}
+
+/**
+ * Find the other half of an action of the bot and its echo in chat, the bot
+ * records its own actions with a moderator and twitch echoes them with the
+ * same duration. Deletes are matched by message and the others by target and
+ * time, an action is only paired once so a moderator repeating the action
+ * right after the bot still gets their own row
+ */
+async fn find_same_action<T: ConnectionTrait>(
+    action: &NewModerationAction,
+    db: &T,
+) -> Result<Option<moderation_action_entity::Model>, Error> {
+    let query = moderation_action_entity::Entity::find()
+        .filter(moderation_action_entity::Column::ChannelId.eq(action.channel_id))
+        .filter(moderation_action_entity::Column::Action.eq(action.action))
+        .filter(moderation_action_entity::Column::Echoed.eq(false));
+
+    let query = match is_echo(action) {
+        true => query.filter(moderation_action_entity::Column::ModeratorId.is_not_null()),
+        false => query
+            .filter(moderation_action_entity::Column::ModeratorId.is_null())
+            .filter(moderation_action_entity::Column::Automated.eq(false)),
+    };
+    let query = match action.duration {
+        Some(x) => query.filter(moderation_action_entity::Column::Duration.eq(x)),
+        None => query.filter(moderation_action_entity::Column::Duration.is_null()),
+    };
+
+    let query = match (&action.action, &action.msg_id) {
+        (ModerationActionType::Delete, Some(msg_id)) => {
+            query.filter(moderation_action_entity::Column::MsgId.eq(String::from(msg_id)))
+        }
+        (ModerationActionType::Delete, None) => return Ok(None),
+        _ => {
+            let window = Duration::seconds(MERGE_WINDOW);
+            let query = query.filter(
+                moderation_action_entity::Column::Timestamp
+                    .between(action.timestamp - window, action.timestamp + window),
+            );
+            match action.target_user_id {
+                Some(x) => query.filter(moderation_action_entity::Column::TargetUserId.eq(x)),
+                None => query.filter(moderation_action_entity::Column::TargetUserId.is_null()),
+            }
+        }
+    };
+
+    let existing = query
+        .order_by_desc(moderation_action_entity::Column::Timestamp)
+        .one(db)
+        .await?;
+    return Ok(existing);
+}
+
+/**
+ * Record a moderation action taken in a channel, twitch echoes the actions of
+ * the bot as CLEARMSG and CLEARCHAT so the echo and the record of the bot
+ * become one row with what either of them knows
+ */
+pub async fn record_moderation_action<T: ConnectionTrait>(
+    action: NewModerationAction,
+    db: &T,
+) -> Result<(), Error> {
+    if let Some(existing) = find_same_action(&action, db).await? {
+        let mut update: moderation_action_entity::ActiveModel = existing.clone().into();
+        if existing.reason.is_none() && action.reason.is_some() {
+            update.reason = Set(action.reason);
+        }
+        if existing.msg_id.is_none() && action.msg_id.is_some() {
+            update.msg_id = Set(action.msg_id);
+        }
+        if existing.moderator_id.is_none() && action.moderator_id.is_some() {
+            update.moderator_id = Set(action.moderator_id);
+        }
+        if existing.automated == 0 && action.automated {
+            update.automated = Set(1);
+        }
+        update.echoed = Set(1);
+        update.updated_at = Set(chrono::Utc::now().naive_utc());
+        update.update(db).await?;
+
+        return Ok(());
+    }
+
+    let moderation_action = moderation_action_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        channel_id: Set(action.channel_id),
+        target_user_id: Set(action.target_user_id),
+        action: Set(action.action),
+        duration: Set(action.duration),
+        reason: Set(action.reason),
+        msg_id: Set(action.msg_id),
+        moderator_id: Set(action.moderator_id),
+        automated: Set(action.automated as i8),
+        echoed: Set(0),
+        timestamp: Set(action.timestamp),
+        ..Default::default()
+    };
+
+    moderation_action_entity::Entity::insert(moderation_action)
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Get the latest moderation actions against a user in a channel, newest first
+ */
+pub async fn get_user_history<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    limit: u64,
+    db: &T,
+) -> Result<Vec<moderation_action_entity::Model>, Error> {
+    let history = moderation_action_entity::Entity::find()
+        .filter(moderation_action_entity::Column::ChannelId.eq(channel_id))
+        .filter(moderation_action_entity::Column::TargetUserId.eq(user_id))
+        .order_by_desc(moderation_action_entity::Column::Timestamp)
+        .limit(limit)
+        .all(db)
+        .await?;
+    return Ok(history);
+}
+
+/**
+ * Count the moderation actions against a user in a channel by type
+ */
+pub async fn get_user_action_counts<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<Vec<ActionCount>, Error> {
+    let counts = moderation_action_entity::Entity::find()
+        .select_only()
+        .column(moderation_action_entity::Column::Action)
+        .column_as(moderation_action_entity::Column::Id.count(), "count")
+        .filter(moderation_action_entity::Column::ChannelId.eq(channel_id))
+        .filter(moderation_action_entity::Column::TargetUserId.eq(user_id))
+        .group_by(moderation_action_entity::Column::Action)
+        .into_model::<ActionCount>()
+        .all(db)
+        .await?;
+    return Ok(counts);
+}
@@ -0,0 +1,461 @@
+use crate::entity::points_balance as points_balance_entity;
+use crate::entity::points_ledger as points_ledger_entity;
+use crate::entity::points_settings as points_settings_entity;
+use crate::entity::user as user_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set, TransactionTrait};
+use std::collections::HashMap;
+
+/**
+ * Reasons of balance changes in the ledger
+ */
+pub const REASON_WATCH: &str = "watch";
+pub const REASON_GIVE: &str = "give";
+pub const REASON_RECEIVE: &str = "receive";
+pub const REASON_ADD: &str = "add";
+
+/**
+ * The outcome of a transfer between two viewers
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Done { from_balance: i64, to_balance: i64 },
+    Insufficient { balance: i64 },
+    TooLarge,
+}
+
+/**
+ * The outcome of adding or removing points of a viewer
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointsChange {
+    Done { balance: i64 },
+    Insufficient { balance: i64 },
+    TooLarge,
+}
+
+/**
+ * The settings of channels that never changed them
+ */
+pub fn default_points_settings(channel_id: i32) -> points_settings_entity::Model {
+    return points_settings_entity::Model {
+        channel_id,
+        enabled: true as i8,
+        points_per_interval: 10,
+        subscriber_multiplier: 200,
+        vip_multiplier: 150,
+        chat_bonus: 5,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+}
+
+/**
+ * Get the points settings of a channel
+ */
+pub async fn get_points_settings<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<points_settings_entity::Model, Error> {
+    let settings = points_settings_entity::Entity::find_by_id(channel_id)
+        .one(db)
+        .await?;
+    return Ok(settings.unwrap_or_else(|| default_points_settings(channel_id)));
+}
+
+/**
+ * Create or update the points settings of a channel
+ */
+pub async fn save_points_settings<T: ConnectionTrait>(
+    settings: points_settings_entity::Model,
+    db: &T,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let created_at = match settings.created_at == Default::default() {
+        true => now,
+        false => settings.created_at,
+    };
+    let mut settings: points_settings_entity::ActiveModel = settings.into();
+    settings = settings.reset_all();
+    settings.created_at = Set(created_at);
+    settings.updated_at = Set(now);
+
+    points_settings_entity::Entity::insert(settings)
+        .on_conflict(
+            OnConflict::column(points_settings_entity::Column::ChannelId)
+                .update_columns([
+                    points_settings_entity::Column::Enabled,
+                    points_settings_entity::Column::PointsPerInterval,
+                    points_settings_entity::Column::SubscriberMultiplier,
+                    points_settings_entity::Column::VipMultiplier,
+                    points_settings_entity::Column::ChatBonus,
+                    points_settings_entity::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * The points a viewer earns for one interval, the multipliers are percent
+ * and only the highest one applies, chatting adds a flat bonus
+ */
+pub fn accrual(
+    settings: &points_settings_entity::Model,
+    subscriber: bool,
+    vip: bool,
+    chatted: bool,
+) -> i64 {
+    if settings.enabled == 0 {
+        return 0;
+    }
+
+    let mut multiplier = 100;
+    if subscriber {
+        multiplier = multiplier.max(settings.subscriber_multiplier);
+    }
+    if vip {
+        multiplier = multiplier.max(settings.vip_multiplier);
+    }
+
+    let mut points = settings.points_per_interval as i64 * multiplier as i64 / 100;
+    if chatted {
+        points += settings.chat_bonus as i64;
+    }
+
+    return points.max(0);
+}
+
+/**
+ * Get the balance of a viewer in a channel
+ */
+pub async fn get_balance<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<i64, Error> {
+    let balance = points_balance_entity::Entity::find()
+        .filter(points_balance_entity::Column::ChannelId.eq(channel_id))
+        .filter(points_balance_entity::Column::UserId.eq(user_id))
+        .one(db)
+        .await?;
+    return Ok(balance.map(|x| x.balance).unwrap_or(0));
+}
+
+/**
+ * Get the rank of a balance in a channel, starting at 1
+ */
+pub async fn get_rank<T: ConnectionTrait>(
+    channel_id: i32,
+    balance: i64,
+    db: &T,
+) -> Result<u64, Error> {
+    let higher = points_balance_entity::Entity::find()
+        .filter(points_balance_entity::Column::ChannelId.eq(channel_id))
+        .filter(points_balance_entity::Column::Balance.gt(balance))
+        .count(db)
+        .await?;
+    return Ok(higher + 1);
+}
+
+/**
+ * Get the highest balances of a channel with their users
+ */
+pub async fn get_top<T: ConnectionTrait>(
+    channel_id: i32,
+    limit: u64,
+    db: &T,
+) -> Result<Vec<(points_balance_entity::Model, Option<user_entity::Model>)>, Error> {
+    let top = points_balance_entity::Entity::find()
+        .find_also_related(user_entity::Entity)
+        .filter(points_balance_entity::Column::ChannelId.eq(channel_id))
+        .filter(points_balance_entity::Column::Balance.gt(0))
+        .order_by_desc(points_balance_entity::Column::Balance)
+        .limit(limit)
+        .all(db)
+        .await?;
+    return Ok(top);
+}
+
+/**
+ * Create the missing balances of viewers, existing ones are kept, the insert
+ * locks the rows in the order of the user ids like `lock_balance` does
+ */
+async fn ensure_balances<T: ConnectionTrait>(
+    channel_id: i32,
+    user_ids: &[i32],
+    db: &T,
+) -> Result<(), Error> {
+    let mut user_ids = user_ids.to_vec();
+    user_ids.sort();
+    user_ids.dedup();
+
+    let balances = user_ids
+        .iter()
+        .map(|user_id| points_balance_entity::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            channel_id: Set(channel_id),
+            user_id: Set(*user_id),
+            balance: Set(0),
+            ..Default::default()
+        })
+        .collect::<Vec<points_balance_entity::ActiveModel>>();
+    if balances.is_empty() {
+        return Ok(());
+    }
+
+    let insert = points_balance_entity::Entity::insert_many(balances)
+        .on_conflict(
+            OnConflict::columns([
+                points_balance_entity::Column::ChannelId,
+                points_balance_entity::Column::UserId,
+            ])
+            .update_column(points_balance_entity::Column::ChannelId)
+            .to_owned(),
+        )
+        .exec(db)
+        .await;
+
+    return match insert {
+        Ok(_) | Err(DbErr::RecordNotInserted) => Ok(()),
+        Err(e) => Err(Error::new(e)),
+    };
+}
+
+/**
+ * Lock the balance of a viewer until the transaction ends
+ */
+async fn lock_balance<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    txn: &T,
+) -> Result<points_balance_entity::Model, Error> {
+    let balance = points_balance_entity::Entity::find()
+        .filter(points_balance_entity::Column::ChannelId.eq(channel_id))
+        .filter(points_balance_entity::Column::UserId.eq(user_id))
+        .lock_exclusive()
+        .one(txn)
+        .await?;
+
+    return match balance {
+        Some(x) => Ok(x),
+        None => Err(Error::msg("Balance not found")),
+    };
+}
+
+async fn set_balance<T: ConnectionTrait>(id: &str, balance: i64, txn: &T) -> Result<(), Error> {
+    points_balance_entity::Entity::update_many()
+        .col_expr(points_balance_entity::Column::Balance, Expr::value(balance))
+        .col_expr(
+            points_balance_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(points_balance_entity::Column::Id.eq(id))
+        .exec(txn)
+        .await?;
+
+    return Ok(());
+}
+
+fn ledger_entry(
+    channel_id: i32,
+    user_id: i32,
+    amount: i64,
+    reason: &str,
+    actor_id: Option<i32>,
+    reference: Option<String>,
+) -> points_ledger_entity::ActiveModel {
+    return points_ledger_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        channel_id: Set(channel_id),
+        user_id: Set(user_id),
+        amount: Set(amount),
+        reason: Set(String::from(reason)),
+        actor_id: Set(actor_id),
+        reference: Set(reference),
+        ..Default::default()
+    };
+}
+
+/**
+ * Add the points viewers earned while watching, the users have to exist
+ */
+pub async fn accrue_points<T: ConnectionTrait + TransactionTrait>(
+    channel_id: i32,
+    amounts: &[(i32, i64)],
+    db: &T,
+) -> Result<(), Error> {
+    let amounts = amounts
+        .iter()
+        .filter(|(_, amount)| *amount > 0)
+        .copied()
+        .collect::<Vec<(i32, i64)>>();
+    if amounts.is_empty() {
+        return Ok(());
+    }
+
+    // one update per distinct amount instead of one per viewer
+    let mut by_amount: HashMap<i64, Vec<i32>> = HashMap::new();
+    for (user_id, amount) in amounts.iter() {
+        by_amount.entry(*amount).or_default().push(*user_id);
+    }
+
+    let txn = db.begin().await?;
+
+    let user_ids = amounts.iter().map(|(x, _)| *x).collect::<Vec<i32>>();
+    ensure_balances(channel_id, &user_ids, &txn).await?;
+
+    for (amount, user_ids) in by_amount {
+        points_balance_entity::Entity::update_many()
+            .col_expr(
+                points_balance_entity::Column::Balance,
+                Expr::col(points_balance_entity::Column::Balance).add(amount),
+            )
+            .col_expr(
+                points_balance_entity::Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(points_balance_entity::Column::ChannelId.eq(channel_id))
+            .filter(points_balance_entity::Column::UserId.is_in(user_ids))
+            .exec(&txn)
+            .await?;
+    }
+
+    let entries = amounts
+        .iter()
+        .map(|(user_id, amount)| ledger_entry(channel_id, *user_id, *amount, REASON_WATCH, None, None))
+        .collect::<Vec<points_ledger_entity::ActiveModel>>();
+    points_ledger_entity::Entity::insert_many(entries)
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    return Ok(());
+}
+
+/**
+ * Add or remove points of a viewer, the balance can not become negative or
+ * leave the range of the column
+ */
+pub async fn change_points<T: ConnectionTrait + TransactionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    amount: i64,
+    reason: &str,
+    actor_id: Option<i32>,
+    db: &T,
+) -> Result<PointsChange, Error> {
+    let txn = db.begin().await?;
+
+    ensure_balances(channel_id, &[user_id], &txn).await?;
+    let balance = lock_balance(channel_id, user_id, &txn).await?;
+
+    let new_balance = match balance.balance.checked_add(amount) {
+        Some(x) if x < 0 => {
+            txn.rollback().await?;
+            return Ok(PointsChange::Insufficient {
+                balance: balance.balance,
+            });
+        }
+        Some(x) => x,
+        None => {
+            txn.rollback().await?;
+            return Ok(PointsChange::TooLarge);
+        }
+    };
+
+    set_balance(&balance.id, new_balance, &txn).await?;
+    ledger_entry(channel_id, user_id, amount, reason, actor_id, None)
+        .insert(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    return Ok(PointsChange::Done {
+        balance: new_balance,
+    });
+}
+
+/**
+ * Move points between two viewers, both balances are locked in the order of
+ * the user ids so concurrent transfers can not deadlock or overdraw
+ */
+pub async fn transfer_points<T: ConnectionTrait + TransactionTrait>(
+    channel_id: i32,
+    from_user_id: i32,
+    to_user_id: i32,
+    amount: i64,
+    db: &T,
+) -> Result<Transfer, Error> {
+    if amount <= 0 || from_user_id == to_user_id {
+        return Err(Error::msg("Invalid transfer"));
+    }
+
+    let txn = db.begin().await?;
+
+    ensure_balances(channel_id, &[from_user_id, to_user_id], &txn).await?;
+    let (from, to) = match from_user_id < to_user_id {
+        true => {
+            let from = lock_balance(channel_id, from_user_id, &txn).await?;
+            let to = lock_balance(channel_id, to_user_id, &txn).await?;
+            (from, to)
+        }
+        false => {
+            let to = lock_balance(channel_id, to_user_id, &txn).await?;
+            let from = lock_balance(channel_id, from_user_id, &txn).await?;
+            (from, to)
+        }
+    };
+
+    if from.balance < amount {
+        txn.rollback().await?;
+        return Ok(Transfer::Insufficient {
+            balance: from.balance,
+        });
+    }
+
+    let from_balance = from.balance - amount;
+    let to_balance = match to.balance.checked_add(amount) {
+        Some(x) => x,
+        None => {
+            txn.rollback().await?;
+            return Ok(Transfer::TooLarge);
+        }
+    };
+    set_balance(&from.id, from_balance, &txn).await?;
+    set_balance(&to.id, to_balance, &txn).await?;
+
+    let reference = Uuid::new_v4().to_string();
+    points_ledger_entity::Entity::insert_many([
+        ledger_entry(
+            channel_id,
+            from_user_id,
+            -amount,
+            REASON_GIVE,
+            Some(from_user_id),
+            Some(String::from(&reference)),
+        ),
+        ledger_entry(
+            channel_id,
+            to_user_id,
+            amount,
+            REASON_RECEIVE,
+            Some(from_user_id),
+            Some(reference),
+        ),
+    ])
+    .exec(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    return Ok(Transfer::Done {
+        from_balance,
+        to_balance,
+    });
+}
@@ -0,0 +1,131 @@
+use crate::entity::channel as channel_entity;
+use crate::entity::quote as quote_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::{Expr, Order};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set, TransactionTrait};
+
+/**
+ * Get a quote of a channel by its number
+ */
+pub async fn get_quote<T: ConnectionTrait>(
+    channel_id: i32,
+    number: i32,
+    db: &T,
+) -> Result<Option<quote_entity::Model>, Error> {
+    let quote = quote_entity::Entity::find()
+        .filter(quote_entity::Column::ChannelId.eq(channel_id))
+        .filter(quote_entity::Column::Number.eq(number))
+        .one(db)
+        .await?;
+    return Ok(quote);
+}
+
+/**
+ * Get a random quote of a channel
+ */
+pub async fn get_random_quote<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Option<quote_entity::Model>, Error> {
+    let quote = quote_entity::Entity::find()
+        .filter(quote_entity::Column::ChannelId.eq(channel_id))
+        .order_by(Expr::cust("RAND()"), Order::Asc)
+        .one(db)
+        .await?;
+    return Ok(quote);
+}
+
+/**
+ * Get the quotes of a channel containing a text, the oldest first
+ */
+pub async fn search_quotes<T: ConnectionTrait>(
+    channel_id: i32,
+    text: &str,
+    limit: u64,
+    db: &T,
+) -> Result<Vec<quote_entity::Model>, Error> {
+    let quotes = quote_entity::Entity::find()
+        .filter(quote_entity::Column::ChannelId.eq(channel_id))
+        .filter(quote_entity::Column::Text.contains(text))
+        .order_by_asc(quote_entity::Column::Number)
+        .limit(limit)
+        .all(db)
+        .await?;
+    return Ok(quotes);
+}
+
+/**
+ * Get all quotes of a channel by their number
+ */
+pub async fn get_quotes<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<quote_entity::Model>, Error> {
+    let quotes = quote_entity::Entity::find()
+        .filter(quote_entity::Column::ChannelId.eq(channel_id))
+        .order_by_asc(quote_entity::Column::Number)
+        .all(db)
+        .await?;
+    return Ok(quotes);
+}
+
+/**
+ * Create a quote with a new id and the next number of its channel, numbers
+ * come from a counter on the locked channel row so they are never handed out
+ * twice, not even after the last quote was deleted
+ */
+pub async fn create_quote<T: ConnectionTrait + TransactionTrait>(
+    mut quote: quote_entity::ActiveModel,
+    db: &T,
+) -> Result<quote_entity::Model, Error> {
+    let channel_id = match &quote.channel_id {
+        sea_orm::ActiveValue::Set(x) | sea_orm::ActiveValue::Unchanged(x) => *x,
+        sea_orm::ActiveValue::NotSet => return Err(Error::msg("No channel id")),
+    };
+
+    let txn = db.begin().await?;
+
+    let channel = channel_entity::Entity::find_by_id(channel_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+    let channel = match channel {
+        Some(x) => x,
+        None => return Err(Error::msg("Channel not found")),
+    };
+    // quotes added before the counter existed
+    let last = quote_entity::Entity::find()
+        .filter(quote_entity::Column::ChannelId.eq(channel_id))
+        .order_by_desc(quote_entity::Column::Number)
+        .one(&txn)
+        .await?;
+    let number = channel
+        .last_quote_number
+        .max(last.map(|x| x.number).unwrap_or(0))
+        + 1;
+
+    channel_entity::Entity::update_many()
+        .col_expr(channel_entity::Column::LastQuoteNumber, Expr::value(number))
+        .filter(channel_entity::Column::Id.eq(channel_id))
+        .exec(&txn)
+        .await?;
+
+    quote.id = Set(Uuid::new_v4().to_string());
+    quote.number = Set(number);
+    let quote = quote.insert(&txn).await?;
+
+    txn.commit().await?;
+
+    return Ok(quote);
+}
+
+/**
+ * Delete a quote, the numbers of the other quotes stay the same
+ */
+pub async fn delete_quote<T: ConnectionTrait>(id: &str, db: &T) -> Result<(), Error> {
+    quote_entity::Entity::delete_by_id(id.to_string())
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
@@ -0,0 +1,308 @@
+use crate::entity::raffle as raffle_entity;
+use crate::entity::raffle_entry as raffle_entry_entity;
+use crate::entity::sea_orm_active_enums::RaffleStatus;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect, Set, TransactionTrait};
+use sha2::{Digest, Sha256};
+
+/**
+ * The hash of a seed announced when a raffle starts, the seed itself is only
+ * revealed with the draw
+ */
+pub fn hash_seed(seed: &str) -> String {
+    return hex::encode(Sha256::digest(seed.as_bytes()));
+}
+
+/**
+ * Pick a winner from `(user_id, weight)` entries, the same seed, entries and
+ * draw number always give the same winner so a draw can be verified later.
+ * Entries are ordered by user id and hashes that would bias the modulo are
+ * rejected, returns the index into the given entries
+ */
+pub fn pick_winner(seed: &str, entries: &[(i32, i32)], draw_number: i32) -> Option<usize> {
+    let mut order = (0..entries.len())
+        .filter(|i| entries[*i].1 > 0)
+        .collect::<Vec<usize>>();
+    order.sort_by_key(|i| entries[*i].0);
+
+    let total = order.iter().map(|i| entries[*i].1 as u64).sum::<u64>();
+    if total == 0 {
+        return None;
+    }
+    let limit = u64::MAX - u64::MAX % total;
+
+    let mut attempt: u32 = 0;
+    let ticket = loop {
+        let hash = Sha256::digest(format!("{}:{}:{}", seed, draw_number, attempt).as_bytes());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        let value = u64::from_be_bytes(bytes);
+        if value < limit {
+            break value % total;
+        }
+        attempt += 1;
+    };
+
+    let mut sum = 0;
+    for i in order {
+        sum += entries[i].1 as u64;
+        if ticket < sum {
+            return Some(i);
+        }
+    }
+
+    return None;
+}
+
+/**
+ * Replay all draws of a raffle from its seed and entries, returns the user
+ * id of each winner in draw order or why the stored result does not match
+ */
+pub fn verify_draws(
+    raffle: &raffle_entity::Model,
+    entries: &[raffle_entry_entity::Model],
+) -> Result<Vec<i32>, String> {
+    if hash_seed(&raffle.seed) != raffle.seed_hash {
+        return Err(String::from("The seed does not match the announced hash"));
+    }
+
+    let draws = entries.iter().filter_map(|x| x.draw_number).max().unwrap_or(0);
+    let mut winners: Vec<i32> = Vec::new();
+    for draw_number in 1..=draws {
+        // entries drawn before are out of the draw
+        let remaining = entries
+            .iter()
+            .filter(|x| x.draw_number.map(|n| n >= draw_number).unwrap_or(true))
+            .collect::<Vec<&raffle_entry_entity::Model>>();
+        let weights = remaining
+            .iter()
+            .map(|x| (x.user_id, x.weight))
+            .collect::<Vec<(i32, i32)>>();
+
+        let winner = match pick_winner(&raffle.seed, &weights, draw_number) {
+            Some(x) => remaining[x],
+            None => return Err(format!("Draw {} has no entries", draw_number)),
+        };
+        if winner.draw_number != Some(draw_number) {
+            return Err(format!(
+                "Draw {} should have been won by {}",
+                draw_number, winner.user_id
+            ));
+        }
+        winners.push(winner.user_id);
+    }
+
+    return Ok(winners);
+}
+
+/**
+ * Get a raffle by id
+ */
+pub async fn get_raffle<T: ConnectionTrait>(
+    id: &str,
+    db: &T,
+) -> Result<Option<raffle_entity::Model>, Error> {
+    let raffle = raffle_entity::Entity::find_by_id(id.to_string())
+        .one(db)
+        .await?;
+    return Ok(raffle);
+}
+
+/**
+ * Get the raffle of a channel that takes entries
+ */
+pub async fn get_open_raffle<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Option<raffle_entity::Model>, Error> {
+    let raffle = raffle_entity::Entity::find()
+        .filter(raffle_entity::Column::ChannelId.eq(channel_id))
+        .filter(raffle_entity::Column::Status.eq(RaffleStatus::Open))
+        .order_by_desc(raffle_entity::Column::CreatedAt)
+        .one(db)
+        .await?;
+    return Ok(raffle);
+}
+
+/**
+ * Get the raffle of a channel started last that was not cancelled
+ */
+pub async fn get_latest_raffle<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Option<raffle_entity::Model>, Error> {
+    let raffle = raffle_entity::Entity::find()
+        .filter(raffle_entity::Column::ChannelId.eq(channel_id))
+        .filter(raffle_entity::Column::Status.ne(RaffleStatus::Cancelled))
+        .order_by_desc(raffle_entity::Column::CreatedAt)
+        .one(db)
+        .await?;
+    return Ok(raffle);
+}
+
+/**
+ * Get the entries of a raffle by user id
+ */
+pub async fn get_entries<T: ConnectionTrait>(
+    raffle_id: &str,
+    db: &T,
+) -> Result<Vec<raffle_entry_entity::Model>, Error> {
+    let entries = raffle_entry_entity::Entity::find()
+        .filter(raffle_entry_entity::Column::RaffleId.eq(raffle_id))
+        .order_by_asc(raffle_entry_entity::Column::UserId)
+        .all(db)
+        .await?;
+    return Ok(entries);
+}
+
+/**
+ * Check if a user won an earlier raffle of a channel
+ */
+pub async fn has_won<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<bool, Error> {
+    let wins = raffle_entry_entity::Entity::find()
+        .inner_join(raffle_entity::Entity)
+        .filter(raffle_entity::Column::ChannelId.eq(channel_id))
+        .filter(raffle_entry_entity::Column::UserId.eq(user_id))
+        .filter(raffle_entry_entity::Column::DrawNumber.is_not_null())
+        .count(db)
+        .await?;
+    return Ok(wins > 0);
+}
+
+/**
+ * Create a raffle with a new id and the hash of its seed
+ */
+pub async fn create_raffle<T: ConnectionTrait>(
+    mut raffle: raffle_entity::ActiveModel,
+    db: &T,
+) -> Result<raffle_entity::Model, Error> {
+    let seed_hash = match &raffle.seed {
+        sea_orm::ActiveValue::Set(x) | sea_orm::ActiveValue::Unchanged(x) => hash_seed(x),
+        sea_orm::ActiveValue::NotSet => return Err(Error::msg("No seed")),
+    };
+
+    raffle.id = Set(Uuid::new_v4().to_string());
+    raffle.seed_hash = Set(seed_hash);
+    raffle.status = Set(RaffleStatus::Open);
+    let raffle = raffle.insert(db).await?;
+
+    return Ok(raffle);
+}
+
+/**
+ * Enter a user into a raffle, entering again keeps the first entry
+ */
+pub async fn add_entry<T: ConnectionTrait>(
+    raffle_id: &str,
+    user_id: i32,
+    weight: i32,
+    db: &T,
+) -> Result<(), Error> {
+    let entry = raffle_entry_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        raffle_id: Set(String::from(raffle_id)),
+        user_id: Set(user_id),
+        weight: Set(weight),
+        draw_number: Set(None),
+        ..Default::default()
+    };
+
+    raffle_entry_entity::Entity::insert(entry)
+        .on_conflict(
+            OnConflict::columns([
+                raffle_entry_entity::Column::RaffleId,
+                raffle_entry_entity::Column::UserId,
+            ])
+            .update_column(raffle_entry_entity::Column::RaffleId)
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Stop taking entries and draw the next winner among the entries not drawn
+ * yet, the raffle is locked so concurrent draws get their own number
+ */
+pub async fn draw_winner<T: ConnectionTrait + TransactionTrait>(
+    raffle_id: &str,
+    db: &T,
+) -> Result<Option<raffle_entry_entity::Model>, Error> {
+    let txn = db.begin().await?;
+
+    let raffle = raffle_entity::Entity::find_by_id(raffle_id.to_string())
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+    let raffle = match raffle {
+        Some(x) => x,
+        None => return Err(Error::msg("Raffle not found")),
+    };
+    if raffle.status == RaffleStatus::Cancelled {
+        return Err(Error::msg("Raffle was cancelled"));
+    }
+
+    let entries = get_entries(raffle_id, &txn).await?;
+    let draw_number = entries.iter().filter_map(|x| x.draw_number).max().unwrap_or(0) + 1;
+    let remaining = entries
+        .iter()
+        .filter(|x| x.draw_number.is_none())
+        .collect::<Vec<&raffle_entry_entity::Model>>();
+    let weights = remaining
+        .iter()
+        .map(|x| (x.user_id, x.weight))
+        .collect::<Vec<(i32, i32)>>();
+
+    let winner = pick_winner(&raffle.seed, &weights, draw_number).map(|x| remaining[x].clone());
+    if let Some(winner) = &winner {
+        raffle_entry_entity::Entity::update_many()
+            .col_expr(raffle_entry_entity::Column::DrawNumber, Expr::value(draw_number))
+            .col_expr(
+                raffle_entry_entity::Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(raffle_entry_entity::Column::Id.eq(winner.id.to_string()))
+            .exec(&txn)
+            .await?;
+    }
+
+    if raffle.status == RaffleStatus::Open {
+        let mut raffle: raffle_entity::ActiveModel = raffle.into();
+        raffle.status = Set(RaffleStatus::Drawn);
+        raffle.ended_at = Set(Some(chrono::Utc::now()));
+        raffle.updated_at = Set(chrono::Utc::now().naive_utc());
+        raffle.update(&txn).await?;
+    }
+
+    txn.commit().await?;
+
+    return Ok(winner.map(|x| raffle_entry_entity::Model {
+        draw_number: Some(draw_number),
+        ..x
+    }));
+}
+
+/**
+ * Cancel a raffle, its entries are kept
+ */
+pub async fn cancel_raffle<T: ConnectionTrait>(id: &str, db: &T) -> Result<(), Error> {
+    raffle_entity::Entity::update_many()
+        .col_expr(raffle_entity::Column::Status, Expr::value(RaffleStatus::Cancelled))
+        .col_expr(raffle_entity::Column::EndedAt, Expr::value(chrono::Utc::now()))
+        .col_expr(
+            raffle_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(raffle_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
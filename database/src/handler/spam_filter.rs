@@ -0,0 +1,115 @@
+use crate::entity::sea_orm_active_enums::{Permission, SpamFilterType};
+use crate::entity::spam_filter as spam_filter_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{prelude::*, Set};
+
+/**
+ * The settings of a filter a channel did not configure yet, filters are off
+ * until a channel turns them on
+ */
+pub fn default_spam_filter(channel_id: i32, filter: SpamFilterType) -> spam_filter_entity::Model {
+    let (threshold, min_length) = match filter {
+        SpamFilterType::Link => (0, 0),
+        // percent of the letters
+        SpamFilterType::Caps => (70, 15),
+        // emotes in a message
+        SpamFilterType::Emotes => (10, 0),
+        // percent of the characters
+        SpamFilterType::Symbols => (50, 15),
+        // the same message in a row
+        SpamFilterType::Repeat => (3, 0),
+        // characters in a message
+        SpamFilterType::Length => (400, 0),
+    };
+
+    return spam_filter_entity::Model {
+        id: String::new(),
+        channel_id,
+        filter,
+        enabled: false as i8,
+        threshold,
+        min_length,
+        exempt_permission: Permission::Vip,
+        timeout: 60,
+        long_timeout: 600,
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+}
+
+/**
+ * Get the settings of a filter of a channel
+ */
+pub async fn get_spam_filter<T: ConnectionTrait>(
+    channel_id: i32,
+    filter: SpamFilterType,
+    db: &T,
+) -> Result<spam_filter_entity::Model, Error> {
+    let spam_filter = spam_filter_entity::Entity::find()
+        .filter(spam_filter_entity::Column::ChannelId.eq(channel_id))
+        .filter(spam_filter_entity::Column::Filter.eq(filter))
+        .one(db)
+        .await?;
+    return Ok(spam_filter.unwrap_or_else(|| default_spam_filter(channel_id, filter)));
+}
+
+/**
+ * Get the filters a channel turned on
+ */
+pub async fn get_enabled_spam_filters<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<spam_filter_entity::Model>, Error> {
+    let spam_filters = spam_filter_entity::Entity::find()
+        .filter(spam_filter_entity::Column::ChannelId.eq(channel_id))
+        .filter(spam_filter_entity::Column::Enabled.eq(true))
+        .all(db)
+        .await?;
+    return Ok(spam_filters);
+}
+
+/**
+ * Create or update the settings of a filter of a channel
+ */
+pub async fn save_spam_filter<T: ConnectionTrait>(
+    spam_filter: spam_filter_entity::Model,
+    db: &T,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let created_at = match spam_filter.created_at == Default::default() {
+        true => now,
+        false => spam_filter.created_at,
+    };
+    let id = match spam_filter.id.is_empty() {
+        true => Uuid::new_v4().to_string(),
+        false => String::from(&spam_filter.id),
+    };
+    let mut spam_filter: spam_filter_entity::ActiveModel = spam_filter.into();
+    spam_filter = spam_filter.reset_all();
+    spam_filter.id = Set(id);
+    spam_filter.created_at = Set(created_at);
+    spam_filter.updated_at = Set(now);
+
+    spam_filter_entity::Entity::insert(spam_filter)
+        .on_conflict(
+            OnConflict::columns([
+                spam_filter_entity::Column::ChannelId,
+                spam_filter_entity::Column::Filter,
+            ])
+            .update_columns([
+                spam_filter_entity::Column::Enabled,
+                spam_filter_entity::Column::Threshold,
+                spam_filter_entity::Column::MinLength,
+                spam_filter_entity::Column::ExemptPermission,
+                spam_filter_entity::Column::Timeout,
+                spam_filter_entity::Column::LongTimeout,
+                spam_filter_entity::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
@@ -0,0 +1,19 @@
+use crate::entity::stream_history as stream_history_entity;
+use anyhow::{Error, Result};
+use sea_orm::{prelude::*, QueryOrder};
+
+/**
+ * Get the stream a channel is currently live with
+ */
+pub async fn get_current_stream<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Option<stream_history_entity::Model>, Error> {
+    let stream = stream_history_entity::Entity::find()
+        .filter(stream_history_entity::Column::ChannelId.eq(channel_id))
+        .filter(stream_history_entity::Column::EndedAt.is_null())
+        .order_by_desc(stream_history_entity::Column::StartedAt)
+        .one(db)
+        .await?;
+    return Ok(stream);
+}
@@ -0,0 +1,193 @@
+use crate::entity::strike as strike_entity;
+use crate::entity::strike_settings as strike_settings_entity;
+use anyhow::{Error, Result};
+use chrono::NaiveDateTime;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{prelude::*, QueryOrder, Set};
+
+pub const DEFAULT_LADDER: &str = "warn,60,600,ban";
+const MAX_TIMEOUT: i32 = 14 * 24 * 60 * 60;
+
+/**
+ * What a strike does to a viewer
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrikeStep {
+    Warn,
+    Timeout(i32),
+    Ban,
+}
+
+/**
+ * Parse a ladder like `warn,60,600,ban` where a number is a timeout in
+ * seconds, the errors are meant to be shown to the user
+ */
+pub fn parse_ladder(ladder: &str) -> Result<Vec<StrikeStep>, String> {
+    let steps = ladder
+        .split(',')
+        .map(|x| x.trim().to_lowercase())
+        .map(|x| match x.as_str() {
+            "warn" => Ok(StrikeStep::Warn),
+            "ban" => Ok(StrikeStep::Ban),
+            _ => match x.parse::<i32>() {
+                Ok(x) if (1..=MAX_TIMEOUT).contains(&x) => Ok(StrikeStep::Timeout(x)),
+                _ => Err(format!(
+                    "{} is not warn, ban or a timeout of up to {} seconds",
+                    x, MAX_TIMEOUT
+                )),
+            },
+        })
+        .collect::<Result<Vec<StrikeStep>, String>>()?;
+
+    return Ok(steps);
+}
+
+/**
+ * The step of the ladder for the given number of active strikes, strikes
+ * past the end of the ladder repeat its last step
+ */
+pub fn strike_step(ladder: &[StrikeStep], strikes: usize) -> StrikeStep {
+    let index = strikes.max(1) - 1;
+    return match ladder.get(index).or(ladder.last()) {
+        Some(x) => *x,
+        None => StrikeStep::Warn,
+    };
+}
+
+/**
+ * The strike settings of a channel that did not change them
+ */
+pub fn default_strike_settings(channel_id: i32) -> strike_settings_entity::Model {
+    return strike_settings_entity::Model {
+        channel_id,
+        decay_hours: 30 * 24,
+        ladder: String::from(DEFAULT_LADDER),
+        created_at: Default::default(),
+        updated_at: Default::default(),
+    };
+}
+
+/**
+ * Get the strike settings of a channel
+ */
+pub async fn get_strike_settings<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<strike_settings_entity::Model, Error> {
+    let settings = strike_settings_entity::Entity::find_by_id(channel_id)
+        .one(db)
+        .await?;
+    return Ok(settings.unwrap_or_else(|| default_strike_settings(channel_id)));
+}
+
+/**
+ * Create or update the strike settings of a channel
+ */
+pub async fn save_strike_settings<T: ConnectionTrait>(
+    settings: strike_settings_entity::Model,
+    db: &T,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let created_at = match settings.created_at == Default::default() {
+        true => now,
+        false => settings.created_at,
+    };
+    let mut settings: strike_settings_entity::ActiveModel = settings.into();
+    settings = settings.reset_all();
+    settings.created_at = Set(created_at);
+    settings.updated_at = Set(now);
+
+    strike_settings_entity::Entity::insert(settings)
+        .on_conflict(
+            OnConflict::column(strike_settings_entity::Column::ChannelId)
+                .update_columns([
+                    strike_settings_entity::Column::DecayHours,
+                    strike_settings_entity::Column::Ladder,
+                    strike_settings_entity::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Get the strikes of a user in a channel given since a time that were not
+ * pardoned, newest first
+ */
+pub async fn get_active_strikes<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    since: NaiveDateTime,
+    db: &T,
+) -> Result<Vec<strike_entity::Model>, Error> {
+    let strikes = strike_entity::Entity::find()
+        .filter(strike_entity::Column::ChannelId.eq(channel_id))
+        .filter(strike_entity::Column::UserId.eq(user_id))
+        .filter(strike_entity::Column::PardonedAt.is_null())
+        .filter(strike_entity::Column::CreatedAt.gte(since))
+        .order_by_desc(strike_entity::Column::CreatedAt)
+        .all(db)
+        .await?;
+    return Ok(strikes);
+}
+
+/**
+ * Give a user a strike in a channel
+ */
+pub async fn add_strike<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    moderator_id: Option<i32>,
+    reason: Option<String>,
+    db: &T,
+) -> Result<strike_entity::Model, Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let strike = strike_entity::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        channel_id: Set(channel_id),
+        user_id: Set(user_id),
+        moderator_id: Set(moderator_id),
+        reason: Set(reason),
+        pardoned_at: Set(None),
+        pardoned_by: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+    let strike = strike.insert(db).await?;
+
+    return Ok(strike);
+}
+
+/**
+ * Pardon the active strikes of a user in a channel, they are kept for the
+ * history, returns how many were pardoned
+ */
+pub async fn pardon_strikes<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    since: NaiveDateTime,
+    pardoned_by: i32,
+    db: &T,
+) -> Result<u64, Error> {
+    let result = strike_entity::Entity::update_many()
+        .col_expr(
+            strike_entity::Column::PardonedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .col_expr(strike_entity::Column::PardonedBy, Expr::value(pardoned_by))
+        .col_expr(
+            strike_entity::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now().naive_utc()),
+        )
+        .filter(strike_entity::Column::ChannelId.eq(channel_id))
+        .filter(strike_entity::Column::UserId.eq(user_id))
+        .filter(strike_entity::Column::PardonedAt.is_null())
+        .filter(strike_entity::Column::CreatedAt.gte(since))
+        .exec(db)
+        .await?;
+
+    return Ok(result.rows_affected);
+}
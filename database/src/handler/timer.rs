@@ -0,0 +1,138 @@
+use crate::entity::timer as timer_entity;
+use anyhow::{Error, Result};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, QueryOrder, Set, TransactionTrait};
+
+/**
+ * Get the timers of a channel in their order
+ */
+pub async fn get_timers<T: ConnectionTrait>(
+    channel_id: i32,
+    db: &T,
+) -> Result<Vec<timer_entity::Model>, Error> {
+    let timers = timer_entity::Entity::find()
+        .filter(timer_entity::Column::ChannelId.eq(channel_id))
+        .order_by_asc(timer_entity::Column::Position)
+        .order_by_asc(timer_entity::Column::Name)
+        .all(db)
+        .await?;
+    return Ok(timers);
+}
+
+/**
+ * Get a timer of a channel by its name
+ */
+pub async fn get_timer<T: ConnectionTrait>(
+    channel_id: i32,
+    name: &str,
+    db: &T,
+) -> Result<Option<timer_entity::Model>, Error> {
+    let timer = timer_entity::Entity::find()
+        .filter(timer_entity::Column::ChannelId.eq(channel_id))
+        .filter(timer_entity::Column::Name.eq(name.to_lowercase()))
+        .one(db)
+        .await?;
+    return Ok(timer);
+}
+
+/**
+ * Create a timer with a new id at the end of the order of its channel
+ */
+pub async fn create_timer<T: ConnectionTrait>(
+    mut timer: timer_entity::ActiveModel,
+    db: &T,
+) -> Result<timer_entity::Model, Error> {
+    let channel_id = match &timer.channel_id {
+        sea_orm::ActiveValue::Set(x) | sea_orm::ActiveValue::Unchanged(x) => *x,
+        sea_orm::ActiveValue::NotSet => return Err(Error::msg("No channel id")),
+    };
+    let position = get_timers(channel_id, db)
+        .await?
+        .iter()
+        .map(|x| x.position + 1)
+        .max()
+        .unwrap_or(0);
+
+    timer.id = Set(Uuid::new_v4().to_string());
+    timer.position = Set(position);
+    let timer = timer.insert(db).await?;
+
+    return Ok(timer);
+}
+
+/**
+ * Update the changed columns of a timer
+ */
+pub async fn update_timer<T: ConnectionTrait>(
+    mut timer: timer_entity::ActiveModel,
+    db: &T,
+) -> Result<timer_entity::Model, Error> {
+    timer.updated_at = Set(chrono::Utc::now().naive_utc());
+    let timer = timer.update(db).await?;
+
+    return Ok(timer);
+}
+
+/**
+ * Delete a timer
+ */
+pub async fn delete_timer<T: ConnectionTrait>(id: &str, db: &T) -> Result<(), Error> {
+    timer_entity::Entity::delete_by_id(id.to_string())
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
+
+/**
+ * Move a timer to a zero based index in the order of its channel, the other
+ * timers shift and all positions are renumbered
+ */
+pub async fn move_timer<T: ConnectionTrait + TransactionTrait>(
+    channel_id: i32,
+    id: &str,
+    index: usize,
+    db: &T,
+) -> Result<(), Error> {
+    let txn = db.begin().await?;
+
+    let mut timers = get_timers(channel_id, &txn).await?;
+    let current = match timers.iter().position(|x| x.id == id) {
+        Some(x) => x,
+        None => return Err(Error::msg("Timer not found")),
+    };
+    let timer = timers.remove(current);
+    timers.insert(index.min(timers.len()), timer);
+
+    for (position, timer) in timers.iter().enumerate() {
+        if timer.position == position as i32 {
+            continue;
+        }
+        timer_entity::Entity::update_many()
+            .col_expr(timer_entity::Column::Position, Expr::value(position as i32))
+            .filter(timer_entity::Column::Id.eq(timer.id.to_string()))
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    return Ok(());
+}
+
+/**
+ * Remember when a timer was posted
+ */
+pub async fn mark_posted<T: ConnectionTrait>(
+    id: &str,
+    timestamp: DateTimeUtc,
+    db: &T,
+) -> Result<(), Error> {
+    timer_entity::Entity::update_many()
+        .col_expr(timer_entity::Column::LastPosted, Expr::value(timestamp))
+        .filter(timer_entity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+
+    return Ok(());
+}
@@ -68,13 +68,26 @@ pub async fn get_user<T: ConnectionTrait>(
     return Ok(user);
 }
 
+/**
+ * Get a user by nick, nicks are lowercase
+ */
+pub async fn get_user_by_nick<T: ConnectionTrait>(
+    nick: &str,
+    db: &T,
+) -> Result<Option<user_entity::Model>, Error> {
+    let user = user_entity::Entity::find()
+        .filter(user_entity::Column::Nick.eq(nick.to_lowercase()))
+        .one(db)
+        .await?;
+    return Ok(user);
+}
+
 /**
  * Delete a user
  */
 pub async fn delete_user<T: ConnectionTrait>(user_id: i32, db: &T) -> Result<(), Error> {
     let user = user_entity::Entity::find_by_id(user_id).one(db).await?;
-    if user.is_some() {
-        let user = user.unwrap();
+    if let Some(user) = user {
         user.delete(db).await?;
     }
     return Ok(());
@@ -85,8 +98,7 @@ pub async fn delete_user<T: ConnectionTrait>(user_id: i32, db: &T) -> Result<(),
  */
 pub async fn deactivate_user<T: ConnectionTrait>(user_id: i32, db: &T) -> Result<(), Error> {
     let user = user_entity::Entity::find_by_id(user_id).one(db).await?;
-    if user.is_some() {
-        let user = user.unwrap();
+    if let Some(user) = user {
         let mut user: user_entity::ActiveModel = user.into();
         user.email = NotSet;
         user.update(db).await?;
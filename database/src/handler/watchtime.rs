@@ -68,3 +68,26 @@ pub async fn stop_watching<T: ConnectionTrait>(
 
     return Ok(());
 }
+
+/**
+ * Get the total seconds a user watched a channel, open sessions count until now
+ */
+pub async fn get_watch_time<T: ConnectionTrait>(
+    channel_id: i32,
+    user_id: i32,
+    db: &T,
+) -> Result<i64, Error> {
+    let watch_time: Vec<watch_time_entity::Model> = watch_time_entity::Entity::find()
+        .filter(watch_time_entity::Column::BoardcasterId.eq(channel_id))
+        .filter(watch_time_entity::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+
+    let now = chrono::Utc::now();
+    let seconds = watch_time
+        .iter()
+        .map(|x| (x.ended_at.unwrap_or(now) - x.started_at).num_seconds().max(0))
+        .sum();
+
+    return Ok(seconds);
+}
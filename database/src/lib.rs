@@ -1,6 +1,9 @@
 pub mod entity;
 pub mod handler;
 
+#[cfg(test)]
+mod test;
+
 use sea_orm::{DatabaseConnection, Database};
 use anyhow::{Result, Error};
 
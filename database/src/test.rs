@@ -0,0 +1,83 @@
+use crate::entity::raffle as raffle_entity;
+use crate::entity::raffle_entry as raffle_entry_entity;
+use crate::entity::sea_orm_active_enums::RaffleStatus;
+use crate::handler::raffle::{hash_seed, pick_winner, verify_draws};
+
+#[test]
+fn pick_winner_test() {
+    let entries = vec![(3, 1), (1, 2), (2, 0)];
+    let winner = pick_winner("seed", &entries, 1);
+    assert!(matches!(winner, Some(0 | 1)));
+    // the order of the entries does not change the winner
+    let reversed = entries.iter().rev().cloned().collect::<Vec<(i32, i32)>>();
+    assert_eq!(
+        pick_winner("seed", &reversed, 1).map(|x| reversed[x].0),
+        winner.map(|x| entries[x].0)
+    );
+    assert_eq!(pick_winner("seed", &[(2, 0)], 1), None);
+    assert_eq!(pick_winner("seed", &[], 1), None);
+}
+
+fn entry(user_id: i32, weight: i32) -> raffle_entry_entity::Model {
+    let now = chrono::Utc::now().naive_utc();
+    return raffle_entry_entity::Model {
+        id: user_id.to_string(),
+        raffle_id: "1".to_string(),
+        user_id,
+        weight,
+        draw_number: None,
+        created_at: now,
+        updated_at: now,
+    };
+}
+
+#[test]
+fn verify_draws_test() {
+    let now = chrono::Utc::now();
+    let raffle = raffle_entity::Model {
+        id: "1".to_string(),
+        channel_id: 1,
+        keyword: "!join".to_string(),
+        status: RaffleStatus::Drawn,
+        followers_only: 0,
+        subscribers_only: 0,
+        min_watch_time: 0,
+        subscriber_weight: 1,
+        exclude_winners: 0,
+        seed: "seed".to_string(),
+        seed_hash: hash_seed("seed"),
+        started_by: 1,
+        ended_at: Some(now),
+        created_at: now.naive_utc(),
+        updated_at: now.naive_utc(),
+    };
+    let mut entries = vec![entry(1, 1), entry(2, 2), entry(3, 1), entry(4, 3)];
+
+    // draw like the bot does, each draw only among the entries not drawn yet
+    let mut drawn: Vec<i32> = Vec::new();
+    for draw_number in 1..=3 {
+        let remaining = (0..entries.len())
+            .filter(|i| entries[*i].draw_number.is_none())
+            .collect::<Vec<usize>>();
+        let weights = remaining
+            .iter()
+            .map(|i| (entries[*i].user_id, entries[*i].weight))
+            .collect::<Vec<(i32, i32)>>();
+        let winner = remaining[pick_winner(&raffle.seed, &weights, draw_number).unwrap()];
+        entries[winner].draw_number = Some(draw_number);
+        drawn.push(entries[winner].user_id);
+    }
+    assert_eq!(verify_draws(&raffle, &entries), Ok(drawn.clone()));
+
+    // a winner swapped after the draw does not verify
+    let mut swapped = entries.clone();
+    let first = swapped.iter().position(|x| x.draw_number == Some(1)).unwrap();
+    let other = swapped.iter().position(|x| x.draw_number.is_none()).unwrap();
+    swapped[first].draw_number = None;
+    swapped[other].draw_number = Some(1);
+    assert!(verify_draws(&raffle, &swapped).is_err());
+
+    let mut reseeded = raffle.clone();
+    reseeded.seed = "other".to_string();
+    assert!(verify_draws(&reseeded, &entries).is_err());
+}
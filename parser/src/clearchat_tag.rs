@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use anyhow::{Result, Error};
+
+#[derive(Debug, Clone)]
+pub struct ClearChatTags {
+    pub ban_duration: Option<i32>,
+    pub room_id: i32,
+    pub target_user_id: Option<i32>,
+    pub tmi_sent_ts: i64,
+}
+
+/**
+ * Parse the tags from a CLEARCHAT message
+ */
+pub fn parse(tags: &HashMap<String, String>) -> Result<ClearChatTags, Error> {
+    let ban_duration = match tags.get("ban-duration") {
+        Some(x) => Some(x.parse::<i32>()?),
+        _ => None,
+    };
+
+    let room_id = match tags.get("room-id") {
+        Some(x) => x.parse::<i32>()?,
+        _ => return Err(Error::msg("Missing room-id tag")),
+    };
+
+    let target_user_id = match tags.get("target-user-id") {
+        Some(x) => Some(x.parse::<i32>()?),
+        _ => None,
+    };
+
+    let tmi_sent_ts = match tags.get("tmi-sent-ts") {
+        Some(x) => x.parse::<i64>()?,
+        _ => return Err(Error::msg("Missing tmi-sent-ts tag")),
+    };
+
+    Ok(ClearChatTags {
+        ban_duration,
+        room_id,
+        target_user_id,
+        tmi_sent_ts,
+    })
+}
@@ -19,10 +19,7 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<ClearMsgTags, Error> {
     };
 
     let room_id = match tags.get("room-id") {
-        Some(x) => match x.parse::<i64>() {
-            Ok(x) => Some(x),
-            Err(_) => None,
-        },
+        Some(x) => x.parse::<i64>().ok(),
         _ => return Err(Error::msg("Missing room-id tag")),
     };
 
@@ -1,8 +1,21 @@
-use anyhow::{Error, Result};
 use std::collections::HashMap;
 
 use crate::privmsg_tag::PrivMsgTags;
 use crate::clearmsg_tag::ClearMsgTags;
+use crate::clearchat_tag::ClearChatTags;
+use crate::roomstate_tag::RoomStateTags;
+use crate::userstate_tag::{GlobalUserStateTags, UserStateTags};
+use crate::usernotice_tag::UserNoticeTags;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ParseError {
+    #[error("Message ends inside the tags section")]
+    UnterminatedTags,
+    #[error("Message ends inside the source section")]
+    UnterminatedSource,
+    #[error("Tag without a key: {0}")]
+    EmptyTagKey(String),
+}
 
 #[derive(Debug, Clone)]
 pub struct ChatSource {
@@ -13,8 +26,15 @@ pub struct ChatSource {
 #[derive(Debug, Clone, PartialEq)]
 pub enum IRCCommandType {
     PING,
+    PONG,
+    RECONNECT,
     PRIVMSG,
     CLEARMSG,
+    CLEARCHAT,
+    USERNOTICE,
+    ROOMSTATE,
+    USERSTATE,
+    GLOBALUSERSTATE,
     JOIN,
     UNKNOWN,
 }
@@ -47,12 +67,7 @@ impl ParsedMessage {
             _ => return None,
         };
 
-        let tags = crate::privmsg_tag::parse(&tags);
-
-        match tags {
-            Ok(x) => Some(x),
-            _ => None,
-        }
+        return crate::privmsg_tag::parse(&tags).ok();
     }
 
     pub fn clearmsg_tags(&self) -> Option<ClearMsgTags> {
@@ -61,20 +76,59 @@ impl ParsedMessage {
             _ => return None,
         };
 
-        let tags = crate::clearmsg_tag::parse(&tags);
+        return crate::clearmsg_tag::parse(&tags).ok();
+    }
+
+    pub fn clearchat_tags(&self) -> Option<ClearChatTags> {
+        let tags = match self.tags.clone() {
+            Some(x) => x,
+            _ => return None,
+        };
 
+        return crate::clearchat_tag::parse(&tags).ok();
+    }
 
-        match tags {
-            Ok(x) => Some(x),
-            _ => None,
-        }
+    pub fn roomstate_tags(&self) -> Option<RoomStateTags> {
+        let tags = match self.tags.clone() {
+            Some(x) => x,
+            _ => return None,
+        };
+
+        return crate::roomstate_tag::parse(&tags).ok();
+    }
+
+    pub fn userstate_tags(&self) -> Option<UserStateTags> {
+        let tags = match self.tags.clone() {
+            Some(x) => x,
+            _ => return None,
+        };
+
+        return crate::userstate_tag::parse(&tags).ok();
+    }
+
+    pub fn globaluserstate_tags(&self) -> Option<GlobalUserStateTags> {
+        let tags = match self.tags.clone() {
+            Some(x) => x,
+            _ => return None,
+        };
+
+        return crate::userstate_tag::parse_global(&tags).ok();
+    }
+
+    pub fn usernotice_tags(&self) -> Option<UserNoticeTags> {
+        let tags = match self.tags.clone() {
+            Some(x) => x,
+            _ => return None,
+        };
+
+        return crate::usernotice_tag::parse(&tags).ok();
     }
 }
 
 /**
- * Parse the source of the message from the twitch irc
+ * Parse a single message from the twitch irc
  */
-pub async fn parse(msg: &str) -> Result<ParsedMessage, Error> {
+pub async fn parse(msg: &str) -> Result<ParsedMessage, ParseError> {
     let mut idx = 0;
     let mut tags: Option<HashMap<String, String>> = None;
     let mut source: ChatSource = ChatSource {
@@ -82,31 +136,23 @@ pub async fn parse(msg: &str) -> Result<ParsedMessage, Error> {
         host: "".to_string(),
     };
 
-    if msg.chars().nth(idx) == Some('@') {
+    if msg[idx..].starts_with('@') {
         idx += 1;
-        let end = match msg[idx..].find(" ") {
+        let end = match msg[idx..].find(' ') {
             Some(x) => x + idx,
-            _ => return Err(Error::msg("Invalid message")),
+            _ => return Err(ParseError::UnterminatedTags),
         };
-        let tags_string = msg[idx..end].split(";").collect::<Vec<&str>>();
-        tags = Some(HashMap::new());
-        for tag in tags_string {
-            let tag = tag.split("=").collect::<Vec<&str>>();
-            tags.as_mut()
-                .unwrap()
-                .insert(tag[0].to_string(), tag[1].to_string());
-        }
-
+        tags = Some(parse_tags(&msg[idx..end])?);
         idx = end + 1;
     }
 
-    if msg.chars().nth(idx) == Some(':') {
+    if msg[idx..].starts_with(':') {
         idx += 1;
-        let end = match msg[idx..].find(" ") {
+        let end = match msg[idx..].find(' ') {
             Some(x) => x + idx,
-            _ => return Err(Error::msg("Invalid message")),
+            _ => return Err(ParseError::UnterminatedSource),
         };
-        source = parse_source(&msg[idx..end].to_string());
+        source = parse_source(&msg[idx..end]);
         idx = end + 1;
     }
 
@@ -115,7 +161,7 @@ pub async fn parse(msg: &str) -> Result<ParsedMessage, Error> {
         _ => msg.len(),
     };
 
-    let command = parse_command(&msg[idx..end].trim().to_string());
+    let command = parse_command(msg[idx..end].trim());
     let mut params: Option<String> = None;
     let mut chat_command: Option<ChatCommand> = None;
 
@@ -123,7 +169,7 @@ pub async fn parse(msg: &str) -> Result<ParsedMessage, Error> {
         idx = end + 1;
 
         params = Some(msg[idx..].to_string());
-        chat_command = parse_params(&msg[idx..].to_string());
+        chat_command = parse_params(&msg[idx..]);
     }
 
     return Ok(ParsedMessage {
@@ -135,6 +181,64 @@ pub async fn parse(msg: &str) -> Result<ParsedMessage, Error> {
     });
 }
 
+/**
+ * Parse the IRCv3 tags section, without the leading '@'
+ *
+ * Valueless (`key`) and empty (`key=`) tags both map to an empty string and
+ * when a key is repeated the last value wins.
+ */
+fn parse_tags(raw: &str) -> Result<HashMap<String, String>, ParseError> {
+    let mut tags: HashMap<String, String> = HashMap::new();
+
+    for tag in raw.split(';') {
+        if tag.is_empty() {
+            continue;
+        }
+
+        let (key, value) = match tag.split_once('=') {
+            Some((key, value)) => (key, unescape_tag_value(value)),
+            None => (tag, String::new()),
+        };
+
+        if key.is_empty() {
+            return Err(ParseError::EmptyTagKey(tag.to_string()));
+        }
+
+        tags.insert(key.to_string(), value);
+    }
+
+    return Ok(tags);
+}
+
+/**
+ * Unescape an IRCv3 tag value
+ *
+ * An unknown escape drops the backslash and a trailing lone backslash is removed.
+ */
+fn unescape_tag_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some(':') => unescaped.push(';'),
+            Some('s') => unescaped.push(' '),
+            Some('\\') => unescaped.push('\\'),
+            Some('r') => unescaped.push('\r'),
+            Some('n') => unescaped.push('\n'),
+            Some(x) => unescaped.push(x),
+            None => (),
+        }
+    }
+
+    return unescaped;
+}
+
 /**
  * Parse the command from the twitch irc
  */
@@ -149,9 +253,16 @@ fn parse_command(command: &str) -> IRCCommand {
     return IRCCommand {
         command: match split[0] {
             "PING" => IRCCommandType::PING,
+            "PONG" => IRCCommandType::PONG,
+            "RECONNECT" => IRCCommandType::RECONNECT,
             "PRIVMSG" => IRCCommandType::PRIVMSG,
             "JOIN" => IRCCommandType::JOIN,
             "CLEARMSG" => IRCCommandType::CLEARMSG,
+            "CLEARCHAT" => IRCCommandType::CLEARCHAT,
+            "USERNOTICE" => IRCCommandType::USERNOTICE,
+            "ROOMSTATE" => IRCCommandType::ROOMSTATE,
+            "USERSTATE" => IRCCommandType::USERSTATE,
+            "GLOBALUSERSTATE" => IRCCommandType::GLOBALUSERSTATE,
             _ => IRCCommandType::UNKNOWN
         },
         params,
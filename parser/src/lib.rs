@@ -1,6 +1,10 @@
 pub mod irc_parser;
 mod clearmsg_tag;
-mod privmsg_tag;
+pub mod clearchat_tag;
+pub mod roomstate_tag;
+pub mod privmsg_tag;
+pub mod usernotice_tag;
+pub mod userstate_tag;
 
 #[cfg(test)]
 mod test;
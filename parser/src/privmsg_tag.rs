@@ -31,10 +31,7 @@ pub struct PrivMsgTags {
  * Parse the tags from a PRIVMSG message
  */
 pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
-    let badge_info = match tags.get("badge-info") {
-        Some(x) => Some(x.to_string()),
-        None => None,
-    };
+    let badge_info = tags.get("badge-info").map(|x| x.to_string());
 
     let badges = match tags.get("badges") {
         Some(x) => x.split(",").map(|x| x.to_string()).collect::<Vec<String>>(),
@@ -44,10 +41,7 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
     let admin = badges.iter().any(|x| x.contains("broadcaster/1"));
 
     let bits = match tags.get("bits") {
-        Some(x) => match x.parse::<i32>() {
-            Ok(x) => x,
-            Err(_) => 0,
-        },
+        Some(x) => x.parse::<i32>().unwrap_or_default(),
         None => 0,
     };
 
@@ -61,10 +55,7 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
         None => return Err(Error::msg("No display name")),
     };
 
-    let emotes = match tags.get("emotes") {
-        Some(x) => Some(x.to_string()),
-        None => None,
-    };
+    let emotes = tags.get("emotes").map(|x| x.to_string());
 
     let id = match tags.get("id") {
         Some(x) => x.to_string(),
@@ -72,32 +63,17 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
     };
 
     let moderator = match tags.get("mod") {
-        Some(x) => match x.as_str() {
-            "1" => true,
-            _ => false,
-        },
+        Some(x) => x == "1",
         None => return Err(Error::msg("No mod")),
     };
 
-    let reply_parent_msg_id = match tags.get("reply-parent-msg-id") {
-        Some(x) => Some(x.to_string()),
-        None => None,
-    };
+    let reply_parent_msg_id = tags.get("reply-parent-msg-id").map(|x| x.to_string());
 
-    let reply_parent_user_nick = match tags.get("reply-parent-user-login") {
-        Some(x) => Some(x.to_string()),
-        None => None,
-    };
+    let reply_parent_user_nick = tags.get("reply-parent-user-login").map(|x| x.to_string());
 
-    let reply_parent_user_display_name = match tags.get("reply-parent-display-name") {
-        Some(x) => Some(x.to_string()),
-        None => None,
-    };
+    let reply_parent_user_display_name = tags.get("reply-parent-display-name").map(|x| x.to_string());
 
-    let reply_parent_body = match tags.get("reply-parent-msg-body") {
-        Some(x) => Some(x.to_string().replace("\\s", " ")),
-        None => None,
-    };
+    let reply_parent_body = tags.get("reply-parent-msg-body").map(|x| x.to_string());
 
     let room_id = match tags.get("room-id") {
         Some(x) => match x.parse::<i32>() {
@@ -108,10 +84,7 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
     };
 
     let subscriber = match tags.get("subscriber") {
-        Some(x) => match x.as_str() {
-            "1" => true,
-            _ => false,
-        },
+        Some(x) => x == "1",
         None => return Err(Error::msg("No subscriber")),
     };
 
@@ -120,10 +93,7 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
         None => return Err(Error::msg("No tmi-sent-ts")),
     };
 
-    let turbo = match tags.get("turbo") {
-        Some(_) => true,
-        None => false,
-    };
+    let turbo = tags.get("turbo").is_some();
 
     let user_type = match tags.get("user-type") {
         Some(x) => match x.as_str() {
@@ -143,10 +113,7 @@ pub fn parse(tags: &HashMap<String, String>) -> Result<PrivMsgTags, Error> {
         None => return Err(Error::msg("No user id")),
     };
 
-    let vip = match tags.get("vip") {
-        Some(_) => true,
-        None => false,
-    };
+    let vip = tags.get("vip").is_some();
 
     return Ok(PrivMsgTags {
         badge_info,
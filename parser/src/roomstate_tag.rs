@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use anyhow::{Result, Error};
+
+/**
+ * Twitch only sends the changed settings on updates, so every setting is optional
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomStateTags {
+    pub emote_only: Option<bool>,
+    pub followers_only: Option<i32>,
+    pub r9k: Option<bool>,
+    pub room_id: i32,
+    pub slow: Option<i32>,
+    pub subs_only: Option<bool>,
+}
+
+/**
+ * Parse the tags from a ROOMSTATE message
+ */
+pub fn parse(tags: &HashMap<String, String>) -> Result<RoomStateTags, Error> {
+    let emote_only = tags.get("emote-only").map(|x| x == "1");
+
+    let followers_only = match tags.get("followers-only") {
+        Some(x) => Some(x.parse::<i32>()?),
+        _ => None,
+    };
+
+    let r9k = tags.get("r9k").map(|x| x == "1");
+
+    let room_id = match tags.get("room-id") {
+        Some(x) => x.parse::<i32>()?,
+        _ => return Err(Error::msg("Missing room-id tag")),
+    };
+
+    let slow = match tags.get("slow") {
+        Some(x) => Some(x.parse::<i32>()?),
+        _ => None,
+    };
+
+    let subs_only = tags.get("subs-only").map(|x| x == "1");
+
+    Ok(RoomStateTags {
+        emote_only,
+        followers_only,
+        r9k,
+        room_id,
+        slow,
+        subs_only,
+    })
+}
@@ -3,7 +3,7 @@ async fn ping_parse_test() {
     let input = "PING :tmi.twitch.tv";
     let parsed = crate::irc_parser::parse(input).await;
 
-    assert_eq!(parsed.is_ok(), true);
+    assert!(parsed.is_ok());
     let parsed = parsed.unwrap();
 
     assert_eq!(
@@ -13,16 +13,33 @@ async fn ping_parse_test() {
     assert_eq!(parsed.params, Some("tmi.twitch.tv".to_string()));
 }
 
+#[tokio::test]
+async fn reconnect_parse_test() {
+    let parsed = crate::irc_parser::parse(":tmi.twitch.tv RECONNECT").await.unwrap();
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::RECONNECT
+    );
+
+    let parsed = crate::irc_parser::parse(":tmi.twitch.tv PONG tmi.twitch.tv :tmi.twitch.tv")
+        .await
+        .unwrap();
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::PONG
+    );
+}
+
 #[tokio::test]
 async fn privmsg_parse_test() {
     let input = "@badges=staff/1,broadcaster/1,turbo/1;color=#FF0000;display-name=PetsgomOO;emote-only=1;emotes=33:0-7;flags=0-7:A.6/P.6,25-36:A.1/I.2;id=c285c9ed-8b1b-4702-ae1c-c64d76cc74ef;mod=0;room-id=81046256;subscriber=0;turbo=0;tmi-sent-ts=1550868292494;user-id=81046256;user-type=staff :petsgomoo!petsgomoo@petsgomoo.tmi.twitch.tv PRIVMSG #petsgomoo :DansGame";
 
     let parsed = crate::irc_parser::parse(input).await;
-    assert_eq!(parsed.is_ok(), true);
+    assert!(parsed.is_ok());
     let parsed = parsed.unwrap();
 
     let tags = parsed.privmsg_tags();
-    assert_eq!(tags.is_some(), true);
+    assert!(tags.is_some());
     let tags = tags.unwrap();
 
     assert_eq!(
@@ -32,6 +49,212 @@ async fn privmsg_parse_test() {
     assert_eq!(parsed.params, Some("DansGame".to_string()));
     assert_eq!(parsed.source.nick, "petsgomoo");
     assert_eq!(parsed.source.host, "petsgomoo@petsgomoo.tmi.twitch.tv");
-    assert_eq!(tags.admin, true);
+    assert!(tags.admin);
     assert_eq!(tags.room_id, 81046256);
 }
+
+#[tokio::test]
+async fn tag_unescape_test() {
+    let input = "@display-name=Foo;reply-parent-msg-body=hello\\sworld\\:\\\\\\r\\n;unknown=\\a\\ :tmi.twitch.tv PRIVMSG #foo :bar";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    let tags = parsed.tags.unwrap();
+
+    assert_eq!(tags.get("display-name"), Some(&"Foo".to_string()));
+    assert_eq!(
+        tags.get("reply-parent-msg-body"),
+        Some(&"hello world;\\\r\n".to_string())
+    );
+    assert_eq!(tags.get("unknown"), Some(&"a".to_string()));
+}
+
+#[tokio::test]
+async fn tag_valueless_and_duplicate_test() {
+    let input = "@emote-only;emotes=;color=#FF0000;color=#00FF00; :tmi.twitch.tv PRIVMSG #foo :bar";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    let tags = parsed.tags.unwrap();
+
+    assert_eq!(tags.get("emote-only"), Some(&"".to_string()));
+    assert_eq!(tags.get("emotes"), Some(&"".to_string()));
+    assert_eq!(tags.get("color"), Some(&"#00FF00".to_string()));
+    assert_eq!(tags.len(), 3);
+}
+
+#[tokio::test]
+async fn parse_error_test() {
+    let parsed = crate::irc_parser::parse("@badges=staff/1").await;
+    assert_eq!(
+        parsed.unwrap_err(),
+        crate::irc_parser::ParseError::UnterminatedTags
+    );
+
+    let parsed = crate::irc_parser::parse("@=foo :tmi.twitch.tv PING").await;
+    assert_eq!(
+        parsed.unwrap_err(),
+        crate::irc_parser::ParseError::EmptyTagKey("=foo".to_string())
+    );
+
+    let parsed = crate::irc_parser::parse(":tmi.twitch.tv").await;
+    assert_eq!(
+        parsed.unwrap_err(),
+        crate::irc_parser::ParseError::UnterminatedSource
+    );
+}
+
+#[tokio::test]
+async fn usernotice_resub_parse_test() {
+    let input = "@badge-info=;badges=staff/1,broadcaster/1,turbo/1;color=#008000;display-name=ronni;emotes=;id=db25007f-7a18-43eb-9379-80131e44d633;login=ronni;mod=0;msg-id=resub;msg-param-cumulative-months=6;msg-param-streak-months=2;msg-param-should-share-streak=1;msg-param-sub-plan=Prime;msg-param-sub-plan-name=Prime;room-id=12345678;subscriber=1;system-msg=ronni\\shas\\ssubscribed\\sfor\\s6\\smonths!;tmi-sent-ts=1507246572675;turbo=1;user-id=87654321;user-type=staff :tmi.twitch.tv USERNOTICE #dallas :Great stream -- keep it up!";
+
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::USERNOTICE
+    );
+    assert_eq!(parsed.params, Some("Great stream -- keep it up!".to_string()));
+
+    let tags = parsed.usernotice_tags().unwrap();
+    assert_eq!(tags.system_msg, "ronni has subscribed for 6 months!");
+    assert_eq!(tags.room_id, 12345678);
+    assert_eq!(
+        tags.event,
+        crate::usernotice_tag::UserNoticeEvent::Resub {
+            cumulative_months: 6,
+            streak_months: Some(2),
+            sub_plan: "Prime".to_string(),
+            sub_plan_name: "Prime".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn usernotice_subgift_parse_test() {
+    let input = "@badge-info=;badges=staff/1,premium/1;color=#0000FF;display-name=TWW2;emotes=;id=e9176cd8-5e22-4684-ad40-ce53c2561c5e;login=tww2;mod=0;msg-id=subgift;msg-param-months=1;msg-param-recipient-display-name=Mr_Woodchuck;msg-param-recipient-id=55554444;msg-param-recipient-user-name=mr_woodchuck;msg-param-sub-plan-name=House\\sof\\sNyoro~n;msg-param-sub-plan=1000;room-id=19571752;subscriber=0;system-msg=TWW2\\sgifted\\sa\\sTier\\s1\\ssub\\sto\\sMr_Woodchuck!;tmi-sent-ts=1521159445153;turbo=0;user-id=87654321;user-type=staff :tmi.twitch.tv USERNOTICE #forstycup";
+
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    assert_eq!(parsed.params, None);
+
+    let tags = parsed.usernotice_tags().unwrap();
+    assert_eq!(
+        tags.event,
+        crate::usernotice_tag::UserNoticeEvent::SubGift {
+            months: 1,
+            gift_months: 1,
+            recipient_id: 55554444,
+            recipient_user_name: "mr_woodchuck".to_string(),
+            recipient_display_name: "Mr_Woodchuck".to_string(),
+            sub_plan: "1000".to_string(),
+            sub_plan_name: "House of Nyoro~n".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn usernotice_raid_parse_test() {
+    let input = "@badge-info=;badges=turbo/1;color=#9ACD32;display-name=TestChannel;emotes=;id=3d830f12-795c-447d-af3c-ea05e40fbddb;login=testchannel;mod=0;msg-id=raid;msg-param-displayName=TestChannel;msg-param-login=testchannel;msg-param-viewerCount=15;room-id=33332222;subscriber=0;system-msg=15\\sraiders\\sfrom\\sTestChannel\\shave\\sjoined\\n!;tmi-sent-ts=1507246572675;turbo=1;user-id=123456;user-type= :tmi.twitch.tv USERNOTICE #othertestchannel";
+
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    let tags = parsed.usernotice_tags().unwrap();
+
+    assert_eq!(tags.system_msg, "15 raiders from TestChannel have joined\n!");
+    assert_eq!(
+        tags.event,
+        crate::usernotice_tag::UserNoticeEvent::Raid {
+            display_name: "TestChannel".to_string(),
+            login: "testchannel".to_string(),
+            viewer_count: 15,
+        }
+    );
+}
+
+#[tokio::test]
+async fn usernotice_invalid_param_parse_test() {
+    let input = "@badge-info=;badges=turbo/1;color=#9ACD32;display-name=TestChannel;emotes=;id=3d830f12-795c-447d-af3c-ea05e40fbddb;login=testchannel;mod=0;msg-id=raid;msg-param-displayName=TestChannel;msg-param-login=testchannel;msg-param-viewerCount=many;room-id=33332222;subscriber=0;system-msg=15\\sraiders\\sfrom\\sTestChannel\\shave\\sjoined\\n!;tmi-sent-ts=1507246572675;turbo=1;user-id=123456;user-type= :tmi.twitch.tv USERNOTICE #othertestchannel";
+
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    let tags = parsed.usernotice_tags().unwrap();
+
+    assert_eq!(tags.room_id, 33332222);
+    assert_eq!(tags.user_id, 123456);
+    match tags.event {
+        crate::usernotice_tag::UserNoticeEvent::Unknown { msg_id, error } => {
+            assert_eq!(msg_id, "raid");
+            assert!(error.is_some());
+        }
+        x => panic!("Expected an unknown event, got {:?}", x),
+    }
+}
+
+#[tokio::test]
+async fn clearchat_parse_test() {
+    let input = "@room-id=12345678;target-user-id=87654321;tmi-sent-ts=1642715756806 :tmi.twitch.tv CLEARCHAT #dallas :ronni";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::CLEARCHAT
+    );
+    assert_eq!(parsed.params, Some("ronni".to_string()));
+
+    let tags = parsed.clearchat_tags().unwrap();
+    assert_eq!(tags.ban_duration, None);
+    assert_eq!(tags.room_id, 12345678);
+    assert_eq!(tags.target_user_id, Some(87654321));
+
+    let input = "@ban-duration=350;room-id=12345678;target-user-id=87654321;tmi-sent-ts=1642719320727 :tmi.twitch.tv CLEARCHAT #dallas :ronni";
+    let tags = crate::irc_parser::parse(input).await.unwrap().clearchat_tags().unwrap();
+    assert_eq!(tags.ban_duration, Some(350));
+
+    let input = "@room-id=12345678;tmi-sent-ts=1642715695392 :tmi.twitch.tv CLEARCHAT #dallas";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    assert_eq!(parsed.params, None);
+    assert_eq!(parsed.clearchat_tags().unwrap().target_user_id, None);
+}
+
+#[tokio::test]
+async fn roomstate_parse_test() {
+    let input = "@emote-only=0;followers-only=-1;r9k=0;room-id=12345678;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE #bar";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::ROOMSTATE
+    );
+
+    let tags = parsed.roomstate_tags().unwrap();
+    assert_eq!(tags.emote_only, Some(false));
+    assert_eq!(tags.followers_only, Some(-1));
+    assert_eq!(tags.room_id, 12345678);
+
+    let input = "@room-id=12345678;slow=10 :tmi.twitch.tv ROOMSTATE #bar";
+    let tags = crate::irc_parser::parse(input).await.unwrap().roomstate_tags().unwrap();
+    assert_eq!(tags.slow, Some(10));
+    assert_eq!(tags.emote_only, None);
+    assert_eq!(tags.subs_only, None);
+}
+
+#[tokio::test]
+async fn userstate_parse_test() {
+    let input = "@badge-info=;badges=moderator/1;color=;display-name=dustin;emote-sets=0,300374282;mod=1;subscriber=0;user-type=mod :tmi.twitch.tv USERSTATE #dallas";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::USERSTATE
+    );
+    assert_eq!(parsed.command.params, vec!["#dallas".to_string()]);
+
+    let tags = parsed.userstate_tags().unwrap();
+    assert!(tags.moderator);
+    assert!(!tags.vip);
+    assert_eq!(tags.emote_sets, vec!["0".to_string(), "300374282".to_string()]);
+
+    let input = "@badge-info=;badges=;color=#0D4200;display-name=dustin;emote-sets=0;turbo=0;user-id=12345678;user-type= :tmi.twitch.tv GLOBALUSERSTATE";
+    let parsed = crate::irc_parser::parse(input).await.unwrap();
+    assert_eq!(
+        parsed.command.command,
+        crate::irc_parser::IRCCommandType::GLOBALUSERSTATE
+    );
+
+    let tags = parsed.globaluserstate_tags().unwrap();
+    assert_eq!(tags.user_id, 12345678);
+    assert!(tags.badges.is_empty());
+}
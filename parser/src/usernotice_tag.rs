@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use anyhow::{Error, Result};
+use serde::Serialize;
+
+use database::entity::sea_orm_active_enums::UserType;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserNoticeEvent {
+    Sub {
+        cumulative_months: i32,
+        streak_months: Option<i32>,
+        sub_plan: String,
+        sub_plan_name: String,
+    },
+    Resub {
+        cumulative_months: i32,
+        streak_months: Option<i32>,
+        sub_plan: String,
+        sub_plan_name: String,
+    },
+    SubGift {
+        months: i32,
+        gift_months: i32,
+        recipient_id: i32,
+        recipient_user_name: String,
+        recipient_display_name: String,
+        sub_plan: String,
+        sub_plan_name: String,
+    },
+    SubMysteryGift {
+        mass_gift_count: i32,
+        sender_count: Option<i32>,
+        sub_plan: String,
+    },
+    GiftPaidUpgrade {
+        promo_gift_total: Option<i32>,
+        promo_name: Option<String>,
+        sender_login: String,
+        sender_name: String,
+    },
+    AnonGiftPaidUpgrade {
+        promo_gift_total: Option<i32>,
+        promo_name: Option<String>,
+    },
+    Raid {
+        display_name: String,
+        login: String,
+        viewer_count: i32,
+    },
+    Unraid,
+    Ritual {
+        ritual_name: String,
+    },
+    BitsBadgeTier {
+        threshold: i32,
+    },
+    Announcement {
+        color: Option<String>,
+    },
+    Unknown {
+        msg_id: String,
+        /** why the msg-param-* of a known msg-id could not be parsed */
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct UserNoticeTags {
+    pub badge_info: Option<String>,
+    pub badges: Vec<String>,
+    pub admin: bool,
+    pub color: String,
+    pub display_name: String,
+    pub emotes: Option<String>,
+    pub id: String,
+    pub login: String,
+    pub moderator: bool,
+    pub msg_id: String,
+    pub room_id: i32,
+    pub subscriber: bool,
+    pub system_msg: String,
+    pub tmi_sent_ts: String,
+    pub turbo: bool,
+    pub user_id: i32,
+    pub user_type: UserType,
+    pub vip: bool,
+    pub event: UserNoticeEvent,
+}
+
+/**
+ * Parse the tags from a USERNOTICE message
+ */
+pub fn parse(tags: &HashMap<String, String>) -> Result<UserNoticeTags, Error> {
+    let badge_info = tags.get("badge-info").map(|x| x.to_string());
+
+    let badges = match tags.get("badges") {
+        Some(x) => x.split(",").map(|x| x.to_string()).collect::<Vec<String>>(),
+        None => return Err(Error::msg("No badges")),
+    };
+
+    let admin = badges.iter().any(|x| x.contains("broadcaster/1"));
+
+    let color = match tags.get("color") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No color")),
+    };
+
+    let display_name = match tags.get("display-name") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No display name")),
+    };
+
+    let emotes = tags.get("emotes").map(|x| x.to_string());
+
+    let id = match tags.get("id") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No id")),
+    };
+
+    let login = match tags.get("login") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No login")),
+    };
+
+    let moderator = match tags.get("mod") {
+        Some(x) => x == "1",
+        None => return Err(Error::msg("No mod")),
+    };
+
+    let msg_id = match tags.get("msg-id") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No msg-id")),
+    };
+
+    let room_id = match tags.get("room-id") {
+        Some(x) => match x.parse::<i32>() {
+            Ok(x) => x,
+            Err(_) => return Err(Error::msg("Invalid room id")),
+        },
+        None => return Err(Error::msg("No room id")),
+    };
+
+    let subscriber = match tags.get("subscriber") {
+        Some(x) => x == "1",
+        None => return Err(Error::msg("No subscriber")),
+    };
+
+    let system_msg = match tags.get("system-msg") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No system-msg")),
+    };
+
+    let tmi_sent_ts = match tags.get("tmi-sent-ts") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No tmi-sent-ts")),
+    };
+
+    let turbo = match tags.get("turbo") {
+        Some(x) => x == "1",
+        None => false,
+    };
+
+    let user_type = match tags.get("user-type") {
+        Some(x) => match x.as_str() {
+            "global_mod" => UserType::Globalmod,
+            "admin" => UserType::Globaladmin,
+            "staff" => UserType::Staff,
+            _ => UserType::Normal,
+        },
+        None => UserType::Normal,
+    };
+
+    let user_id = match tags.get("user-id") {
+        Some(x) => match x.parse::<i32>() {
+            Ok(x) => x,
+            Err(_) => return Err(Error::msg("Invalid user id")),
+        },
+        None => return Err(Error::msg("No user id")),
+    };
+
+    let vip = tags.get("vip").is_some();
+
+    // a missing or malformed msg-param-* does not lose the notice
+    let event = match parse_event(&msg_id, tags) {
+        Ok(x) => x,
+        Err(e) => UserNoticeEvent::Unknown {
+            msg_id: msg_id.to_string(),
+            error: Some(format!("{:?}", e)),
+        },
+    };
+
+    return Ok(UserNoticeTags {
+        badge_info,
+        badges,
+        admin,
+        color,
+        display_name,
+        emotes,
+        id,
+        login,
+        moderator,
+        msg_id,
+        room_id,
+        subscriber,
+        system_msg,
+        tmi_sent_ts,
+        turbo,
+        user_id,
+        user_type,
+        vip,
+        event,
+    });
+}
+
+/**
+ * Parse the msg-param-* tags for the given msg-id
+ */
+fn parse_event(msg_id: &str, tags: &HashMap<String, String>) -> Result<UserNoticeEvent, Error> {
+    let event = match msg_id {
+        "sub" => UserNoticeEvent::Sub {
+            cumulative_months: param(tags, "cumulative-months")?,
+            streak_months: optional_streak(tags)?,
+            sub_plan: param(tags, "sub-plan")?,
+            sub_plan_name: param(tags, "sub-plan-name")?,
+        },
+        "resub" => UserNoticeEvent::Resub {
+            cumulative_months: param(tags, "cumulative-months")?,
+            streak_months: optional_streak(tags)?,
+            sub_plan: param(tags, "sub-plan")?,
+            sub_plan_name: param(tags, "sub-plan-name")?,
+        },
+        "subgift" | "anonsubgift" => UserNoticeEvent::SubGift {
+            months: param(tags, "months")?,
+            gift_months: optional_param(tags, "gift-months")?.unwrap_or(1),
+            recipient_id: param(tags, "recipient-id")?,
+            recipient_user_name: match optional_param(tags, "recipient-user-name")? {
+                Some(x) => x,
+                None => param(tags, "recipient-name")?,
+            },
+            recipient_display_name: param(tags, "recipient-display-name")?,
+            sub_plan: param(tags, "sub-plan")?,
+            sub_plan_name: param(tags, "sub-plan-name")?,
+        },
+        "submysterygift" | "anonsubmysterygift" => UserNoticeEvent::SubMysteryGift {
+            mass_gift_count: param(tags, "mass-gift-count")?,
+            sender_count: optional_param(tags, "sender-count")?,
+            sub_plan: param(tags, "sub-plan")?,
+        },
+        "giftpaidupgrade" => UserNoticeEvent::GiftPaidUpgrade {
+            promo_gift_total: optional_param(tags, "promo-gift-total")?,
+            promo_name: optional_param(tags, "promo-name")?,
+            sender_login: param(tags, "sender-login")?,
+            sender_name: param(tags, "sender-name")?,
+        },
+        "anongiftpaidupgrade" => UserNoticeEvent::AnonGiftPaidUpgrade {
+            promo_gift_total: optional_param(tags, "promo-gift-total")?,
+            promo_name: optional_param(tags, "promo-name")?,
+        },
+        "raid" => UserNoticeEvent::Raid {
+            display_name: param(tags, "displayName")?,
+            login: param(tags, "login")?,
+            viewer_count: param(tags, "viewerCount")?,
+        },
+        "unraid" => UserNoticeEvent::Unraid,
+        "ritual" => UserNoticeEvent::Ritual {
+            ritual_name: param(tags, "ritual-name")?,
+        },
+        "bitsbadgetier" => UserNoticeEvent::BitsBadgeTier {
+            threshold: param(tags, "threshold")?,
+        },
+        "announcement" => UserNoticeEvent::Announcement {
+            color: optional_param(tags, "color")?,
+        },
+        _ => UserNoticeEvent::Unknown {
+            msg_id: msg_id.to_string(),
+            error: None,
+        },
+    };
+
+    return Ok(event);
+}
+
+/**
+ * Get the streak months, twitch sends them only if the user shares the streak
+ */
+fn optional_streak(tags: &HashMap<String, String>) -> Result<Option<i32>, Error> {
+    let should_share = optional_param::<String>(tags, "should-share-streak")?;
+    if should_share.as_deref() != Some("1") {
+        return Ok(None);
+    }
+
+    return optional_param(tags, "streak-months");
+}
+
+/**
+ * Get a required msg-param-* tag
+ */
+fn param<T: FromStr>(tags: &HashMap<String, String>, name: &str) -> Result<T, Error> {
+    return match optional_param(tags, name)? {
+        Some(x) => Ok(x),
+        None => Err(Error::msg(format!("No msg-param-{}", name))),
+    };
+}
+
+/**
+ * Get an optional msg-param-* tag, empty values count as missing
+ */
+fn optional_param<T: FromStr>(tags: &HashMap<String, String>, name: &str) -> Result<Option<T>, Error> {
+    let value = match tags.get(&format!("msg-param-{}", name)) {
+        Some(x) if !x.is_empty() => x,
+        _ => return Ok(None),
+    };
+
+    return match value.parse::<T>() {
+        Ok(x) => Ok(Some(x)),
+        Err(_) => Err(Error::msg(format!("Invalid msg-param-{}", name))),
+    };
+}
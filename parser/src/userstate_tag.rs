@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use anyhow::{Error, Result};
+
+use database::entity::sea_orm_active_enums::UserType;
+
+#[derive(Debug, Clone)]
+pub struct UserStateTags {
+    pub badge_info: Option<String>,
+    pub badges: Vec<String>,
+    pub admin: bool,
+    pub color: String,
+    pub display_name: String,
+    pub emote_sets: Vec<String>,
+    pub id: Option<String>,
+    pub moderator: bool,
+    pub subscriber: bool,
+    pub turbo: bool,
+    pub user_type: UserType,
+    pub vip: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct GlobalUserStateTags {
+    pub badge_info: Option<String>,
+    pub badges: Vec<String>,
+    pub color: String,
+    pub display_name: String,
+    pub emote_sets: Vec<String>,
+    pub turbo: bool,
+    pub user_id: i32,
+    pub user_type: UserType,
+}
+
+/**
+ * Parse the tags from a USERSTATE message
+ */
+pub fn parse(tags: &HashMap<String, String>) -> Result<UserStateTags, Error> {
+    let badge_info = tags.get("badge-info").map(|x| x.to_string());
+    let badges = parse_badges(tags)?;
+    let admin = badges.iter().any(|x| x.contains("broadcaster/1"));
+    let vip = badges.iter().any(|x| x.starts_with("vip/"));
+
+    let color = match tags.get("color") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No color")),
+    };
+
+    let display_name = match tags.get("display-name") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No display name")),
+    };
+
+    let emote_sets = parse_emote_sets(tags);
+
+    let id = tags.get("id").map(|x| x.to_string());
+
+    let moderator = match tags.get("mod") {
+        Some(x) => x == "1",
+        None => return Err(Error::msg("No mod")),
+    };
+
+    let subscriber = match tags.get("subscriber") {
+        Some(x) => x == "1",
+        None => return Err(Error::msg("No subscriber")),
+    };
+
+    let turbo = match tags.get("turbo") {
+        Some(x) => x == "1",
+        None => false,
+    };
+
+    let user_type = parse_user_type(tags);
+
+    return Ok(UserStateTags {
+        badge_info,
+        badges,
+        admin,
+        color,
+        display_name,
+        emote_sets,
+        id,
+        moderator,
+        subscriber,
+        turbo,
+        user_type,
+        vip,
+    });
+}
+
+/**
+ * Parse the tags from a GLOBALUSERSTATE message
+ */
+pub fn parse_global(tags: &HashMap<String, String>) -> Result<GlobalUserStateTags, Error> {
+    let badge_info = tags.get("badge-info").map(|x| x.to_string());
+    let badges = parse_badges(tags)?;
+
+    let color = match tags.get("color") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No color")),
+    };
+
+    let display_name = match tags.get("display-name") {
+        Some(x) => x.to_string(),
+        None => return Err(Error::msg("No display name")),
+    };
+
+    let emote_sets = parse_emote_sets(tags);
+
+    let turbo = match tags.get("turbo") {
+        Some(x) => x == "1",
+        None => false,
+    };
+
+    let user_id = match tags.get("user-id") {
+        Some(x) => match x.parse::<i32>() {
+            Ok(x) => x,
+            Err(_) => return Err(Error::msg("Invalid user id")),
+        },
+        None => return Err(Error::msg("No user id")),
+    };
+
+    let user_type = parse_user_type(tags);
+
+    return Ok(GlobalUserStateTags {
+        badge_info,
+        badges,
+        color,
+        display_name,
+        emote_sets,
+        turbo,
+        user_id,
+        user_type,
+    });
+}
+
+fn parse_badges(tags: &HashMap<String, String>) -> Result<Vec<String>, Error> {
+    return match tags.get("badges") {
+        Some(x) if x.is_empty() => Ok(vec![]),
+        Some(x) => Ok(x.split(",").map(|x| x.to_string()).collect::<Vec<String>>()),
+        None => Err(Error::msg("No badges")),
+    };
+}
+
+fn parse_emote_sets(tags: &HashMap<String, String>) -> Vec<String> {
+    return match tags.get("emote-sets") {
+        Some(x) if !x.is_empty() => x.split(",").map(|x| x.to_string()).collect::<Vec<String>>(),
+        _ => vec![],
+    };
+}
+
+fn parse_user_type(tags: &HashMap<String, String>) -> UserType {
+    return match tags.get("user-type") {
+        Some(x) => match x.as_str() {
+            "global_mod" => UserType::Globalmod,
+            "admin" => UserType::Globaladmin,
+            "staff" => UserType::Staff,
+            _ => UserType::Normal,
+        },
+        None => UserType::Normal,
+    };
+}
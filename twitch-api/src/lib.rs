@@ -9,6 +9,14 @@ pub struct Chatter {
     pub user_name: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct Follower {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub followed_at: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct Pagination {
     cursor: Option<String>,
@@ -20,6 +28,11 @@ struct ChatterResponse {
     pagination: Option<Pagination>,
 }
 
+#[derive(Debug, Deserialize)]
+struct FollowerResponse {
+    data: Vec<Follower>,
+}
+
 #[async_recursion]
 pub async fn get_chatters(
     client_id: &str,
@@ -36,7 +49,7 @@ pub async fn get_chatters(
         channel_id, bot_id
     );
 
-    if after != "" {
+    if !after.is_empty() {
         url = format!("{}&after={}", url, after);
     }
 
@@ -68,3 +81,158 @@ pub async fn get_chatters(
 
     return Ok(());
 }
+
+/**
+ * Get when a user followed a channel, the token has to be of the broadcaster
+ * or a moderator of the channel
+ */
+pub async fn get_follower(
+    client_id: &str,
+    bot_token: &str,
+    channel_id: i32,
+    user_id: i32,
+) -> Result<Option<Follower>, Error> {
+    let client = reqwest::Client::new();
+
+    let url = format!(
+        "https://api.twitch.tv/helix/channels/followers?broadcaster_id={}&user_id={}",
+        channel_id, user_id
+    );
+
+    let res = client
+        .get(&url)
+        .header("Client-Id", client_id)
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<FollowerResponse>()
+        .await?;
+
+    return Ok(res.data.into_iter().next());
+}
+
+/**
+ * Send a whisper from the bot to a user, the bot needs the user:manage:whispers scope
+ */
+pub async fn send_whisper(
+    client_id: &str,
+    bot_token: &str,
+    bot_id: i32,
+    user_id: i32,
+    message: &str,
+) -> Result<(), Error> {
+    let client = reqwest::Client::new();
+
+    let url = format!(
+        "https://api.twitch.tv/helix/whispers?from_user_id={}&to_user_id={}",
+        bot_id, user_id
+    );
+
+    client
+        .post(&url)
+        .header("Client-Id", client_id)
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .json(&serde_json::json!({ "message": message }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    return Ok(());
+}
+
+/**
+ * Delete a chat message, the bot has to be a moderator of the channel with
+ * the moderator:manage:chat_messages scope
+ */
+pub async fn delete_chat_message(
+    client_id: &str,
+    bot_token: &str,
+    bot_id: i32,
+    channel_id: i32,
+    message_id: &str,
+) -> Result<(), Error> {
+    let client = reqwest::Client::new();
+
+    let url = format!(
+        "https://api.twitch.tv/helix/moderation/chat?broadcaster_id={}&moderator_id={}&message_id={}",
+        channel_id, bot_id, message_id
+    );
+
+    client
+        .delete(&url)
+        .header("Client-Id", client_id)
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    return Ok(());
+}
+
+/**
+ * Ban a user from the chat of a channel, with a duration in seconds the ban
+ * is a timeout, the bot has to be a moderator of the channel with the
+ * moderator:manage:banned_users scope
+ */
+pub async fn ban_user(
+    client_id: &str,
+    bot_token: &str,
+    bot_id: i32,
+    channel_id: i32,
+    user_id: i32,
+    duration: Option<i32>,
+    reason: &str,
+) -> Result<(), Error> {
+    let client = reqwest::Client::new();
+
+    let url = format!(
+        "https://api.twitch.tv/helix/moderation/bans?broadcaster_id={}&moderator_id={}",
+        channel_id, bot_id
+    );
+
+    let mut data = serde_json::json!({ "user_id": user_id.to_string(), "reason": reason });
+    if let Some(duration) = duration {
+        data["duration"] = serde_json::json!(duration);
+    }
+
+    client
+        .post(&url)
+        .header("Client-Id", client_id)
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .json(&serde_json::json!({ "data": data }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    return Ok(());
+}
+
+/**
+ * Lift the ban or timeout of a user in the chat of a channel, needs the same
+ * scope as `ban_user`
+ */
+pub async fn unban_user(
+    client_id: &str,
+    bot_token: &str,
+    bot_id: i32,
+    channel_id: i32,
+    user_id: i32,
+) -> Result<(), Error> {
+    let client = reqwest::Client::new();
+
+    let url = format!(
+        "https://api.twitch.tv/helix/moderation/bans?broadcaster_id={}&moderator_id={}&user_id={}",
+        channel_id, bot_id, user_id
+    );
+
+    client
+        .delete(&url)
+        .header("Client-Id", client_id)
+        .header("Authorization", format!("Bearer {}", bot_token))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    return Ok(());
+}
@@ -0,0 +1,133 @@
+use anyhow::{Error, Result};
+use cache::redis::aio::Connection;
+use database::entity::channel_chat_state as channel_chat_state_entity;
+use database::sea_orm::{ActiveValue, DatabaseConnection};
+use parser::roomstate_tag::RoomStateTags;
+use parser::userstate_tag::UserStateTags;
+use serde::{Deserialize, Serialize};
+
+/**
+ * The chat settings of a channel and the standing of the bot in it
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatState {
+    pub channel_id: i32,
+    pub emote_only: bool,
+    pub followers_only: Option<i32>,
+    pub r9k: bool,
+    pub slow: i32,
+    pub subs_only: bool,
+    pub bot_moderator: bool,
+    pub bot_vip: bool,
+}
+
+impl ChatState {
+    pub fn new(channel_id: i32) -> ChatState {
+        return ChatState {
+            channel_id,
+            ..Default::default()
+        };
+    }
+
+    /**
+     * Apply a full or partial ROOMSTATE update
+     */
+    pub fn apply_room_state(&mut self, tags: &RoomStateTags) {
+        if let Some(x) = tags.emote_only {
+            self.emote_only = x;
+        }
+        if let Some(x) = tags.followers_only {
+            self.followers_only = if x < 0 { None } else { Some(x) };
+        }
+        if let Some(x) = tags.r9k {
+            self.r9k = x;
+        }
+        if let Some(x) = tags.slow {
+            self.slow = x;
+        }
+        if let Some(x) = tags.subs_only {
+            self.subs_only = x;
+        }
+    }
+
+    /**
+     * Apply the USERSTATE the bot receives for itself
+     */
+    pub fn apply_user_state(&mut self, tags: &UserStateTags) {
+        self.bot_moderator = tags.moderator || tags.admin;
+        self.bot_vip = tags.vip;
+    }
+}
+
+impl From<channel_chat_state_entity::Model> for ChatState {
+    fn from(model: channel_chat_state_entity::Model) -> Self {
+        return ChatState {
+            channel_id: model.channel_id,
+            emote_only: model.emote_only != 0,
+            followers_only: model.followers_only,
+            r9k: model.r9k != 0,
+            slow: model.slow,
+            subs_only: model.subs_only != 0,
+            bot_moderator: model.bot_moderator != 0,
+            bot_vip: model.bot_vip != 0,
+        };
+    }
+}
+
+fn cache_key(channel_id: i32) -> String {
+    return format!("channel:{}:chat_state", channel_id);
+}
+
+/**
+ * Get the chat state of a channel from redis, falling back to the db
+ */
+pub async fn get_chat_state(
+    channel_id: i32,
+    db: &DatabaseConnection,
+    redis: &mut Connection,
+) -> Result<ChatState, Error> {
+    if let Some(state) = cache::get_as::<ChatState>(&cache_key(channel_id), redis).await? {
+        return Ok(state);
+    }
+
+    let state = match database::handler::chat_state::get_chat_state(channel_id, db).await? {
+        Some(x) => ChatState::from(x),
+        None => return Ok(ChatState::new(channel_id)),
+    };
+
+    cache::set(&cache_key(channel_id), &serde_json::to_string(&state)?, redis).await?;
+
+    return Ok(state);
+}
+
+/**
+ * Save the chat state of a channel to the db and redis
+ */
+pub async fn save_chat_state(
+    state: &ChatState,
+    db: &DatabaseConnection,
+    redis: &mut Connection,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().naive_utc();
+
+    database::handler::chat_state::save_chat_state(
+        channel_chat_state_entity::ActiveModel {
+            channel_id: ActiveValue::Set(state.channel_id),
+            emote_only: ActiveValue::Set(state.emote_only as i8),
+            followers_only: ActiveValue::Set(state.followers_only),
+            r9k: ActiveValue::Set(state.r9k as i8),
+            slow: ActiveValue::Set(state.slow),
+            subs_only: ActiveValue::Set(state.subs_only as i8),
+            bot_moderator: ActiveValue::Set(state.bot_moderator as i8),
+            bot_vip: ActiveValue::Set(state.bot_vip as i8),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+        },
+        db,
+    )
+    .await?;
+
+    cache::set(&cache_key(state.channel_id), &serde_json::to_string(state)?, redis).await?;
+
+    return Ok(());
+}
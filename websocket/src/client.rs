@@ -17,10 +17,10 @@ pub async fn connect(url: &str) -> Result<WebSocketStream<MaybeTlsStream<TcpStre
     Ok(ws)
 }
 
-pub async fn connect_channels(
-    db: &DatabaseConnection,
-    ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
-) -> Result<(), Error> {
+/**
+ * Get the nicks of all active channels
+ */
+pub async fn get_active_channel_names(db: &DatabaseConnection) -> Result<Vec<String>, Error> {
     let channels: Vec<channel_entity::Model> = match channel_entity::Entity::find()
         .filter(channel_entity::Column::Active.eq(1))
         .all(db)
@@ -38,17 +38,25 @@ pub async fn connect_channels(
     let users_vec: Vec<String> = users
         .into_iter()
         .filter_map(|x| {
-            if x.is_none() {
-                return None;
-            }
-
-            let user = x.unwrap();
+            let user = x?;
             let nick = user.nick;
 
             return Some(nick);
         })
         .collect::<Vec<String>>();
 
+    return Ok(users_vec);
+}
+
+/**
+ * Join all active channels
+ */
+pub async fn connect_channels(
+    db: &DatabaseConnection,
+    ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+) -> Result<(), Error> {
+    let users_vec = get_active_channel_names(db).await?;
+
     let join = join_channels_message(users_vec.iter().map(|x| x.as_str()).collect(), ws).await;
 
     match join {
@@ -0,0 +1,387 @@
+use anyhow::{Error, Result};
+use database::sea_orm::DatabaseConnection;
+use futures_util::StreamExt;
+use parser::irc_parser::IRCCommandType;
+use rand::Rng;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::client::connect;
+use crate::messages::{auth_message, send_message};
+use crate::rate_limit::{normalize_channel, OutboundMessage, OutboundQueue, SharedRateLimiter};
+
+/**
+ * A session that lived this long resets the backoff
+ */
+const STABLE_SESSION: Duration = Duration::from_secs(30);
+
+/**
+ * Settings for a managed connection to the twitch IRC server
+ */
+#[derive(Debug, Clone)]
+pub struct ConnectionConfig {
+    pub url: String,
+    pub bot_name: String,
+    pub nick: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redis_url: String,
+    pub ping_interval: Duration,
+    pub pong_timeout: Duration,
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl ConnectionConfig {
+    pub fn new(
+        url: &str,
+        bot_name: &str,
+        nick: &str,
+        client_id: &str,
+        client_secret: &str,
+        redis_url: &str,
+    ) -> ConnectionConfig {
+        return ConnectionConfig {
+            url: String::from(url),
+            bot_name: String::from(bot_name),
+            nick: String::from(nick),
+            client_id: String::from(client_id),
+            client_secret: String::from(client_secret),
+            redis_url: String::from(redis_url),
+            ping_interval: Duration::from_secs(60),
+            pong_timeout: Duration::from_secs(10),
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(120),
+        };
+    }
+}
+
+/**
+ * Events emitted by a managed connection
+ */
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    Connected,
+    Disconnected(String),
+    Reconnecting { attempt: u32, delay: Duration },
+    Message(String),
+}
+
+/**
+ * Handle to send messages over a managed connection, messages are rate limited
+ * and the ones sent while disconnected are delivered after the next reconnect
+ */
+#[derive(Debug, Clone)]
+pub struct Connection {
+    sender: mpsc::UnboundedSender<OutboundMessage>,
+}
+
+impl Connection {
+    pub fn send(&self, msg: OutboundMessage) -> Result<(), Error> {
+        if self.sender.send(msg).is_err() {
+            return Err(Error::msg("Connection closed"));
+        }
+
+        return Ok(());
+    }
+
+    /**
+     * Send a chat message to a channel
+     */
+    pub fn send_privmsg(&self, channel: &str, text: &str) -> Result<(), Error> {
+        return self.send(OutboundMessage::Privmsg {
+            channel: normalize_channel(channel),
+            text: String::from(text),
+            reply_to: None,
+        });
+    }
+
+    /**
+     * Send a chat message to a channel as a reply to another message
+     */
+    pub fn reply(&self, channel: &str, msg_id: &str, text: &str) -> Result<(), Error> {
+        return self.send(OutboundMessage::Privmsg {
+            channel: normalize_channel(channel),
+            text: String::from(text),
+            reply_to: Some(String::from(msg_id)),
+        });
+    }
+
+    pub fn join(&self, channels: Vec<&str>) -> Result<(), Error> {
+        return self.send(OutboundMessage::Join(
+            channels.iter().map(|x| normalize_channel(x)).collect(),
+        ));
+    }
+
+    pub fn part(&self, channels: Vec<&str>) -> Result<(), Error> {
+        return self.send(OutboundMessage::Part(
+            channels.iter().map(|x| normalize_channel(x)).collect(),
+        ));
+    }
+}
+
+/**
+ * Spawn a connection that authenticates, joins the given channels and
+ * reconnects with exponential backoff whenever the socket is lost, it shuts
+ * down once every handle is dropped
+ */
+pub fn spawn(
+    config: ConnectionConfig,
+    db: DatabaseConnection,
+    channels: Vec<String>,
+    limiter: SharedRateLimiter,
+) -> (Connection, mpsc::UnboundedReceiver<ConnectionEvent>) {
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    let (send_tx, send_rx) = mpsc::unbounded_channel();
+    let channels = channels.iter().map(|x| normalize_channel(x)).collect();
+
+    tokio::spawn(run(config, db, channels, limiter, send_rx, event_tx));
+
+    return (Connection { sender: send_tx }, event_rx);
+}
+
+enum SessionEnd {
+    Lost(String),
+    Closed,
+}
+
+async fn run(
+    config: ConnectionConfig,
+    db: DatabaseConnection,
+    mut channels: HashSet<String>,
+    limiter: SharedRateLimiter,
+    mut outbound: mpsc::UnboundedReceiver<OutboundMessage>,
+    events: mpsc::UnboundedSender<ConnectionEvent>,
+) {
+    let mut attempt: u32 = 0;
+    let mut queue = OutboundQueue::new(limiter);
+
+    loop {
+        if attempt > 0 {
+            let delay = backoff(attempt, &config);
+            if events
+                .send(ConnectionEvent::Reconnecting { attempt, delay })
+                .is_err()
+            {
+                return;
+            }
+            // a handle dropped during the backoff closes the connection, it
+            // must not reconnect and join its old channels again
+            if !wait_backoff(delay, &mut channels, &mut outbound, &mut queue).await {
+                let _ = events.send(ConnectionEvent::Disconnected(String::from("Connection closed")));
+                return;
+            }
+        }
+
+        let reason = match open(&config, &db).await {
+            Ok(mut ws) => {
+                if events.send(ConnectionEvent::Connected).is_err() {
+                    return;
+                }
+
+                let mut joined = channels.iter().cloned().collect::<Vec<String>>();
+                joined.sort();
+                queue.rejoin(joined);
+                let opened_at = Instant::now();
+                let end = session(&mut ws, &config, &mut channels, &mut outbound, &mut queue, &events).await;
+                let _ = ws.close(None).await;
+
+                // a session dropped right away, like for a refused login,
+                // keeps backing off
+                if opened_at.elapsed() >= STABLE_SESSION {
+                    attempt = 0;
+                }
+
+                match end {
+                    SessionEnd::Lost(reason) => reason,
+                    SessionEnd::Closed => {
+                        let _ = events.send(ConnectionEvent::Disconnected(String::from("Connection closed")));
+                        return;
+                    }
+                }
+            }
+            Err(e) => format!("{:?}", e),
+        };
+
+        if events.send(ConnectionEvent::Disconnected(reason)).is_err() {
+            return;
+        }
+
+        attempt = attempt.saturating_add(1);
+    }
+}
+
+/**
+ * Queue an outbound message and keep track of the joined channels so they
+ * are joined again after a reconnect
+ */
+fn accept(msg: OutboundMessage, channels: &mut HashSet<String>, queue: &mut OutboundQueue) {
+    match msg {
+        OutboundMessage::Join(x) => {
+            channels.extend(x.iter().cloned());
+            queue.push(OutboundMessage::Join(x));
+        }
+        OutboundMessage::Part(x) => {
+            for channel in x.iter() {
+                channels.remove(channel);
+            }
+            queue.push(OutboundMessage::Part(x));
+        }
+        msg => queue.push(msg),
+    }
+}
+
+/**
+ * Sleep before a reconnect while still taking outbound messages, returns
+ * false once every handle is dropped
+ */
+async fn wait_backoff(
+    delay: Duration,
+    channels: &mut HashSet<String>,
+    outbound: &mut mpsc::UnboundedReceiver<OutboundMessage>,
+    queue: &mut OutboundQueue,
+) -> bool {
+    let deadline = Instant::now() + delay;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return true,
+            msg = outbound.recv() => match msg {
+                Some(msg) => accept(msg, channels, queue),
+                None => return false,
+            },
+        }
+    }
+}
+
+/**
+ * Open the socket and authenticate with a fresh token
+ */
+async fn open(
+    config: &ConnectionConfig,
+    db: &DatabaseConnection,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, Error> {
+    let mut redis = cache::connect(&config.redis_url).await?;
+    let token = auth::token::get_bot_token(
+        &config.bot_name,
+        &config.client_id,
+        &config.client_secret,
+        db,
+        &mut redis,
+    )
+    .await?;
+
+    let mut ws = connect(&config.url).await?;
+    auth_message(&token, &config.nick, &mut ws).await?;
+
+    return Ok(ws);
+}
+
+/**
+ * Pump a connected socket until it is lost or every handle is dropped
+ */
+async fn session(
+    ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    config: &ConnectionConfig,
+    channels: &mut HashSet<String>,
+    outbound: &mut mpsc::UnboundedReceiver<OutboundMessage>,
+    queue: &mut OutboundQueue,
+    events: &mpsc::UnboundedSender<ConnectionEvent>,
+) -> SessionEnd {
+    let mut ping = tokio::time::interval_at(Instant::now() + config.ping_interval, config.ping_interval);
+    let mut pong_deadline: Option<Instant> = None;
+
+    loop {
+        for line in queue.drain_ready(Instant::now()) {
+            if let Err(e) = send_message(&line, ws).await {
+                return SessionEnd::Lost(format!("{:?}", e));
+            }
+        }
+        let next_ready = queue.next_ready(Instant::now());
+
+        tokio::select! {
+            msg = ws.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(x))) => x,
+                    Some(Ok(Message::Close(_))) => return SessionEnd::Lost(String::from("Socket closed by server")),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return SessionEnd::Lost(format!("{:?}", e)),
+                    None => return SessionEnd::Lost(String::from("Socket closed")),
+                };
+
+                for line in text.split("\r\n").filter(|x| !x.is_empty()) {
+                    let parsed = match parser::irc_parser::parse(line).await {
+                        Ok(x) => x,
+                        Err(_) => {
+                            let _ = events.send(ConnectionEvent::Message(String::from(line)));
+                            continue;
+                        }
+                    };
+
+                    match parsed.command.command {
+                        IRCCommandType::PING => {
+                            if let Err(e) = send_message("PONG :tmi.twitch.tv", ws).await {
+                                return SessionEnd::Lost(format!("{:?}", e));
+                            }
+                        }
+                        IRCCommandType::PONG => pong_deadline = None,
+                        IRCCommandType::RECONNECT => {
+                            return SessionEnd::Lost(String::from("Server requested a reconnect"));
+                        }
+                        IRCCommandType::USERSTATE => {
+                            if let (Some(channel), Some(tags)) = (parsed.command.params.first(), parsed.userstate_tags()) {
+                                queue
+                                    .limiter
+                                    .lock()
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .set_moderator(channel, tags.moderator || tags.admin);
+                            }
+                            if events.send(ConnectionEvent::Message(String::from(line))).is_err() {
+                                return SessionEnd::Lost(String::from("Event receiver dropped"));
+                            }
+                        }
+                        _ => {
+                            if events.send(ConnectionEvent::Message(String::from(line))).is_err() {
+                                return SessionEnd::Lost(String::from("Event receiver dropped"));
+                            }
+                        }
+                    }
+                }
+            }
+            msg = outbound.recv() => {
+                match msg {
+                    Some(msg) => accept(msg, channels, queue),
+                    None => return SessionEnd::Closed,
+                }
+            }
+            _ = tokio::time::sleep_until(next_ready.unwrap_or_else(Instant::now)), if next_ready.is_some() => (),
+            _ = ping.tick(), if pong_deadline.is_none() => {
+                if let Err(e) = send_message("PING :tmi.twitch.tv", ws).await {
+                    return SessionEnd::Lost(format!("{:?}", e));
+                }
+                pong_deadline = Some(Instant::now() + config.pong_timeout);
+            }
+            _ = tokio::time::sleep_until(pong_deadline.unwrap_or_else(Instant::now)), if pong_deadline.is_some() => {
+                return SessionEnd::Lost(String::from("No PONG received"));
+            }
+        }
+    }
+}
+
+/**
+ * Exponential backoff with up to 50% random jitter
+ */
+fn backoff(attempt: u32, config: &ConnectionConfig) -> Duration {
+    let exp = config
+        .min_backoff
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    let delay = exp.min(config.max_backoff);
+    let jitter = rand::thread_rng().gen_range(0.0..0.5);
+
+    return delay + delay.mul_f64(jitter);
+}
@@ -0,0 +1,106 @@
+use anyhow::{Error, Result};
+use database::handler::channel::{ChannelEvent, CHANNEL_EVENTS};
+use database::sea_orm::DatabaseConnection;
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::client::get_active_channel_names;
+use crate::pool::{move_channels, ConnectionPool};
+
+/**
+ * Keep the pool in the active channels, channel events published by the
+ * database handlers are applied right away and the active channels are
+ * reconciled every interval in case an event was missed
+ */
+pub fn spawn(
+    pool: Arc<Mutex<ConnectionPool>>,
+    redis_url: &str,
+    db: DatabaseConnection,
+    interval: Duration,
+) -> JoinHandle<()> {
+    let redis_url = String::from(redis_url);
+
+    return tokio::spawn(async move {
+        let mut reconcile_interval = tokio::time::interval(interval);
+
+        loop {
+            let mut pubsub = match cache::subscribe(&redis_url, CHANNEL_EVENTS).await {
+                Ok(x) => x,
+                Err(e) => {
+                    println!("Failed to subscribe to channel events: {:?}", e);
+                    reconcile_interval.tick().await;
+                    if let Err(e) = reconcile(&pool, &db).await {
+                        println!("Failed to reconcile channels: {:?}", e);
+                    }
+                    continue;
+                }
+            };
+
+            // events may have been missed while not subscribed
+            reconcile_interval.reset();
+            if let Err(e) = reconcile(&pool, &db).await {
+                println!("Failed to reconcile channels: {:?}", e);
+            }
+
+            let mut messages = pubsub.on_message();
+            loop {
+                tokio::select! {
+                    msg = messages.next() => {
+                        let msg = match msg {
+                            Some(x) => x,
+                            None => break,
+                        };
+
+                        let event = msg
+                            .get_payload::<String>()
+                            .map_err(Error::new)
+                            .and_then(|x| serde_json::from_str::<ChannelEvent>(&x).map_err(Error::new));
+                        let event = match event {
+                            Ok(x) => x,
+                            Err(e) => {
+                                println!("Invalid channel event: {:?}", e);
+                                continue;
+                            }
+                        };
+
+                        if let Err(e) = apply_channel_event(&pool, &event).await {
+                            println!("Failed to apply channel event {:?}: {:?}", event, e);
+                        }
+                    }
+                    _ = reconcile_interval.tick() => {
+                        if let Err(e) = reconcile(&pool, &db).await {
+                            println!("Failed to reconcile channels: {:?}", e);
+                        }
+                    }
+                }
+            }
+
+            println!("Channel events subscription lost, resubscribing");
+        }
+    });
+}
+
+/**
+ * Join or leave the channel of the event
+ */
+pub async fn apply_channel_event(pool: &Mutex<ConnectionPool>, event: &ChannelEvent) -> Result<(), Error> {
+    let closing = match event {
+        ChannelEvent::Join { name, .. } => return pool.lock().await.join(name),
+        ChannelEvent::Part { name, .. } => pool.lock().await.part(name)?,
+    };
+
+    return move_channels(pool, closing).await;
+}
+
+/**
+ * Make the pool join exactly the active channels
+ */
+async fn reconcile(pool: &Arc<Mutex<ConnectionPool>>, db: &DatabaseConnection) -> Result<(), Error> {
+    let channels = get_active_channel_names(db).await?;
+    let closing = pool.lock().await.set_channels(channels)?;
+
+    return move_channels(pool, closing).await;
+}
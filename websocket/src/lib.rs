@@ -1,5 +1,13 @@
+pub mod chat_state;
 pub mod client;
+pub mod connection;
+pub mod control;
 pub mod messages;
+pub mod pool;
+pub mod rate_limit;
 
 pub extern crate futures_util;
 pub extern crate tokio_tungstenite;
+
+#[cfg(test)]
+mod test;
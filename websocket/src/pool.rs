@@ -0,0 +1,334 @@
+use anyhow::{Error, Result};
+use database::sea_orm::DatabaseConnection;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::connection::{spawn, Connection, ConnectionConfig, ConnectionEvent};
+use crate::rate_limit::{normalize_channel, RateLimiter, SharedRateLimiter};
+
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/**
+ * An event from one of the pooled connections
+ */
+#[derive(Debug, Clone)]
+pub struct PoolEvent {
+    pub connection: usize,
+    pub event: ConnectionEvent,
+}
+
+#[derive(Debug)]
+struct PoolConnection {
+    id: usize,
+    connection: Connection,
+    channels: HashSet<String>,
+    closed: oneshot::Receiver<()>,
+}
+
+/**
+ * A connection taken out of the pool, its channels are joined again on the
+ * remaining connections once it closed so their messages are never
+ * delivered twice
+ */
+#[derive(Debug)]
+pub struct ClosingConnection {
+    id: usize,
+    channels: Vec<String>,
+    closed: oneshot::Receiver<()>,
+}
+
+impl ClosingConnection {
+    /**
+     * Wait until the connection closed and get the channels it left
+     */
+    pub async fn wait(self) -> Vec<String> {
+        if tokio::time::timeout(CLOSE_TIMEOUT, self.closed).await.is_err() {
+            println!("Connection {} did not close in time", self.id);
+        }
+
+        return self.channels;
+    }
+}
+
+/**
+ * Wait for the connections taken out of a pool to close without holding the
+ * pool and join their channels again, so messages can be sent meanwhile
+ */
+pub async fn move_channels(
+    pool: &Mutex<ConnectionPool>,
+    closing: Vec<ClosingConnection>,
+) -> Result<(), Error> {
+    for connection in closing {
+        let channels = connection.wait().await;
+
+        let mut pool = pool.lock().await;
+        for channel in channels {
+            // a channel left meanwhile stays left
+            if pool.moving.remove(&channel) {
+                pool.join(&channel)?;
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+/**
+ * Spreads the joined channels across as few connections as the per connection
+ * cap allows, every connection shares the rate limits of the account and the
+ * inbound messages of all of them are merged into one event stream
+ */
+#[derive(Debug)]
+pub struct ConnectionPool {
+    config: ConnectionConfig,
+    db: DatabaseConnection,
+    limiter: SharedRateLimiter,
+    channels_per_connection: usize,
+    connections: Vec<PoolConnection>,
+    moving: HashSet<String>,
+    next_id: usize,
+    events: mpsc::UnboundedSender<PoolEvent>,
+}
+
+impl ConnectionPool {
+    /**
+     * Create a pool joined to the given channels, the pool always keeps at
+     * least one connection open so messages can be sent
+     */
+    pub fn new(
+        config: ConnectionConfig,
+        db: DatabaseConnection,
+        channels: Vec<String>,
+        channels_per_connection: usize,
+    ) -> (ConnectionPool, mpsc::UnboundedReceiver<PoolEvent>) {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+        let mut pool = ConnectionPool {
+            config,
+            db,
+            limiter: RateLimiter::default().shared(),
+            channels_per_connection: channels_per_connection.max(1),
+            connections: vec![],
+            moving: HashSet::new(),
+            next_id: 0,
+            events: event_tx,
+        };
+
+        let mut channels = channels.iter().map(|x| normalize_channel(x)).collect::<Vec<String>>();
+        channels.sort();
+        channels.dedup();
+
+        for chunk in channels.chunks(pool.channels_per_connection) {
+            pool.open(chunk.to_vec());
+        }
+        if pool.connections.is_empty() {
+            pool.open(vec![]);
+        }
+
+        return (pool, event_rx);
+    }
+
+    /**
+     * All joined channels, including the ones waiting to move to another
+     * connection
+     */
+    pub fn channels(&self) -> Vec<String> {
+        let mut channels = self
+            .connections
+            .iter()
+            .flat_map(|x| x.channels.iter().cloned())
+            .chain(self.moving.iter().cloned())
+            .collect::<Vec<String>>();
+        channels.sort();
+
+        return channels;
+    }
+
+    /**
+     * Number of channels joined on each connection
+     */
+    pub fn loads(&self) -> Vec<(usize, usize)> {
+        return self.connections.iter().map(|x| (x.id, x.channels.len())).collect();
+    }
+
+    /**
+     * Join a channel on the least loaded connection with room left, a new
+     * connection is opened when every connection is full
+     */
+    pub fn join(&mut self, channel: &str) -> Result<(), Error> {
+        let channel = normalize_channel(channel);
+        if self.owner(&channel).is_some() || self.moving.contains(&channel) {
+            return Ok(());
+        }
+
+        let cap = self.channels_per_connection;
+        let target = self
+            .connections
+            .iter_mut()
+            .filter(|x| x.channels.len() < cap)
+            .min_by_key(|x| x.channels.len());
+
+        match target {
+            Some(x) => {
+                x.connection.join(vec![&channel])?;
+                x.channels.insert(channel);
+            }
+            None => self.open(vec![channel]),
+        }
+
+        return Ok(());
+    }
+
+    /**
+     * Leave a channel, returns the connections taken out because the rest fit
+     * into fewer, see `rebalance`
+     */
+    pub fn part(&mut self, channel: &str) -> Result<Vec<ClosingConnection>, Error> {
+        let channel = normalize_channel(channel);
+        let index = match self.owner(&channel) {
+            Some(x) => x,
+            None => {
+                self.moving.remove(&channel);
+                return Ok(vec![]);
+            }
+        };
+
+        let pooled = &mut self.connections[index];
+        pooled.connection.part(vec![&channel])?;
+        pooled.channels.remove(&channel);
+
+        return Ok(self.rebalance());
+    }
+
+    /**
+     * Join and leave channels until exactly the given channels are joined,
+     * returns the connections taken out like `part`
+     */
+    pub fn set_channels(&mut self, channels: Vec<String>) -> Result<Vec<ClosingConnection>, Error> {
+        let wanted = channels.iter().map(|x| normalize_channel(x)).collect::<HashSet<String>>();
+        let joined = self.channels().into_iter().collect::<HashSet<String>>();
+
+        let mut closing: Vec<ClosingConnection> = Vec::new();
+        for channel in joined.difference(&wanted) {
+            closing.extend(self.part(channel)?);
+        }
+
+        let mut new_channels = wanted.difference(&joined).collect::<Vec<&String>>();
+        new_channels.sort();
+        for channel in new_channels {
+            self.join(channel)?;
+        }
+
+        return Ok(closing);
+    }
+
+    /**
+     * Take the least loaded connections out while the channels fit into
+     * fewer connections, they leave their channels and close once dropped.
+     * The channels are joined again by `move_channels` after the connections
+     * closed, which is waited for without the pool
+     */
+    pub fn rebalance(&mut self) -> Vec<ClosingConnection> {
+        let joined: usize = self.connections.iter().map(|x| x.channels.len()).sum();
+        let total = joined + self.moving.len();
+        let needed = total.div_ceil(self.channels_per_connection).max(1);
+
+        let mut closing: Vec<ClosingConnection> = Vec::new();
+        while self.connections.len() > needed {
+            let index = match self
+                .connections
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, x)| x.channels.len())
+            {
+                Some((x, _)) => x,
+                None => break,
+            };
+
+            // dropping the last handle closes the connection and leaves its channels
+            let PoolConnection {
+                id,
+                connection,
+                channels,
+                closed,
+            } = self.connections.remove(index);
+            let mut channels = channels.into_iter().collect::<Vec<String>>();
+            channels.sort();
+            if !channels.is_empty() {
+                let _ = connection.part(channels.iter().map(|x| x.as_str()).collect());
+            }
+            drop(connection);
+
+            self.moving.extend(channels.iter().cloned());
+            closing.push(ClosingConnection {
+                id,
+                channels,
+                closed,
+            });
+        }
+
+        return closing;
+    }
+
+    /**
+     * Send a chat message on the connection that joined the channel
+     */
+    pub fn send_privmsg(&self, channel: &str, text: &str) -> Result<(), Error> {
+        return self.connection_for(channel)?.send_privmsg(channel, text);
+    }
+
+    /**
+     * Reply to a message on the connection that joined the channel
+     */
+    pub fn reply(&self, channel: &str, msg_id: &str, text: &str) -> Result<(), Error> {
+        return self.connection_for(channel)?.reply(channel, msg_id, text);
+    }
+
+    fn connection_for(&self, channel: &str) -> Result<&Connection, Error> {
+        let index = self.owner(&normalize_channel(channel)).unwrap_or(0);
+
+        return match self.connections.get(index) {
+            Some(x) => Ok(&x.connection),
+            None => Err(Error::msg("No connection open")),
+        };
+    }
+
+    fn owner(&self, channel: &str) -> Option<usize> {
+        return self.connections.iter().position(|x| x.channels.contains(channel));
+    }
+
+    /**
+     * Spawn a connection for the channels and forward its events to the pool stream
+     */
+    fn open(&mut self, channels: Vec<String>) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (connection, mut events) = spawn(
+            self.config.clone(),
+            self.db.clone(),
+            channels.clone(),
+            self.limiter.clone(),
+        );
+
+        let sender = self.events.clone();
+        let (closed_tx, closed_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                if sender.send(PoolEvent { connection: id, event }).is_err() {
+                    break;
+                }
+            }
+            // the events end after the connection sent its Disconnected
+            let _ = closed_tx.send(());
+        });
+
+        self.connections.push(PoolConnection {
+            id,
+            connection,
+            channels: channels.into_iter().collect(),
+            closed: closed_rx,
+        });
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/**
+ * A message waiting to be sent to the twitch IRC server
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutboundMessage {
+    Privmsg {
+        channel: String,
+        text: String,
+        reply_to: Option<String>,
+    },
+    Join(Vec<String>),
+    Part(Vec<String>),
+}
+
+impl OutboundMessage {
+    /**
+     * Format the message as a single IRC line, line breaks in the text are
+     * replaced so a message can never smuggle in another command
+     */
+    fn to_line(&self) -> String {
+        return match self {
+            OutboundMessage::Privmsg {
+                channel,
+                text,
+                reply_to: Some(reply_to),
+            } => format!(
+                "@reply-parent-msg-id={} PRIVMSG #{} :{}",
+                reply_to,
+                channel,
+                single_line(text)
+            ),
+            OutboundMessage::Privmsg { channel, text, .. } => {
+                format!("PRIVMSG #{} :{}", channel, single_line(text))
+            }
+            OutboundMessage::Join(channels) => format!("JOIN {}", channel_list(channels)),
+            OutboundMessage::Part(channels) => format!("PART {}", channel_list(channels)),
+        };
+    }
+}
+
+fn single_line(text: &str) -> String {
+    return text.replace(['\r', '\n'], " ");
+}
+
+fn channel_list(channels: &[String]) -> String {
+    return channels
+        .iter()
+        .map(|x| format!("#{}", x))
+        .collect::<Vec<String>>()
+        .join(",");
+}
+
+/**
+ * Normalize a channel name to the lowercase login without the leading '#'
+ */
+pub fn normalize_channel(channel: &str) -> String {
+    return channel.trim_start_matches('#').to_lowercase();
+}
+
+/**
+ * Sliding window counter, remembers when each message in the window was sent
+ */
+#[derive(Debug, Clone)]
+struct Window {
+    limit: usize,
+    period: Duration,
+    sent: VecDeque<Instant>,
+}
+
+impl Window {
+    fn new(limit: usize, period: Duration) -> Window {
+        return Window {
+            limit,
+            period,
+            sent: VecDeque::new(),
+        };
+    }
+
+    fn available(&mut self, now: Instant) -> usize {
+        while let Some(first) = self.sent.front() {
+            if *first + self.period > now {
+                break;
+            }
+            self.sent.pop_front();
+        }
+
+        return self.limit.saturating_sub(self.sent.len());
+    }
+
+    fn record(&mut self, now: Instant, count: usize) {
+        for _ in 0..count {
+            self.sent.push_back(now);
+        }
+    }
+
+    fn ready_at(&self) -> Option<Instant> {
+        return self.sent.front().map(|x| *x + self.period);
+    }
+}
+
+/**
+ * The documented twitch IRC limits, PRIVMSG is limited per account and the
+ * higher limit applies to channels where the bot is moderator or broadcaster
+ */
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    normal: Window,
+    moderator: Window,
+    join: Window,
+    moderator_channels: HashSet<String>,
+}
+
+/**
+ * Limiter shared by every connection of the same account
+ */
+pub type SharedRateLimiter = Arc<Mutex<RateLimiter>>;
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        return RateLimiter::new(20, 100, 20);
+    }
+}
+
+impl RateLimiter {
+    /**
+     * Create a limiter with the given PRIVMSG limits per 30 seconds and JOIN limit per 10 seconds
+     */
+    pub fn new(normal: usize, moderator: usize, join: usize) -> RateLimiter {
+        return RateLimiter {
+            normal: Window::new(normal, Duration::from_secs(30)),
+            moderator: Window::new(moderator, Duration::from_secs(30)),
+            join: Window::new(join, Duration::from_secs(10)),
+            moderator_channels: HashSet::new(),
+        };
+    }
+
+    pub fn shared(self) -> SharedRateLimiter {
+        return Arc::new(Mutex::new(self));
+    }
+
+    pub fn set_moderator(&mut self, channel: &str, moderator: bool) {
+        let channel = normalize_channel(channel);
+        if moderator {
+            self.moderator_channels.insert(channel);
+        } else {
+            self.moderator_channels.remove(&channel);
+        }
+    }
+
+    pub fn is_moderator(&self, channel: &str) -> bool {
+        return self.moderator_channels.contains(&normalize_channel(channel));
+    }
+
+    /**
+     * Take a PRIVMSG slot for the channel, every message counts against the
+     * moderator limit and messages to other channels also against the normal one
+     */
+    fn try_privmsg(&mut self, channel: &str, now: Instant) -> bool {
+        let moderator = self.is_moderator(channel);
+
+        if self.moderator.available(now) == 0 {
+            return false;
+        }
+        if !moderator && self.normal.available(now) == 0 {
+            return false;
+        }
+
+        self.moderator.record(now, 1);
+        if !moderator {
+            self.normal.record(now, 1);
+        }
+
+        return true;
+    }
+
+    /**
+     * Take up to `wanted` JOIN slots, returns how many were granted
+     */
+    fn try_join(&mut self, wanted: usize, now: Instant) -> usize {
+        let granted = wanted.min(self.join.available(now));
+        self.join.record(now, granted);
+
+        return granted;
+    }
+}
+
+/**
+ * Queue of outbound messages released as the rate limits allow
+ */
+#[derive(Debug, Clone, Default)]
+pub struct OutboundQueue {
+    pub limiter: SharedRateLimiter,
+    pending: VecDeque<OutboundMessage>,
+}
+
+impl OutboundQueue {
+    pub fn new(limiter: SharedRateLimiter) -> OutboundQueue {
+        return OutboundQueue {
+            limiter,
+            pending: VecDeque::new(),
+        };
+    }
+
+    pub fn push(&mut self, msg: OutboundMessage) {
+        self.pending.push_back(msg);
+    }
+
+    pub fn push_front(&mut self, msg: OutboundMessage) {
+        self.pending.push_front(msg);
+    }
+
+    /**
+     * Join the channels first thing after a reconnect, the joins and parts
+     * still queued are dropped as the channels already reflect them
+     */
+    pub fn rejoin(&mut self, channels: Vec<String>) {
+        self.pending
+            .retain(|x| !matches!(x, OutboundMessage::Join(_) | OutboundMessage::Part(_)));
+        if !channels.is_empty() {
+            self.pending.push_front(OutboundMessage::Join(channels));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.pending.is_empty();
+    }
+
+    /**
+     * Take every message that may be sent right now as IRC lines, a blocked
+     * message only holds back later messages that need the same limit
+     */
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut waiting: VecDeque<OutboundMessage> = VecDeque::new();
+        let mut privmsg_blocked: HashSet<String> = HashSet::new();
+        let mut join_blocked = false;
+        let mut limiter = self.limiter.lock().unwrap_or_else(|e| e.into_inner());
+
+        while let Some(msg) = self.pending.pop_front() {
+            match msg {
+                OutboundMessage::Privmsg { ref channel, .. } => {
+                    if !privmsg_blocked.contains(channel) && limiter.try_privmsg(channel, now) {
+                        lines.push(msg.to_line());
+                    } else {
+                        privmsg_blocked.insert(channel.to_string());
+                        waiting.push_back(msg);
+                    }
+                }
+                OutboundMessage::Join(channels) => {
+                    let granted = match join_blocked {
+                        true => 0,
+                        false => limiter.try_join(channels.len(), now),
+                    };
+                    let (now_channels, later_channels) = channels.split_at(granted);
+
+                    if !now_channels.is_empty() {
+                        lines.push(OutboundMessage::Join(now_channels.to_vec()).to_line());
+                    }
+                    if !later_channels.is_empty() {
+                        join_blocked = true;
+                        waiting.push_back(OutboundMessage::Join(later_channels.to_vec()));
+                    }
+                }
+                OutboundMessage::Part(_) if join_blocked => waiting.push_back(msg),
+                OutboundMessage::Part(_) => lines.push(msg.to_line()),
+            }
+        }
+
+        drop(limiter);
+        self.pending = waiting;
+
+        return lines;
+    }
+
+    /**
+     * When the next blocked message might be released
+     */
+    pub fn next_ready(&mut self, now: Instant) -> Option<Instant> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        let mut limiter = self.limiter.lock().unwrap_or_else(|e| e.into_inner());
+        limiter.normal.available(now);
+        limiter.moderator.available(now);
+        limiter.join.available(now);
+
+        return [
+            limiter.normal.ready_at(),
+            limiter.moderator.ready_at(),
+            limiter.join.ready_at(),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+    }
+}
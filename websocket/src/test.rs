@@ -0,0 +1,159 @@
+use crate::connection::ConnectionConfig;
+use crate::control::apply_channel_event;
+use crate::pool::{move_channels, ConnectionPool};
+use crate::rate_limit::{OutboundMessage, OutboundQueue, RateLimiter};
+use database::handler::channel::ChannelEvent;
+use database::sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+fn privmsg(channel: &str, text: &str) -> OutboundMessage {
+    return OutboundMessage::Privmsg {
+        channel: channel.to_string(),
+        text: text.to_string(),
+        reply_to: None,
+    };
+}
+
+#[test]
+fn privmsg_normal_limit_test() {
+    let mut queue = OutboundQueue::new(RateLimiter::new(2, 5, 20).shared());
+    let now = Instant::now();
+
+    queue.push(privmsg("foo", "a"));
+    queue.push(privmsg("foo", "b"));
+    queue.push(privmsg("foo", "c"));
+
+    assert_eq!(
+        queue.drain_ready(now),
+        vec!["PRIVMSG #foo :a".to_string(), "PRIVMSG #foo :b".to_string()]
+    );
+    assert_eq!(queue.next_ready(now), Some(now + Duration::from_secs(30)));
+    assert!(queue.drain_ready(now + Duration::from_secs(29)).is_empty());
+    assert_eq!(
+        queue.drain_ready(now + Duration::from_secs(30)),
+        vec!["PRIVMSG #foo :c".to_string()]
+    );
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn privmsg_moderator_limit_test() {
+    let mut queue = OutboundQueue::new(RateLimiter::new(1, 3, 20).shared());
+    queue.limiter.lock().unwrap().set_moderator("#Modded", true);
+    let now = Instant::now();
+
+    queue.push(privmsg("foo", "a"));
+    queue.push(privmsg("foo", "b"));
+    queue.push(privmsg("modded", "c"));
+    queue.push(privmsg("modded", "d\r\nJOIN #evil"));
+    queue.push(privmsg("modded", "e"));
+
+    assert_eq!(
+        queue.drain_ready(now),
+        vec![
+            "PRIVMSG #foo :a".to_string(),
+            "PRIVMSG #modded :c".to_string(),
+            "PRIVMSG #modded :d  JOIN #evil".to_string(),
+        ]
+    );
+    assert!(!queue.is_empty());
+}
+
+#[test]
+fn join_limit_test() {
+    let mut queue = OutboundQueue::new(RateLimiter::new(20, 100, 2).shared());
+    let now = Instant::now();
+
+    queue.push(OutboundMessage::Join(vec![
+        "a".to_string(),
+        "b".to_string(),
+        "c".to_string(),
+    ]));
+    queue.push(OutboundMessage::Part(vec!["c".to_string()]));
+    queue.push(privmsg("a", "hi"));
+
+    assert_eq!(
+        queue.drain_ready(now),
+        vec!["JOIN #a,#b".to_string(), "PRIVMSG #a :hi".to_string()]
+    );
+    assert_eq!(
+        queue.drain_ready(now + Duration::from_secs(10)),
+        vec!["JOIN #c".to_string(), "PART #c".to_string()]
+    );
+}
+
+#[test]
+fn rejoin_test() {
+    let mut queue = OutboundQueue::new(RateLimiter::new(20, 100, 20).shared());
+    let now = Instant::now();
+
+    // queued while disconnected, the channels already include them
+    queue.push(OutboundMessage::Join(vec!["b".to_string()]));
+    queue.push(privmsg("a", "hi"));
+    queue.push(OutboundMessage::Part(vec!["c".to_string()]));
+    queue.rejoin(vec!["a".to_string(), "b".to_string()]);
+
+    assert_eq!(
+        queue.drain_ready(now),
+        vec!["JOIN #a,#b".to_string(), "PRIVMSG #a :hi".to_string()]
+    );
+    assert!(queue.is_empty());
+
+    queue.rejoin(vec![]);
+    assert!(queue.is_empty());
+}
+
+fn pool_config() -> ConnectionConfig {
+    return ConnectionConfig::new("ws://127.0.0.1:1", "bot", "bot", "id", "secret", "redis://127.0.0.1:1");
+}
+
+#[tokio::test]
+async fn pool_sharding_test() {
+    let channels = vec!["#A".to_string(), "b".to_string(), "c".to_string(), "a".to_string()];
+    let (pool, _events) = ConnectionPool::new(pool_config(), DatabaseConnection::Disconnected, channels, 2);
+    let pool = Mutex::new(pool);
+
+    assert_eq!(pool.lock().await.channels(), vec!["a", "b", "c"]);
+    assert_eq!(pool.lock().await.loads(), vec![(0, 2), (1, 1)]);
+
+    pool.lock().await.join("d").unwrap();
+    pool.lock().await.join("e").unwrap();
+    assert_eq!(pool.lock().await.loads(), vec![(0, 2), (1, 2), (2, 1)]);
+
+    let closing = pool.lock().await.part("a").unwrap();
+    assert_eq!(closing.len(), 1);
+    // the channels of the closing connection are only joined again after it closed
+    assert_eq!(pool.lock().await.loads(), vec![(1, 2), (2, 1)]);
+    move_channels(&pool, closing).await.unwrap();
+    assert_eq!(pool.lock().await.channels(), vec!["b", "c", "d", "e"]);
+
+    let closing = pool.lock().await.part("b").unwrap();
+    assert!(closing.is_empty());
+    assert_eq!(pool.lock().await.channels(), vec!["c", "d", "e"]);
+    assert_eq!(pool.lock().await.loads().len(), 2);
+
+    let closing = pool.lock().await.set_channels(vec!["e".to_string()]).unwrap();
+    move_channels(&pool, closing).await.unwrap();
+    assert_eq!(pool.lock().await.channels(), vec!["e"]);
+    assert_eq!(pool.lock().await.loads().len(), 1);
+}
+
+#[tokio::test]
+async fn channel_event_test() {
+    let (pool, _events) = ConnectionPool::new(pool_config(), DatabaseConnection::Disconnected, vec![], 2);
+    let pool = Mutex::new(pool);
+
+    let join: ChannelEvent = serde_json::from_str(r#"{"type":"join","channel_id":1,"name":"Foo"}"#).unwrap();
+    apply_channel_event(&pool, &join).await.unwrap();
+    assert_eq!(pool.lock().await.channels(), vec!["foo"]);
+
+    let part = ChannelEvent::Part {
+        channel_id: 1,
+        name: "foo".to_string(),
+    };
+    apply_channel_event(&pool, &part).await.unwrap();
+    assert!(pool.lock().await.channels().is_empty());
+    assert_eq!(pool.lock().await.loads().len(), 1);
+}